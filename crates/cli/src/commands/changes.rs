@@ -0,0 +1,337 @@
+//! File change history commands.
+//!
+//! Every set of AI-proposed files that is written is recorded with each
+//! file's previous content:
+//! - `changes`: List past batches of file changes, or show one with diffs
+//! - `undo`: Restore the files of the last batch made from this directory
+//!   (or a given one)
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use cherry2k_core::config::Config;
+use cherry2k_storage::Database;
+use cherry2k_storage::file_change::{
+    StoredChangeBatch, StoredFileChange, get_change_batch, last_change_batch, list_change_batches,
+    mark_batch_undone,
+};
+use colored::Colorize;
+
+use cherry2k::confirm::{ConfirmResult, confirm};
+use cherry2k::files::{WriteTransaction, generate_diff};
+
+/// List recorded batches of file changes, most recent first.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `limit` - Maximum number of batches to show
+pub async fn list(db: &Database, limit: usize) -> Result<()> {
+    let batches = list_change_batches(db, limit)
+        .await
+        .context("Failed to list file changes")?;
+
+    if batches.is_empty() {
+        println!("No file changes recorded yet.");
+        return Ok(());
+    }
+
+    println!("{:<6} {:<17} {:<7} Status", "ID", "When", "Files");
+    println!("{}", "-".repeat(70));
+
+    for batch in &batches {
+        let status = match batch.undone_at {
+            Some(at) => format!("undone {}", at.format("%Y-%m-%d %H:%M")),
+            None => "applied".to_string(),
+        };
+        println!(
+            "{:<6} {:<17} {:<7} {}",
+            batch.id,
+            batch.created_at.format("%Y-%m-%d %H:%M"),
+            batch.changes.len(),
+            status
+        );
+        for change in &batch.changes {
+            let kind = if change.created_file() { "A" } else { "M" };
+            println!(
+                "         {} {}",
+                kind,
+                display_path(&change.path, &batch.working_dir)
+            );
+        }
+    }
+
+    println!();
+    println!("Show diffs with `cherry2k changes <ID>`, revert with `cherry2k undo [ID]`.");
+    Ok(())
+}
+
+/// Show one batch of file changes with diffs.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `id` - The batch ID
+pub async fn show(db: &Database, id: i64) -> Result<()> {
+    let Some(batch) = get_change_batch(db, id)
+        .await
+        .context("Failed to get file changes")?
+    else {
+        bail!("File changes not found: {}", id);
+    };
+
+    println!(
+        "Changes #{} from {}",
+        batch.id,
+        batch.created_at.format("%Y-%m-%d %H:%M")
+    );
+    if let Some(session_id) = &batch.session_id {
+        println!("Session: {}", session_id);
+    }
+    println!("Directory: {}", batch.working_dir);
+    if let Some(at) = batch.undone_at {
+        println!("Undone: {}", at.format("%Y-%m-%d %H:%M"));
+    }
+
+    for change in &batch.changes {
+        println!();
+        let label = display_path(&change.path, &batch.working_dir);
+        let previous = change.previous_content.as_deref().unwrap_or_default();
+        println!("{}", generate_diff(previous, &change.new_content, &label));
+    }
+    Ok(())
+}
+
+/// Restore the files changed by a batch.
+///
+/// Files edited since the batch was written are not overwritten unless
+/// `force` is set. Files the batch created are removed. The restore is
+/// all or nothing.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration (safety settings)
+/// * `db` - The database connection
+/// * `id` - The batch to undo, or `None` for the most recent one made from
+///   `working_dir` or a parent directory
+/// * `force` - Overwrite files that changed since the batch was written
+/// * `working_dir` - The current working directory
+pub async fn undo(
+    config: &Config,
+    db: &Database,
+    id: Option<i64>,
+    force: bool,
+    working_dir: &Path,
+) -> Result<()> {
+    let batch = match id {
+        Some(id) => get_change_batch(db, id)
+            .await
+            .context("Failed to get file changes")?
+            .with_context(|| format!("File changes not found: {}", id))?,
+        None => match last_change_batch(db, working_dir)
+            .await
+            .context("Failed to get file changes")?
+        {
+            Some(batch) => batch,
+            None => bail!(
+                "No file changes to undo in this directory. \
+                 Undo changes made elsewhere by ID (see `cherry2k changes`)."
+            ),
+        },
+    };
+    if batch.undone_at.is_some() {
+        bail!("Changes #{} were already undone", batch.id);
+    }
+    if !working_dir.starts_with(&batch.working_dir) {
+        println!("Note: made from {}", batch.working_dir);
+    }
+
+    let conflicts = find_conflicts(&batch);
+    if !conflicts.is_empty() {
+        println!(
+            "{} These files changed since #{} was written:",
+            "Warning:".yellow(),
+            batch.id
+        );
+        for (change, reason) in &conflicts {
+            println!(
+                "  {} ({})",
+                display_path(&change.path, &batch.working_dir),
+                reason
+            );
+        }
+        if !force {
+            bail!("Not undoing; re-run with --force to overwrite them");
+        }
+    }
+
+    println!(
+        "Undoing changes #{} from {}:",
+        batch.id,
+        batch.created_at.format("%Y-%m-%d %H:%M")
+    );
+    for change in &batch.changes {
+        let label = display_path(&change.path, &batch.working_dir);
+        match &change.previous_content {
+            Some(previous) => {
+                let current = fs::read_to_string(&change.path).unwrap_or_default();
+                println!();
+                println!("{}", generate_diff(&current, previous, &label));
+            }
+            None => println!("{} {}", "Remove:".red(), label),
+        }
+    }
+    println!();
+
+    if config.safety.confirm_file_writes
+        && !matches!(confirm("Undo these changes?", false)?, ConfirmResult::Yes)
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut transaction = WriteTransaction::new();
+    for change in &batch.changes {
+        let path = Path::new(&change.path);
+        match &change.previous_content {
+            Some(previous) => transaction.stage(path, previous)?,
+            None => transaction.stage_removal(path)?,
+        }
+    }
+    transaction
+        .commit()
+        .context("Failed to undo file changes")?;
+
+    mark_batch_undone(db, batch.id)
+        .await
+        .context("Failed to record undo")?;
+    println!(
+        "{} {} file(s) from changes #{}",
+        "Restored:".green(),
+        batch.changes.len(),
+        batch.id
+    );
+    Ok(())
+}
+
+/// Files whose current content isn't what the batch wrote.
+fn find_conflicts(batch: &StoredChangeBatch) -> Vec<(&StoredFileChange, &'static str)> {
+    batch
+        .changes
+        .iter()
+        .filter_map(|change| {
+            let reason = match fs::read_to_string(&change.path) {
+                Ok(current) if current == change.new_content => return None,
+                Ok(_) => "modified",
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => "deleted",
+                Err(_) => "unreadable",
+            };
+            Some((change, reason))
+        })
+        .collect()
+}
+
+/// Show a path relative to the directory the changes were made from.
+fn display_path(path: &str, working_dir: &str) -> String {
+    Path::new(path)
+        .strip_prefix(working_dir)
+        .map(|relative| relative.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cherry2k_storage::file_change::{NewChangeBatch, NewFileChange, record_change_batch};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn batch_for(changes: Vec<StoredFileChange>) -> StoredChangeBatch {
+        StoredChangeBatch {
+            id: 1,
+            session_id: None,
+            message_id: None,
+            working_dir: "/project".to_string(),
+            created_at: Utc::now(),
+            undone_at: None,
+            changes,
+        }
+    }
+
+    fn change(path: &Path, new_content: &str) -> StoredFileChange {
+        StoredFileChange {
+            id: 1,
+            path: path.display().to_string(),
+            previous_content: Some("old".to_string()),
+            new_content: new_content.to_string(),
+        }
+    }
+
+    #[test]
+    fn detects_modified_and_deleted_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let unchanged = temp_dir.path().join("unchanged.txt");
+        let modified = temp_dir.path().join("modified.txt");
+        let deleted = temp_dir.path().join("deleted.txt");
+        fs::write(&unchanged, "written").unwrap();
+        fs::write(&modified, "edited by hand").unwrap();
+
+        let batch = batch_for(vec![
+            change(&unchanged, "written"),
+            change(&modified, "written"),
+            change(&deleted, "written"),
+        ]);
+        let reasons: Vec<&str> = find_conflicts(&batch)
+            .into_iter()
+            .map(|(_, reason)| reason)
+            .collect();
+
+        assert_eq!(reasons, ["modified", "deleted"]);
+    }
+
+    #[tokio::test]
+    async fn undo_ignores_changes_from_other_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_a = temp_dir.path().join("a");
+        let project_b = temp_dir.path().join("b");
+        fs::create_dir_all(&project_a).unwrap();
+        fs::create_dir_all(&project_b).unwrap();
+        let file = project_a.join("main.rs");
+        fs::write(&file, "written").unwrap();
+
+        let db = Database::open_at(temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        record_change_batch(
+            &db,
+            NewChangeBatch {
+                session_id: None,
+                message_id: None,
+                working_dir: project_a.display().to_string(),
+                changes: vec![NewFileChange {
+                    path: file.display().to_string(),
+                    previous_content: Some("old".to_string()),
+                    new_content: "written".to_string(),
+                }],
+            },
+        )
+        .await
+        .unwrap();
+
+        let result = undo(&Config::default(), &db, None, false, &project_b).await;
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "written");
+        assert!(last_change_batch(&db, &project_a).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn display_path_is_relative_to_working_dir() {
+        assert_eq!(display_path("/project/src/a.rs", "/project"), "src/a.rs");
+        assert_eq!(
+            display_path("/elsewhere/a.rs", "/project"),
+            "/elsewhere/a.rs"
+        );
+    }
+}
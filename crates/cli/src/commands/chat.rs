@@ -7,38 +7,47 @@
 //! Sessions are automatically managed per-directory. Conversation history is
 //! loaded and sent to the provider for context. Messages are saved after each
 //! exchange.
+//!
+//! The last turn can be replaced instead of appended to: `retry` regenerates
+//! the last answer (optionally with another provider or model) and
+//! `edit-last` edits the last question in `$EDITOR` and resends it.
 
 /// Probability threshold for session cleanup (26/256 ≈ 10.2%).
 ///
 /// On each chat completion, we roll a random u8. If it's below this threshold,
-/// we trigger cleanup of old sessions. This spreads the cleanup work across
-/// many requests rather than doing it all at once.
+/// we prune sessions according to the configured retention policy. This spreads
+/// the cleanup work across many requests rather than doing it all at once.
 const CLEANUP_PROBABILITY_THRESHOLD: u8 = 26;
 
 use std::collections::HashMap;
+use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use cherry2k_core::config::Config;
-use cherry2k_core::provider::Role;
-use cherry2k_core::{CompletionRequest, Message, ProviderFactory, command_mode_system_prompt};
-use cherry2k_storage::message::save_message;
-use cherry2k_storage::session::{cleanup_old_sessions, get_or_create_session};
-use cherry2k_storage::{Database, prepare_context};
+use cherry2k_core::config::{Config, PROFILE_ENV_VAR};
+use cherry2k_core::provider::{AiProvider, Role, Summarizer, Tokenizer};
+use cherry2k_core::{CompletionRequest, Message, ProviderFactory};
+use cherry2k_storage::file_change::{NewChangeBatch, NewFileChange, record_change_batch};
+use cherry2k_storage::message::{last_user_message, replace_answer, save_message_from};
+use cherry2k_storage::retention::prune_sessions;
+use cherry2k_storage::session::{get_or_create_session, list_sessions};
+use cherry2k_storage::title::generate_session_title;
+use cherry2k_storage::{
+    Database, RetentionPolicy, StoredMessage, prepare_context, prepare_context_before,
+    summarize_session,
+};
 use serde::Deserialize;
 use tokio_stream::StreamExt;
 
-use cherry2k::confirm::{ConfirmResult, check_blocked_patterns, confirm_command, edit_command};
-use cherry2k::execute::{display_exit_status, execute_command};
 use cherry2k::files;
 use cherry2k::intent::{Intent, detect_intent};
-use cherry2k::output::{
-    ResponseSpinner, StreamWriter, display_provider_error, display_suggested_command,
-};
+use cherry2k::output::{ResponseSpinner, StreamWriter, display_provider_error};
+use cherry2k::persona::load_persona;
 use cherry2k::signal::setup_cancellation;
 use colored::Colorize;
-use tokio_util::sync::CancellationToken;
+
+use super::pipeline::CommandPipeline;
 
 /// Shell context passed from zsh integration.
 #[derive(Debug, Deserialize)]
@@ -81,6 +90,11 @@ struct HistoryEntry {
 /// * `message` - The user's message to send to the AI
 /// * `_plain` - If true, skip markdown rendering (currently unused, for future enhancement)
 /// * `context_file` - Optional path to JSON file with shell context (from zsh integration)
+/// * `inject_file` - Optional path the zsh widget reads back to place a command on the
+///   prompt line (enables the insert option for suggested commands)
+/// * `insert_commands` - If true (and `inject_file` is set), suggested commands are placed
+///   on the prompt line without asking instead of being executed
+/// * `persona` - Persona to answer as, or `None` for the configured default
 ///
 /// # Errors
 ///
@@ -90,11 +104,15 @@ struct HistoryEntry {
 /// - Network errors occur during streaming
 /// - Database operations fail
 /// - Context file cannot be read or parsed (if provided)
+/// - The persona cannot be found
 pub async fn run(
     config: &Config,
     message: &str,
     _plain: bool,
     context_file: Option<&Path>,
+    inject_file: Option<&Path>,
+    insert_commands: bool,
+    persona: Option<&str>,
 ) -> Result<()> {
     // TODO(Phase 5): Use _plain flag to disable markdown rendering
 
@@ -137,24 +155,18 @@ pub async fn run(
 
     tracing::debug!("Using session {} in {}", session_id, working_dir.display());
 
+    // Resolve the persona before anything is saved, so a typo costs nothing
+    let system_prompt = system_prompt(config, persona, &working_dir)?;
+
     // Create provider factory from config
     let factory = ProviderFactory::from_config(config)
         .map_err(|e| anyhow::anyhow!("{}", e))
         .context("Failed to initialize providers")?;
-
-    // Check for in-session provider override
-    let active_provider_name = super::provider::get_active_provider()
-        .filter(|name| factory.contains(name))
-        .unwrap_or_else(|| factory.default_provider_name().to_string());
-
-    let provider = factory
-        .get(&active_provider_name)
-        .ok_or_else(|| anyhow::anyhow!("Provider '{}' not available", active_provider_name))?;
-
-    tracing::debug!("Using provider: {}", provider.provider_id());
+    let provider = select_provider(&factory, None)?;
+    let summarizer = factory.summarizer(provider);
 
     // Load conversation history
-    let context = prepare_context(&db, &session_id, provider)
+    let context = prepare_context(&db, &session_id, provider, None, summarizer)
         .await
         .context("Failed to load conversation history")?;
 
@@ -162,6 +174,7 @@ pub async fn run(
     if context.was_summarized {
         println!("(context summarized)");
     }
+    let summarize_after = context.needs_summary;
 
     // Parse message for command mode markers
     let user_message = message.trim();
@@ -177,6 +190,278 @@ pub async fn run(
     // Check for question mode marker (? suffix)
     let force_question_mode = actual_message.ends_with('?') && !force_command_mode;
 
+    // Save user message before sending request (use actual_message for cleaner history)
+    save_message_from(
+        &db,
+        &session_id,
+        Role::User,
+        actual_message,
+        Some(token_count(provider.tokenizer(None), actual_message)),
+        provider.provider_id(),
+    )
+    .await
+    .context("Failed to save message")?;
+
+    respond(Turn {
+        config,
+        db: &db,
+        session_id: &session_id,
+        provider,
+        model: None,
+        summarizer,
+        system_prompt,
+        history: context.messages,
+        summarize_after,
+        message: actual_message,
+        force_command_mode,
+        force_question_mode,
+        inject_file,
+        insert_commands,
+        replacing: None,
+    })
+    .await
+}
+
+/// Regenerate the last answer in the current directory's session.
+///
+/// The previous answer (and anything after the last question) is replaced
+/// rather than kept in the conversation, once the new answer is saved.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `provider_name` - Provider to answer with instead of the active one
+/// * `model` - Model to answer with instead of the provider's configured one
+/// * `persona` - Persona to answer as, or `None` for the configured default
+pub async fn retry(
+    config: &Config,
+    provider_name: Option<&str>,
+    model: Option<&str>,
+    persona: Option<&str>,
+) -> Result<()> {
+    let factory = ProviderFactory::from_config(config)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .context("Failed to initialize providers")?;
+    let provider = select_provider(&factory, provider_name)?;
+
+    let db = Database::open()
+        .await
+        .context("Failed to open session database")?;
+    let question = last_question(&db).await?;
+
+    let summarizer = factory.summarizer(provider);
+    resend(config, &db, &question, persona, provider, model, summarizer).await
+}
+
+/// Edit the last question in `$EDITOR` and resend it.
+///
+/// The question is replaced in place and everything after it is removed
+/// once the new answer is saved.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `persona` - Persona to answer as, or `None` for the configured default
+pub async fn edit_last(config: &Config, persona: Option<&str>) -> Result<()> {
+    let factory = ProviderFactory::from_config(config)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .context("Failed to initialize providers")?;
+    let provider = select_provider(&factory, None)?;
+
+    let db = Database::open()
+        .await
+        .context("Failed to open session database")?;
+    let question = last_question(&db).await?;
+
+    let edited = edit::edit(&question.content).context("Failed to open editor")?;
+    let edited = edited.trim();
+    if edited.is_empty() {
+        println!("Empty message, nothing sent.");
+        return Ok(());
+    }
+
+    let question = StoredMessage {
+        content: edited.to_string(),
+        ..question
+    };
+
+    let summarizer = factory.summarizer(provider);
+    resend(config, &db, &question, persona, provider, None, summarizer).await
+}
+
+/// Pick the provider to answer with.
+///
+/// An explicitly requested provider must be configured; otherwise the
+/// in-session override (from `cherry2k provider`) or the default is used.
+/// Its API key is read now if it comes from a command, file or keyring.
+pub(crate) fn select_provider<'f>(
+    factory: &'f ProviderFactory,
+    requested: Option<&str>,
+) -> Result<&'f dyn AiProvider> {
+    let name = selected_provider_name(factory, requested)?;
+    let provider = factory
+        .get(&name)
+        .with_context(|| format!("Failed to initialize provider '{}'", name))?
+        .ok_or_else(|| anyhow::anyhow!("Provider '{}' not available", name))?;
+
+    tracing::debug!("Using provider: {}", provider.provider_id());
+    Ok(provider)
+}
+
+/// Name of the provider [`select_provider`] picks, without reading its key.
+pub(crate) fn selected_provider_name(
+    factory: &ProviderFactory,
+    requested: Option<&str>,
+) -> Result<String> {
+    if let Some(name) = requested {
+        if !factory.contains(name) {
+            anyhow::bail!(
+                "Provider '{}' not available (configured: {})",
+                name,
+                factory.list().join(", ")
+            );
+        }
+        return Ok(name.to_string());
+    }
+
+    // Check for in-session provider override
+    Ok(super::provider::get_active_provider()
+        .filter(|name| factory.contains(name))
+        .unwrap_or_else(|| factory.default_provider_name().to_string()))
+}
+
+/// Count the tokens of a message for storage.
+fn token_count(tokenizer: Tokenizer, text: &str) -> i64 {
+    i64::try_from(tokenizer.count(text)).unwrap_or(i64::MAX)
+}
+
+/// Render the system prompt of a persona, falling back to the configured one.
+fn system_prompt(config: &Config, persona: Option<&str>, cwd: &Path) -> Result<String> {
+    let name = persona.or(config.general.persona.as_deref());
+    let persona = load_persona(name, cwd).context("Failed to load persona")?;
+    tracing::debug!("Using persona {} ({:?})", persona.name, persona.source);
+    Ok(persona.render(cwd))
+}
+
+/// Find the current directory's most recent session and its last question.
+async fn last_question(db: &Database) -> Result<StoredMessage> {
+    let working_dir = std::env::current_dir().context("Failed to get current directory")?;
+    let session = list_sessions(db, &working_dir, 1)
+        .await
+        .context("Failed to list sessions")?
+        .into_iter()
+        .next()
+        .context("No session found in this directory")?;
+
+    let question = last_user_message(db, &session.id)
+        .await
+        .context("Failed to load last message")?
+        .with_context(|| format!("Session {} has no message to resend", session.id))?;
+
+    Ok(question)
+}
+
+/// Answer an already saved user message of a session again.
+///
+/// The history sent with it is everything before the question. Messages
+/// after the question are replaced by the new answer when it is saved, and
+/// so is the stored question if its content differs. The persona isn't
+/// stored, so it is rendered from `persona` or the configured default.
+async fn resend(
+    config: &Config,
+    db: &Database,
+    question: &StoredMessage,
+    persona: Option<&str>,
+    provider: &dyn AiProvider,
+    model: Option<&str>,
+    summarizer: Summarizer<'_>,
+) -> Result<()> {
+    let session_id = question.session_id.as_str();
+
+    // Resolve the persona first, so a typo costs nothing
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let system_prompt = system_prompt(config, persona, &cwd)?;
+
+    let context = prepare_context_before(db, session_id, question.id, provider, model, summarizer)
+        .await
+        .context("Failed to load conversation history")?;
+
+    if context.was_summarized {
+        println!("(context summarized)");
+    }
+
+    respond(Turn {
+        config,
+        db,
+        session_id,
+        provider,
+        model,
+        summarizer,
+        system_prompt,
+        history: context.messages,
+        summarize_after: context.needs_summary,
+        force_question_mode: question.content.ends_with('?'),
+        message: &question.content,
+        force_command_mode: false,
+        inject_file: None,
+        insert_commands: false,
+        replacing: Some(question.id),
+    })
+    .await
+}
+
+/// A user message ready to be answered.
+struct Turn<'a> {
+    config: &'a Config,
+    db: &'a Database,
+    session_id: &'a str,
+    provider: &'a dyn AiProvider,
+    /// Model override for this turn
+    model: Option<&'a str>,
+    /// Provider and model for summaries and titles
+    summarizer: Summarizer<'a>,
+    /// Rendered persona prompt sent ahead of everything else
+    system_prompt: String,
+    /// Conversation history before this message
+    history: Vec<Message>,
+    /// Summarize the session once the answer is delivered
+    summarize_after: bool,
+    /// The user message (already saved, without mode markers)
+    message: &'a str,
+    force_command_mode: bool,
+    force_question_mode: bool,
+    inject_file: Option<&'a Path>,
+    insert_commands: bool,
+    /// Earlier question answered again, whose later messages the answer replaces
+    replacing: Option<i64>,
+}
+
+/// Send a user message with its history, stream the answer and act on it.
+///
+/// Saves the answer, titles new sessions in the background, and handles
+/// suggested commands and file proposals.
+async fn respond(turn: Turn<'_>) -> Result<()> {
+    let Turn {
+        config,
+        db,
+        session_id,
+        provider,
+        model,
+        summarizer,
+        system_prompt,
+        history,
+        summarize_after,
+        message: actual_message,
+        force_command_mode,
+        force_question_mode,
+        inject_file,
+        insert_commands,
+        replacing,
+    } = turn;
+
+    // A session with no history is getting its first exchange, which is what we title it from
+    let is_first_exchange = history.is_empty();
+
     // Detect and inject file references before sending to AI
     let cwd = std::env::current_dir().context("Failed to get current directory")?;
     let scope = files::ProjectScope::detect().context("Failed to detect project scope")?;
@@ -218,17 +503,28 @@ pub async fn run(
         )
     };
 
-    // Save user message before sending request (use actual_message for cleaner history)
-    save_message(&db, &session_id, Role::User, actual_message, None)
-        .await
-        .context("Failed to save message")?;
+    // Standing project instructions ride along as a system message, so they are never summarized
+    let instructions = match files::load_instructions(&cwd) {
+        Ok(instructions) => instructions,
+        Err(e) => {
+            eprintln!("Warning: Could not read project instructions: {}", e);
+            None
+        }
+    };
 
     // Build request with history + new message (using augmented version)
-    // Always include command mode system prompt - AI decides based on context
-    let request = CompletionRequest::new()
-        .with_message(Message::system(command_mode_system_prompt()))
-        .with_messages(context.messages)
+    // Always lead with the persona prompt (command mode by default) - AI decides based on context
+    let mut request = CompletionRequest::new().with_message(Message::system(system_prompt));
+    if let Some(instructions) = &instructions {
+        tracing::debug!("Using instructions from {}", instructions.path.display());
+        request = request.with_message(Message::system(instructions.to_system_prompt()));
+    }
+    request = request
+        .with_messages(history)
         .with_message(Message::user(&augmented_message));
+    if let Some(model) = model {
+        request = request.with_model(model);
+    }
 
     tracing::debug!(
         "Request mode: force_command={}, force_question={}",
@@ -284,9 +580,9 @@ pub async fn run(
             _ = cancel_token.cancelled() => {
                 writer.flush()?;
                 println!("\n\nCancelled by user.");
-                // Save partial response if we got any
-                if !collected_response.is_empty() {
-                    let _ = save_message(&db, &session_id, Role::Assistant, &collected_response, None).await;
+                // Save partial response if we got any, unless it would replace a full answer
+                if !collected_response.is_empty() && replacing.is_none() {
+                    let _ = save_message_from(db, session_id, Role::Assistant, &collected_response, None, provider.provider_id()).await;
                 }
                 return Ok(());
             }
@@ -297,123 +593,255 @@ pub async fn run(
     writer.flush()?;
     println!(); // Blank line after response
 
-    // Save assistant response
-    save_message(&db, &session_id, Role::Assistant, &collected_response, None)
-        .await
-        .context("Failed to save response")?;
+    // Save assistant response, with the provider's own count when it reports one
+    let response_tokens = provider
+        .last_usage()
+        .and_then(|usage| usage.completion_tokens)
+        .map(i64::from)
+        .unwrap_or_else(|| token_count(provider.tokenizer(model), &collected_response));
+    let response_id = match replacing {
+        Some(question_id) => {
+            replace_answer(
+                db,
+                session_id,
+                question_id,
+                Some(actual_message),
+                &collected_response,
+                Some(response_tokens),
+                provider.provider_id(),
+            )
+            .await
+        }
+        None => {
+            save_message_from(
+                db,
+                session_id,
+                Role::Assistant,
+                &collected_response,
+                Some(response_tokens),
+                provider.provider_id(),
+            )
+            .await
+        }
+    }
+    .context("Failed to save response")?;
+
+    // The answer is out; title a new session and get the summary ready for the next turn
+    if is_first_exchange || summarize_after {
+        schedule_summary(
+            db,
+            session_id,
+            provider,
+            model,
+            summarizer,
+            config.active_profile.as_deref(),
+            is_first_exchange,
+        )
+        .await;
+    }
 
     // Detect if response contains a command suggestion (skip if force_question_mode)
     // Intent::Question means response was just an explanation, already displayed
     if !force_question_mode && let Intent::Command(detected) = detect_intent(&collected_response) {
-        // Check for blocked dangerous patterns first
-        if let Some(pattern) =
-            check_blocked_patterns(&detected.command, &config.safety.blocked_patterns)
-        {
-            println!();
-            println!(
-                "{} Command matches dangerous pattern: {}",
-                "BLOCKED:".red(),
-                pattern
-            );
-            println!("This command has been blocked for safety reasons.");
-            return Ok(());
-        }
-
-        // Display the command with syntax highlighting
-        display_suggested_command(&detected.command, detected.context.as_deref());
-
-        // Check if confirmation is required (respect config)
-        let mut command_to_run = detected.command.clone();
-
-        if config.safety.confirm_commands {
-            // Ask for confirmation
-            loop {
-                match confirm_command(&command_to_run)? {
-                    ConfirmResult::Yes => {
-                        // Re-check blocked patterns after edit
-                        if let Some(pattern) =
-                            check_blocked_patterns(&command_to_run, &config.safety.blocked_patterns)
-                        {
-                            println!();
-                            println!(
-                                "{} Command matches dangerous pattern: {}",
-                                "BLOCKED:".red(),
-                                pattern
-                            );
-                            println!("This command has been blocked for safety reasons.");
-                            return Ok(());
-                        }
-
-                        run_command(&command_to_run, &cancel_token).await?;
-                        break;
-                    }
-                    ConfirmResult::No => {
-                        println!("Command cancelled.");
-                        break;
-                    }
-                    ConfirmResult::Edit => {
-                        command_to_run = edit_command(&command_to_run)?;
-                        // Re-display the edited command
-                        display_suggested_command(&command_to_run, None);
-                        // Loop continues to re-confirm
-                    }
-                }
-            }
-        } else {
-            // Auto-execute without confirmation (confirm_commands = false)
-            run_command(&command_to_run, &cancel_token).await?;
-        }
+        let pipeline = CommandPipeline {
+            config,
+            db,
+            session_id: Some(session_id),
+            inject_file,
+            insert_commands,
+        };
+        pipeline
+            .handle(
+                &detected.command,
+                detected.context.as_deref(),
+                &cancel_token,
+            )
+            .await?;
     }
 
     // Check for file write proposals in the response (after command handling)
     if !force_question_mode {
-        let proposals = files::extract_file_proposals(&collected_response, &cwd);
+        let mut proposals = files::extract_file_proposals(&collected_response, &cwd);
+        for edit in files::extract_file_edits(&collected_response, &cwd) {
+            match edit.apply() {
+                Ok(proposal) => proposals.push(proposal),
+                Err(e) => {
+                    eprintln!("{} {:#}", "Error:".red(), e);
+                    eprintln!("The file was left unchanged.");
+                }
+            }
+        }
         if !proposals.is_empty() {
             tracing::info!("AI proposed {} file change(s)", proposals.len());
-            process_file_proposals(&proposals, &scope, config).await?;
+            let origin = ChangeOrigin {
+                db,
+                session_id,
+                message_id: response_id,
+                working_dir: &cwd,
+            };
+            process_file_proposals(&proposals, &scope, config, origin).await?;
         }
     }
 
     // Probabilistic cleanup (~10% of the time)
     // Using random to avoid timing-based patterns
     if rand::random::<u8>() < CLEANUP_PROBABILITY_THRESHOLD
-        && let Ok(count) = cleanup_old_sessions(&db).await
-        && count > 0
+        && let Err(e) = prune_sessions(db, &RetentionPolicy::from(&config.retention)).await
     {
-        tracing::debug!("Cleaned up {} old sessions", count);
+        tracing::warn!("Failed to prune old sessions: {}", e);
     }
 
     Ok(())
 }
 
-/// Execute a command with signal handling and display results.
+/// Summarize a session in the background, titling it first if asked to.
 ///
-/// Extracted helper to reduce duplication in the confirmation and auto-execute paths.
-async fn run_command(command: &str, cancel_token: &CancellationToken) -> Result<()> {
-    println!(); // Blank line before execution
+/// Spawns a detached `cherry2k summarize` so the shell gets its prompt back
+/// right away. If that fails, the work is done before returning.
+/// The child gets the same profile, even one selected with `--profile`.
+async fn schedule_summary(
+    db: &Database,
+    session_id: &str,
+    provider: &dyn AiProvider,
+    model: Option<&str>,
+    summarizer: Summarizer<'_>,
+    profile: Option<&str>,
+    title: bool,
+) {
+    let spawned = std::env::current_exe().and_then(|exe| {
+        let mut command = std::process::Command::new(exe);
+        command
+            .args([
+                "summarize",
+                session_id,
+                "--provider",
+                provider.provider_id(),
+            ])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        if let Some(model) = model {
+            command.args(["--model", model]);
+        }
+        if title {
+            command.arg("--title");
+        }
+        if let Some(profile) = profile {
+            command.env(PROFILE_ENV_VAR, profile);
+        }
+        // Own process group, so Ctrl+C at the prompt doesn't stop it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        command.spawn()
+    });
+
+    match spawned {
+        Ok(child) => tracing::debug!(
+            "Summarizing session {} in process {}",
+            session_id,
+            child.id()
+        ),
+        Err(e) => {
+            tracing::debug!(
+                "Could not start background summarizer ({}), summarizing now",
+                e
+            );
+            if title && let Err(e) = generate_session_title(db, session_id, summarizer).await {
+                tracing::warn!("Failed to generate session title: {}", e);
+            }
+            if let Err(e) = summarize_session(db, session_id, provider, model, summarizer).await {
+                tracing::warn!("Failed to summarize session: {}", e);
+            }
+        }
+    }
+}
 
-    // Execute with signal handling
-    let result = execute_command(command, Some(cancel_token.clone())).await?;
+/// Summarize a session's older messages if its context is over threshold.
+///
+/// Runs detached after a chat answer (see [`schedule_summary`]), so nothing
+/// is printed.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `session_id` - The session to summarize
+/// * `provider_name` - Provider to summarize with
+/// * `model` - Model whose context window sets the threshold
+/// * `title` - Title the session first if it has no title yet
+pub async fn summarize(
+    config: &Config,
+    session_id: &str,
+    provider_name: Option<&str>,
+    model: Option<&str>,
+    title: bool,
+) -> Result<()> {
+    let factory = ProviderFactory::from_config(config)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .context("Failed to initialize providers")?;
+    let provider = select_provider(&factory, provider_name)?;
 
-    // Display exit status
-    display_exit_status(result.status);
+    let db = Database::open()
+        .await
+        .context("Failed to open session database")?;
 
-    if result.was_cancelled {
-        println!("Command interrupted.");
+    let summarizer = factory.summarizer(provider);
+    if title {
+        // A missing title shouldn't keep the summary from being written
+        match generate_session_title(&db, session_id, summarizer).await {
+            Ok(title) => tracing::debug!("Session {} titled: {:?}", session_id, title),
+            Err(e) => tracing::warn!("Failed to generate session title: {}", e),
+        }
     }
 
+    let summarized = summarize_session(&db, session_id, provider, model, summarizer)
+        .await
+        .context("Failed to summarize session")?;
+    tracing::debug!("Session {} summarized: {}", session_id, summarized);
+
     Ok(())
 }
 
+/// Read a file's current text before it is overwritten.
+///
+/// # Returns
+/// The content, `None` if the file doesn't exist yet, or an error if it
+/// can't be read as text
+fn snapshot_file(path: &Path) -> std::io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Where a set of file proposals came from, for recording the changes.
+struct ChangeOrigin<'a> {
+    db: &'a Database,
+    session_id: &'a str,
+    /// The assistant message that proposed the changes
+    message_id: i64,
+    working_dir: &'a Path,
+}
+
 /// Process file write proposals from AI response.
 ///
-/// Validates each proposal for safety, displays diffs, and writes files after user approval.
+/// Validates each proposal for safety, displays diffs, and writes files after
+/// user approval. Several files are written together: if one write fails,
+/// none of them change. The previous content of written files is recorded so
+/// `cherry2k undo` can restore it.
 async fn process_file_proposals(
     proposals: &[files::FileProposal],
     scope: &files::ProjectScope,
     config: &Config,
+    origin: ChangeOrigin<'_>,
 ) -> Result<()> {
-    use files::{validate_write_path, ValidationResult, write_file_with_approval, WriteResult};
+    use files::{
+        ValidationResult, WriteResult, validate_write_path, write_file_with_approval,
+        write_multiple_files,
+    };
 
     // Handle multiple files with summary
     if proposals.len() > 1 {
@@ -426,18 +854,15 @@ async fn process_file_proposals(
         println!();
     }
 
-    // Process each proposal
+    // Validate paths first
+    let mut writable = Vec::with_capacity(proposals.len());
     for proposal in proposals {
-        // Validate path first
         match validate_write_path(&proposal.path, scope) {
             ValidationResult::Ok => {
                 // Safe to proceed
             }
             ValidationResult::OutOfScope { path, root } => {
-                println!(
-                    "{} File is outside project scope",
-                    "Warning:".yellow()
-                );
+                println!("{} File is outside project scope", "Warning:".yellow());
                 println!("  File: {}", path);
                 println!("  Project root: {}", root);
                 println!("  Proceeding with extra caution...");
@@ -454,26 +879,138 @@ async fn process_file_proposals(
                 continue; // Skip this proposal entirely
             }
         }
+        writable.push((proposal.path.clone(), proposal.content.clone()));
+    }
 
-        // Show diff and get approval
-        let result = write_file_with_approval(
-            &proposal.path,
-            &proposal.content,
-            !config.safety.confirm_file_writes, // auto_write if confirm disabled
-        )?;
+    // Snapshot the current content before anything is written
+    let snapshots: Vec<_> = writable
+        .iter()
+        .map(|(path, _)| snapshot_file(path))
+        .collect();
+
+    // Show diffs and get approval
+    let auto_write = !config.safety.confirm_file_writes;
+    let results = match writable.as_slice() {
+        [] => return Ok(()),
+        [(path, content)] => vec![write_file_with_approval(path, content, auto_write)?],
+        _ => write_multiple_files(&writable, auto_write)?,
+    };
 
+    let mut changes = Vec::new();
+    for (((path, _), snapshot), result) in writable.iter().zip(snapshots).zip(results) {
         match result {
             WriteResult::Written { path } => {
                 println!("{} {}", "Wrote:".green(), path.display());
+                match (snapshot, fs::read_to_string(&path)) {
+                    (Ok(previous_content), Ok(new_content)) => changes.push(NewFileChange {
+                        path: path.display().to_string(),
+                        previous_content,
+                        new_content,
+                    }),
+                    _ => eprintln!(
+                        "{} {} is not text; this change can't be undone",
+                        "Warning:".yellow(),
+                        path.display()
+                    ),
+                }
             }
             WriteResult::Cancelled => {
-                println!("{} {}", "Skipped:".yellow(), proposal.path.display());
+                println!("{} {}", "Skipped:".yellow(), path.display());
             }
             WriteResult::Skipped => {
-                // No changes needed (already handled by write_file_with_approval)
+                // No changes needed (already reported while reviewing)
             }
         }
     }
 
+    if !changes.is_empty() {
+        let batch = NewChangeBatch {
+            session_id: Some(origin.session_id.to_string()),
+            message_id: Some(origin.message_id),
+            working_dir: origin.working_dir.display().to_string(),
+            changes,
+        };
+        match record_change_batch(origin.db, batch).await {
+            Ok(_) => println!("Undo with `cherry2k undo`"),
+            Err(e) => tracing::warn!("Failed to record file changes for undo: {}", e),
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cherry2k_core::provider::CompletionStream;
+    use cherry2k_core::{ConfigError, ProviderError};
+    use cherry2k_storage::message::{get_messages, save_message};
+    use cherry2k_storage::session::create_session;
+    use futures::future::BoxFuture;
+    use tempfile::TempDir;
+
+    /// A provider whose requests always fail.
+    struct FailingProvider;
+
+    impl AiProvider for FailingProvider {
+        fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> BoxFuture<'_, Result<CompletionStream, ProviderError>> {
+            Box::pin(async move { Err(ProviderError::RequestFailed("offline".to_string())) })
+        }
+
+        fn provider_id(&self) -> &'static str {
+            "failing"
+        }
+
+        fn validate_config(&self) -> Result<(), ConfigError> {
+            Ok(())
+        }
+
+        fn health_check(&self) -> BoxFuture<'_, Result<(), ProviderError>> {
+            Box::pin(async move { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_resend_keeps_previous_answer() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let session_id = create_session(&db, temp_dir.path()).await.unwrap();
+        save_message(&db, &session_id, Role::User, "Question", None)
+            .await
+            .unwrap();
+        save_message(&db, &session_id, Role::Assistant, "Old answer", None)
+            .await
+            .unwrap();
+        let question = last_user_message(&db, &session_id).await.unwrap().unwrap();
+        let edited = StoredMessage {
+            content: "Edited question".to_string(),
+            ..question
+        };
+
+        let provider = FailingProvider;
+        let result = resend(
+            &Config::default(),
+            &db,
+            &edited,
+            None,
+            &provider,
+            None,
+            Summarizer::new(&provider),
+        )
+        .await;
+
+        assert!(result.is_err());
+        let contents: Vec<String> = get_messages(&db, &session_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(contents, ["Question", "Old answer"]);
+    }
+}
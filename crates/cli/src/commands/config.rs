@@ -1,16 +1,36 @@
 //! Config command handler
 //!
-//! Displays the current configuration.
+//! Displays the current configuration, reads and changes settings, checks
+//! config files and manages trust in project config files.
+//!
+//! Everything except `run` and `get` works on the files directly, so a
+//! broken config can still be inspected and repaired.
 
-use anyhow::Result;
-use cherry2k_core::config::Config;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-/// Format API key status for display (never shows actual key).
-fn api_key_status(key: &Option<String>) -> &'static str {
-    if key.is_some() {
-        "configured"
-    } else {
-        "not set"
+use anyhow::{Context, Result, bail};
+use cherry2k::confirm::{ConfirmResult, confirm};
+use cherry2k_core::config::{
+    Config, PROJECT_CONFIG_FILE, TrustStore, config_schema, find_project_configs, get_config_path,
+    get_trust_store_path, set_config_value, validate_config_file, validate_config_str,
+};
+
+/// Format a retention limit for display (0 means no limit).
+fn limit_label(value: u64, unit: &str) -> String {
+    match (value, unit) {
+        (0, _) => "unlimited".to_string(),
+        (v, "") => v.to_string(),
+        (v, unit) => format!("{} {}", v, unit),
+    }
+}
+
+/// Format a context window override for display.
+fn context_window_label(window: Option<usize>, fallback: &str) -> String {
+    match window {
+        Some(tokens) => format!("{} tokens", tokens),
+        None => fallback.to_string(),
     }
 }
 
@@ -23,6 +43,18 @@ pub fn run(config: &Config) -> Result<()> {
     println!("[General]");
     println!("  Default provider: {}", config.general.default_provider);
     println!("  Log level: {}", config.general.log_level);
+    println!(
+        "  Profile: {}",
+        config.active_profile.as_deref().unwrap_or("none")
+    );
+    if !config.profiles.is_empty() {
+        let names: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        println!("  Profiles: {}", names.join(", "));
+    }
+    println!(
+        "  Persona: {}",
+        config.general.persona.as_deref().unwrap_or("default")
+    );
     println!();
 
     println!("[Safety]");
@@ -37,18 +69,60 @@ pub fn run(config: &Config) -> Result<()> {
     );
     println!();
 
+    println!("[Retention]");
+    println!(
+        "  Max age: {}",
+        limit_label(config.retention.max_age_days.into(), "days")
+    );
+    println!(
+        "  Max sessions per directory: {}",
+        limit_label(config.retention.max_sessions_per_dir.into(), "")
+    );
+    println!(
+        "  Max database size: {}",
+        limit_label(config.retention.max_db_size_mb, "MB")
+    );
+    println!();
+
+    println!("[Context]");
+    println!(
+        "  Summarizer provider: {}",
+        config
+            .context
+            .summarizer_provider
+            .as_deref()
+            .unwrap_or("active provider")
+    );
+    println!(
+        "  Summarizer model: {}",
+        config
+            .context
+            .summarizer_model
+            .as_deref()
+            .unwrap_or("provider default")
+    );
+    println!();
+
     if let Some(ref openai) = config.openai {
         println!("[OpenAI]");
         println!("  Base URL: {}", openai.base_url);
         println!("  Model: {}", openai.model);
-        println!("  API key: {}", api_key_status(&openai.api_key));
+        println!(
+            "  Context window: {}",
+            context_window_label(openai.context_window, "from model")
+        );
+        println!("  API key: {}", openai.api_key_source());
         println!();
     }
 
     if let Some(ref anthropic) = config.anthropic {
         println!("[Anthropic]");
         println!("  Model: {}", anthropic.model);
-        println!("  API key: {}", api_key_status(&anthropic.api_key));
+        println!(
+            "  Context window: {}",
+            context_window_label(anthropic.context_window, "from model")
+        );
+        println!("  API key: {}", anthropic.api_key_source());
         println!();
     }
 
@@ -56,8 +130,276 @@ pub fn run(config: &Config) -> Result<()> {
         println!("[Ollama]");
         println!("  Host: {}", ollama.host);
         println!("  Model: {}", ollama.model);
+        println!(
+            "  Context window: {}",
+            context_window_label(ollama.context_window, "Ollama default")
+        );
         println!();
     }
 
+    if !config.project_files.is_empty() {
+        println!("[Project files]");
+        for file in &config.project_files {
+            let status = if file.trusted { "trusted" } else { "untrusted" };
+            println!("  {} ({})", file.path.display(), status);
+            if !file.ignored.is_empty() {
+                println!("    Ignored: {}", file.ignored.join(", "));
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Trust project config files, or revoke trust.
+///
+/// Trust covers the files' current contents; a trusted file that changes
+/// must be trusted again.
+///
+/// # Arguments
+///
+/// * `path` - Config file or its directory, or `None` for every project
+///   file that applies to the current directory
+/// * `revoke` - Stop trusting the files instead
+pub fn trust(path: Option<&Path>, revoke: bool) -> Result<()> {
+    let files = match path {
+        Some(path) => vec![resolve_project_file(path)?],
+        None => {
+            let cwd = std::env::current_dir().context("Failed to get current directory")?;
+            find_project_configs(&cwd)
+        }
+    };
+    if files.is_empty() {
+        bail!("No {} found in this project", PROJECT_CONFIG_FILE);
+    }
+
+    let store_path = get_trust_store_path();
+    let mut store = TrustStore::load(&store_path)?;
+    for file in &files {
+        if revoke {
+            if store.revoke(file) {
+                println!("No longer trusted: {}", file.display());
+            } else {
+                println!("Not trusted: {}", file.display());
+            }
+        } else {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            store.trust(file, &content);
+            println!("Trusted: {}", file.display());
+        }
+    }
+    store.save(&store_path)?;
+    Ok(())
+}
+
+/// Resolve a trust argument to an absolute config file path.
+fn resolve_project_file(path: &Path) -> Result<PathBuf> {
+    let path = if path.is_dir() {
+        path.join(PROJECT_CONFIG_FILE)
+    } else {
+        path.to_path_buf()
+    };
+    path.canonicalize()
+        .with_context(|| format!("Failed to find {}", path.display()))
+}
+
+/// Print one setting of the loaded configuration.
+///
+/// Strings are printed bare for use in scripts; sections as TOML. API keys
+/// are redacted.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `key` - Dotted key, e.g. `general.log_level`
+pub fn get(config: &Config, key: &str) -> Result<()> {
+    match config.get(key)? {
+        Some(toml::Value::String(value)) => println!("{}", value),
+        Some(toml::Value::Table(table)) => {
+            print!(
+                "{}",
+                toml::to_string(&table).context("Failed to format section")?
+            )
+        }
+        Some(value) => println!("{}", value),
+        None => bail!("{} is not set", key),
+    }
+    Ok(())
+}
+
+/// Change a setting in the user config file, keeping its comments.
+///
+/// # Arguments
+///
+/// * `key` - Dotted key, e.g. `general.log_level`
+/// * `value` - New value; parsed as TOML when possible, else a string
+pub fn set(key: &str, value: &str) -> Result<()> {
+    let path = get_config_path();
+    let content = read_config_file(&path)?;
+    let updated = set_config_value(&content, key, value)?;
+    write_config_file(&path, &updated)?;
+    println!("Set {} in {}", key, path.display());
+    Ok(())
+}
+
+/// Edit the user config file in `$EDITOR`.
+///
+/// The file is only saved once the edited version is valid; until then the
+/// user can go back to the editor or discard the changes.
+pub fn edit() -> Result<()> {
+    let path = get_config_path();
+    let original = read_config_file(&path)?;
+    let builder = edit::Builder::new()
+        .prefix("cherry2k-config")
+        .suffix(".toml")
+        .clone();
+
+    let mut content = original.clone();
+    loop {
+        content = edit::edit_with_builder(&content, &builder).context("Failed to open editor")?;
+        if content == original {
+            println!("No changes.");
+            return Ok(());
+        }
+
+        match validate_config_str(&content) {
+            Ok(unknown) => {
+                for key in unknown {
+                    eprintln!("Warning: unknown setting {}", key);
+                }
+                break;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                if !matches!(confirm("Edit again?", false)?, ConfirmResult::Yes) {
+                    println!("Discarded changes, {} is unchanged", path.display());
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    write_config_file(&path, &content)?;
+    println!("Saved {}", path.display());
+    Ok(())
+}
+
+/// Check every config file that applies to the current directory.
+///
+/// Reports each file with the location of any problem and the settings it
+/// doesn't know, then checks that the selected profile exists.
+///
+/// # Arguments
+///
+/// * `profile` - Profile given with `--profile`, if any
+/// * `strict` - Count unknown settings as problems instead of warnings
+pub fn validate(profile: Option<&str>, strict: bool) -> Result<()> {
+    let mut problems = 0;
+    let mut report =
+        |path: &Path, result: Result<Vec<String>, cherry2k_core::ConfigError>| match result {
+            Ok(unknown) if unknown.is_empty() => println!("ok     {}", path.display()),
+            Ok(unknown) => {
+                let label = if strict {
+                    problems += 1;
+                    "error  "
+                } else {
+                    "warn   "
+                };
+                println!("{}{}", label, path.display());
+                for key in unknown {
+                    println!("       unknown setting {}", key);
+                }
+            }
+            Err(e) => {
+                problems += 1;
+                println!("error  {}", path.display());
+                for line in e.to_string().lines() {
+                    println!("       {}", line);
+                }
+            }
+        };
+
+    let user = get_config_path();
+    if user.exists() {
+        report(&user, validate_config_file(&user));
+    } else {
+        println!("-      {} (not found, using defaults)", user.display());
+    }
+
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    for file in find_project_configs(&cwd) {
+        let result = validate_config_file(&file);
+        report(&file, result);
+    }
+
+    let store = get_trust_store_path();
+    if store.exists() {
+        report(&store, TrustStore::load(&store).map(|_| Vec::new()));
+    }
+
+    if problems == 0
+        && let Err(e) = super::profile::load_config(profile)
+    {
+        problems += 1;
+        println!("error  {}", e);
+    }
+
+    if problems > 0 {
+        bail!("{} problem(s) found", problems);
+    }
+    Ok(())
+}
+
+/// Print the JSON Schema of config.toml.
+///
+/// Editors with TOML schema support (e.g. Taplo, Even Better TOML) use it
+/// for completion and inline checks.
+pub fn schema() -> Result<()> {
+    let schema =
+        serde_json::to_string_pretty(&config_schema()).context("Failed to format schema")?;
+    println!("{}", schema);
+    Ok(())
+}
+
+/// Show which config files are read, in the order they are merged.
+pub fn path() -> Result<()> {
+    let user = get_config_path();
+    let status = if user.exists() { "" } else { " (not found)" };
+    println!("Config file: {}{}", user.display(), status);
+
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let files = find_project_configs(&cwd);
+    if !files.is_empty() {
+        let trust = TrustStore::load(&get_trust_store_path())?;
+        println!("Project files:");
+        for file in files {
+            let trusted = fs::read_to_string(&file)
+                .map(|content| trust.is_trusted(&file, &content))
+                .unwrap_or(false);
+            let status = if trusted { "trusted" } else { "untrusted" };
+            println!("  {} ({})", file.display(), status);
+        }
+    }
+
+    println!("Trusted projects: {}", get_trust_store_path().display());
     Ok(())
 }
+
+/// Read the user config file, or nothing if it doesn't exist yet.
+fn read_config_file(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Write the user config file, creating its directory if needed.
+fn write_config_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create config directory")?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,169 @@
+//! Database maintenance commands.
+//!
+//! Provides commands for managing the session database:
+//! - `migrate`: Apply pending schema migrations (or preview them)
+//! - `prune`: Delete sessions outside the retention policy (or preview them)
+
+use anyhow::{Context, Result};
+use cherry2k_core::config::RetentionConfig;
+use cherry2k_storage::retention::{plan_prune, prune_sessions};
+use cherry2k_storage::{Database, PruneCandidate, RetentionPolicy, SCHEMA_VERSION, backup_path};
+
+/// Apply pending schema migrations to the session database.
+///
+/// Migrations also run automatically whenever the database is opened; this
+/// command makes the upgrade explicit and lets the user preview it first.
+///
+/// # Arguments
+///
+/// * `dry_run` - If true, only list the migrations that would be applied
+pub async fn migrate(dry_run: bool) -> Result<()> {
+    let path = Database::database_path().context("Failed to locate session database")?;
+
+    let pending = Database::pending_migrations_at(&path)
+        .await
+        .context("Failed to inspect session database")?;
+
+    if pending.is_empty() {
+        println!(
+            "Database is up to date (schema version {}).",
+            SCHEMA_VERSION
+        );
+        return Ok(());
+    }
+
+    println!("Database: {}", path.display());
+    println!("{} pending migration(s):", pending.len());
+    for migration in &pending {
+        println!("  {:>3}  {}", migration.version, migration.description);
+    }
+
+    if dry_run {
+        println!();
+        println!("Dry run: no changes made.");
+        return Ok(());
+    }
+
+    // Only an existing database is backed up (a new one has nothing to lose)
+    let from_version = pending[0].version - 1;
+    let existed = path.exists();
+
+    let db = Database::open_at(path.clone())
+        .await
+        .context("Failed to migrate session database")?;
+    let version = db
+        .schema_version()
+        .await
+        .context("Failed to read schema version")?;
+
+    println!();
+    println!("Migrated to schema version {}.", version);
+    if existed && from_version > 0 {
+        println!("Backup: {}", backup_path(&path, from_version).display());
+    }
+
+    Ok(())
+}
+
+/// Delete sessions outside the configured retention policy.
+///
+/// The same policy also runs occasionally after chats; this command applies
+/// it on demand and can preview the result.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `retention` - The `[retention]` settings from config.toml
+/// * `dry_run` - If true, only list the sessions that would be deleted
+pub async fn prune(db: &Database, retention: &RetentionConfig, dry_run: bool) -> Result<()> {
+    let policy = RetentionPolicy::from(retention);
+
+    if dry_run {
+        let candidates = plan_prune(db, &policy)
+            .await
+            .context("Failed to plan prune")?;
+
+        if candidates.is_empty() {
+            println!("Nothing to prune.");
+            return Ok(());
+        }
+
+        print_candidates(&candidates);
+        println!();
+        println!("Dry run: {} session(s) would be deleted.", candidates.len());
+        return Ok(());
+    }
+
+    let report = prune_sessions(db, &policy)
+        .await
+        .context("Failed to prune sessions")?;
+
+    if report.removed.is_empty() {
+        println!("Nothing to prune.");
+        return Ok(());
+    }
+
+    print_candidates(&report.removed);
+    println!();
+    println!("Deleted {} session(s).", report.removed.len());
+    if report.vacuumed {
+        println!(
+            "Compacted database: {} -> {}",
+            format_size(report.size_before),
+            format_size(report.size_after)
+        );
+    }
+
+    Ok(())
+}
+
+/// Print sessions selected for pruning as a table.
+fn print_candidates(candidates: &[PruneCandidate]) {
+    println!(
+        "{:<24} {:<17} {:<16} Title / Directory",
+        "ID", "Last Active", "Reason"
+    );
+    println!("{}", "-".repeat(80));
+
+    for candidate in candidates {
+        println!(
+            "{:<24} {:<17} {:<16} {}",
+            candidate.session_id,
+            candidate.last_message_at.format("%Y-%m-%d %H:%M"),
+            candidate.reason.to_string(),
+            candidate.title.as_deref().unwrap_or(&candidate.working_dir)
+        );
+    }
+}
+
+/// Format a byte count for display.
+fn format_size(bytes: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    const KB: u64 = 1024;
+
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else {
+        format!("{} KB", bytes.div_ceil(KB))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod format_size {
+        use super::*;
+
+        #[test]
+        fn uses_kilobytes_below_a_megabyte() {
+            assert_eq!(format_size(4096), "4 KB");
+            assert_eq!(format_size(1), "1 KB");
+        }
+
+        #[test]
+        fn uses_megabytes_above() {
+            assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MB");
+        }
+    }
+}
@@ -0,0 +1,642 @@
+//! Doctor command handler
+//!
+//! Checks everything cherry2k depends on and prints a pass/fail report with
+//! a fix for each problem:
+//! - Config files parse and validate, and the selected profile exists
+//! - Each configured provider has its API key and answers `health_check()`
+//! - The session database is readable, private, migrated and intact
+//! - The zsh plugin is loaded with its Ctrl+G binding
+//! - An editor is available and the terminal supports colour
+//!
+//! Like `config validate`, this runs before the configuration is loaded, so
+//! it still works when the config is broken.
+
+use std::env;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use cherry2k_core::config::{Config, find_project_configs, get_config_path, validate_config_file};
+use cherry2k_core::{AiProvider, AnthropicProvider, OllamaProvider, OpenAiProvider, ProviderError};
+use cherry2k_storage::{Database, SCHEMA_VERSION};
+use colored::Colorize;
+use tokio::process::Command;
+
+/// How long a provider has to answer its health check
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long an interactive zsh may take to start
+const ZSH_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Widget the plugin binds to Ctrl+G
+const CTRL_G_WIDGET: &str = "_cherry2k_ctrl_g_handler";
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A single line of the report
+#[derive(Debug)]
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, Status::Pass, detail, None::<String>)
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(name, Status::Warn, detail, Some(fix))
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(name, Status::Fail, detail, Some(fix))
+    }
+
+    fn new(
+        name: impl Into<String>,
+        status: Status,
+        detail: impl Into<String>,
+        fix: Option<impl Into<String>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix: fix.map(Into::into),
+        }
+    }
+}
+
+/// Run every check and print the report.
+///
+/// # Arguments
+///
+/// * `profile` - Profile given with `--profile`, if any
+///
+/// # Errors
+///
+/// Returns an error if any check failed, so scripts can test the exit code.
+/// Warnings alone don't fail.
+pub async fn run(profile: Option<&str>) -> Result<()> {
+    let (config_checks, config) = check_config(profile);
+    let mut sections = vec![("Config", config_checks)];
+    let providers = match config {
+        Some(config) => check_providers(&config).await,
+        None => vec![Check::warn(
+            "Providers",
+            "skipped, the configuration doesn't load",
+            "Fix the config errors above",
+        )],
+    };
+    sections.push(("Providers", providers));
+    sections.push(("Database", check_database().await));
+    sections.push(("Shell", check_shell().await));
+    sections.push(("Terminal", vec![check_editor(), check_colour()]));
+
+    let mut counts = [0usize; 3];
+    for (title, checks) in &sections {
+        println!("{}", title.bold());
+        for check in checks {
+            counts[check.status as usize] += 1;
+            print_check(check);
+        }
+        println!();
+    }
+
+    let [passed, warnings, failed] = counts;
+    println!(
+        "{} passed, {} warning(s), {} failed",
+        passed, warnings, failed
+    );
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// Print one check with its fix, if any.
+fn print_check(check: &Check) {
+    let label = match check.status {
+        Status::Pass => "pass".green(),
+        Status::Warn => "warn".yellow(),
+        Status::Fail => "FAIL".red().bold(),
+    };
+    let mut lines = check.detail.lines();
+    println!(
+        "  [{}] {}: {}",
+        label,
+        check.name,
+        lines.next().unwrap_or_default()
+    );
+    for line in lines {
+        println!("         {}", line);
+    }
+    if let Some(fix) = &check.fix {
+        println!("         fix: {}", fix);
+    }
+}
+
+// ============================================================================
+// Config
+// ============================================================================
+
+/// Check the config files, then load the effective configuration.
+///
+/// Returns the loaded config for the provider checks, or `None` if it
+/// couldn't be loaded.
+fn check_config(profile: Option<&str>) -> (Vec<Check>, Option<Config>) {
+    let mut checks = Vec::new();
+
+    let user = get_config_path();
+    if user.exists() {
+        checks.push(check_config_file("Config file", &user));
+    } else {
+        checks.push(Check::pass(
+            "Config file",
+            format!("{} not found, using defaults", user.display()),
+        ));
+    }
+
+    if let Ok(cwd) = env::current_dir() {
+        for file in find_project_configs(&cwd) {
+            checks.push(check_config_file("Project file", &file));
+        }
+    }
+
+    // A broken file was just reported; loading would repeat the error
+    if checks.iter().any(|check| check.status == Status::Fail) {
+        return (checks, None);
+    }
+    match super::profile::load_config(profile) {
+        Ok(config) => {
+            let detail = match &config.active_profile {
+                Some(name) => format!("loaded with profile {}", name),
+                None => "loaded".to_string(),
+            };
+            checks.push(Check::pass("Configuration", detail));
+            (checks, Some(config))
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "Configuration",
+                format!("{:#}", e),
+                "Run `cherry2k config validate` for details, or `cherry2k profile --clear`",
+            ));
+            (checks, None)
+        }
+    }
+}
+
+fn check_config_file(name: &str, path: &Path) -> Check {
+    match validate_config_file(path) {
+        Ok(unknown) if unknown.is_empty() => Check::pass(name, path.display().to_string()),
+        Ok(unknown) => Check::warn(
+            name,
+            format!(
+                "{}: unknown setting(s) {}",
+                path.display(),
+                unknown.join(", ")
+            ),
+            "Fix the spelling or remove them; see `cherry2k config schema`",
+        ),
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "Fix the file with `cherry2k config edit`",
+        ),
+    }
+}
+
+// ============================================================================
+// Providers
+// ============================================================================
+
+/// Check that each configured provider is usable and reachable.
+async fn check_providers(config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if let Some(cfg) = &config.openai {
+        let fixes = hosted_fixes("OPENAI_API_KEY", "openai");
+        let check = match cfg.clone().with_resolved_api_key() {
+            Ok(cfg) => check_provider("openai", &OpenAiProvider::new(cfg), &fixes).await,
+            Err(e) => Check::fail("openai", e.to_string(), fixes.setup),
+        };
+        checks.push(check);
+    }
+    if let Some(cfg) = &config.anthropic {
+        let fixes = hosted_fixes("ANTHROPIC_API_KEY", "anthropic");
+        let check = match cfg.clone().with_resolved_api_key() {
+            Ok(cfg) => check_provider("anthropic", &AnthropicProvider::new(cfg), &fixes).await,
+            Err(e) => Check::fail("anthropic", e.to_string(), fixes.setup),
+        };
+        checks.push(check);
+    }
+    if let Some(cfg) = &config.ollama {
+        let fixes = ProviderFixes {
+            setup: "Set ollama.host and ollama.model".to_string(),
+            unreachable: format!(
+                "Start Ollama with `ollama serve`, or set ollama.host (now {})",
+                cfg.host
+            ),
+        };
+        checks.push(check_provider("ollama", &OllamaProvider::new(cfg.clone()), &fixes).await);
+    }
+
+    let default_provider = &config.general.default_provider;
+    let default_usable = checks
+        .iter()
+        .any(|check| check.status == Status::Pass && &check.name == default_provider);
+    if checks.is_empty() {
+        checks.push(Check::fail(
+            "Providers",
+            "no provider configured",
+            "Set OPENAI_API_KEY or ANTHROPIC_API_KEY, or add an [ollama] section",
+        ));
+    } else if !default_usable {
+        checks.push(Check::warn(
+            "Default provider",
+            format!("{} is not usable", default_provider),
+            "Fix it above, or run `cherry2k config set general.default_provider <name>`",
+        ));
+    }
+    checks
+}
+
+/// Fixes for a provider, depending on what went wrong
+struct ProviderFixes {
+    /// The provider is misconfigured or its key is missing or rejected
+    setup: String,
+    /// The provider didn't answer
+    unreachable: String,
+}
+
+fn hosted_fixes(env_var: &str, section: &str) -> ProviderFixes {
+    ProviderFixes {
+        setup: format!(
+            "Set {} or one of {}.api_key, api_key_cmd, api_key_file, api_key_keyring",
+            env_var, section
+        ),
+        unreachable: "Check the network connection and proxy settings".to_string(),
+    }
+}
+
+async fn check_provider(name: &str, provider: &dyn AiProvider, fixes: &ProviderFixes) -> Check {
+    if let Err(e) = provider.validate_config() {
+        return Check::fail(name, e.to_string(), &fixes.setup);
+    }
+    match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, provider.health_check()).await {
+        Ok(Ok(())) => Check::pass(name, "reachable"),
+        Ok(Err(e @ ProviderError::InvalidApiKey { .. })) => {
+            Check::fail(name, e.to_string(), &fixes.setup)
+        }
+        Ok(Err(e)) => Check::fail(name, e.to_string(), &fixes.unreachable),
+        Err(_) => Check::fail(
+            name,
+            format!(
+                "no answer within {} seconds",
+                HEALTH_CHECK_TIMEOUT.as_secs()
+            ),
+            &fixes.unreachable,
+        ),
+    }
+}
+
+// ============================================================================
+// Database
+// ============================================================================
+
+/// Check the session database without creating or migrating it.
+async fn check_database() -> Vec<Check> {
+    let path = match Database::database_path() {
+        Ok(path) => path,
+        Err(e) => {
+            return vec![Check::fail(
+                "Location",
+                e.to_string(),
+                "Set HOME so the data directory can be found",
+            )];
+        }
+    };
+    if !path.exists() {
+        return vec![Check::pass(
+            "Location",
+            format!("{} (created on first chat)", path.display()),
+        )];
+    }
+
+    let mut checks = vec![Check::pass("Location", path.display().to_string())];
+    checks.push(check_database_permissions(&path));
+
+    match Database::pending_migrations_at(&path).await {
+        Ok(pending) if pending.is_empty() => {
+            checks.push(Check::pass("Schema", format!("version {}", SCHEMA_VERSION)));
+        }
+        Ok(pending) => checks.push(Check::warn(
+            "Schema",
+            format!("{} migration(s) pending", pending.len()),
+            "Run `cherry2k db migrate` (it also runs on the next chat)",
+        )),
+        Err(e) => checks.push(Check::fail(
+            "Schema",
+            e.to_string(),
+            "Restore a sessions.db.v*.bak backup, or upgrade cherry2k if the database is newer",
+        )),
+    }
+
+    match Database::integrity_check_at(&path).await {
+        Ok(problems) if problems.is_empty() => checks.push(Check::pass("Integrity", "ok")),
+        Ok(problems) => checks.push(Check::fail(
+            "Integrity",
+            problems.join("; "),
+            format!(
+                "Restore a backup, or move {} away to start fresh",
+                path.display()
+            ),
+        )),
+        Err(e) => checks.push(Check::fail(
+            "Integrity",
+            e.to_string(),
+            format!(
+                "Restore a backup, or move {} away to start fresh",
+                path.display()
+            ),
+        )),
+    }
+    checks
+}
+
+fn check_database_permissions(path: &Path) -> Check {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return Check::fail("Permissions", e.to_string(), "Check the file's owner"),
+    };
+    if metadata.permissions().readonly() {
+        return Check::fail(
+            "Permissions",
+            "read-only",
+            format!("Make it writable: chmod 600 {}", path.display()),
+        );
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Check::warn(
+                "Permissions",
+                format!("{:o}, readable by other users", mode),
+                format!("chmod 600 {}", path.display()),
+            );
+        }
+    }
+    Check::pass("Permissions", "private and writable")
+}
+
+// ============================================================================
+// Shell
+// ============================================================================
+
+/// What an interactive zsh reports about the plugin
+#[derive(Debug, PartialEq, Eq)]
+struct ZshProbe {
+    loaded: bool,
+    ctrl_g: Option<String>,
+}
+
+/// Check that zsh loads the plugin and keeps its Ctrl+G binding.
+///
+/// Starts an interactive zsh, so the user's .zshrc is read exactly as in a
+/// new terminal.
+async fn check_shell() -> Vec<Check> {
+    let Some(zsh) = find_in_path("zsh") else {
+        return vec![Check::warn(
+            "zsh",
+            "not found in PATH",
+            "Install zsh to use AI mode and Ctrl+G",
+        )];
+    };
+    let mut checks = vec![Check::pass("zsh", zsh.display().to_string())];
+
+    let output = Command::new(&zsh)
+        .args([
+            "-i",
+            "-c",
+            "print -r -- \"cherry2k-loaded=${_CHERRY2K_LOADED:-0}\"; \
+             print -r -- \"cherry2k-ctrl-g=$(bindkey '^G')\"",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let probe = match tokio::time::timeout(ZSH_PROBE_TIMEOUT, output).await {
+        Ok(Ok(output)) => parse_zsh_probe(&String::from_utf8_lossy(&output.stdout)),
+        Ok(Err(e)) => {
+            checks.push(Check::warn(
+                "Plugin",
+                format!("failed to start zsh: {}", e),
+                "Check that zsh runs",
+            ));
+            return checks;
+        }
+        Err(_) => {
+            checks.push(Check::warn(
+                "Plugin",
+                format!(
+                    "zsh took over {} seconds to start",
+                    ZSH_PROBE_TIMEOUT.as_secs()
+                ),
+                "Check .zshrc for commands that wait for input",
+            ));
+            return checks;
+        }
+    };
+
+    if !probe.loaded {
+        checks.push(Check::fail(
+            "Plugin",
+            "not loaded by ~/.zshrc",
+            "Add `source /path/to/cherry2k/zsh/cherry2k.plugin.zsh` to ~/.zshrc",
+        ));
+        return checks;
+    }
+    checks.push(Check::pass("Plugin", "loaded"));
+
+    match probe.ctrl_g.as_deref() {
+        Some(CTRL_G_WIDGET) => checks.push(Check::pass("Ctrl+G", CTRL_G_WIDGET)),
+        other => checks.push(Check::warn(
+            "Ctrl+G",
+            format!("bound to {}", other.unwrap_or("nothing")),
+            "Another plugin rebinds Ctrl+G; source cherry2k after it in ~/.zshrc",
+        )),
+    }
+    checks
+}
+
+/// Parses the marker lines printed by the zsh probe.
+///
+/// Other output (prompt themes, greetings) is ignored.
+fn parse_zsh_probe(stdout: &str) -> ZshProbe {
+    let mut probe = ZshProbe {
+        loaded: false,
+        ctrl_g: None,
+    };
+    for line in stdout.lines() {
+        if let Some(value) = line.strip_prefix("cherry2k-loaded=") {
+            probe.loaded = value.trim() == "1";
+        } else if let Some(binding) = line.strip_prefix("cherry2k-ctrl-g=") {
+            // `bindkey '^G'` prints `"^G" widget-name`
+            probe.ctrl_g = binding.split_whitespace().nth(1).map(str::to_string);
+        }
+    }
+    probe
+}
+
+/// Find an executable in `PATH`.
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+// ============================================================================
+// Terminal
+// ============================================================================
+
+/// Check which editor `config edit` and command editing will open.
+fn check_editor() -> Check {
+    let configured = env::var("VISUAL")
+        .ok()
+        .or_else(|| env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty());
+    match (edit::get_editor(), configured) {
+        (Ok(editor), Some(_)) => Check::pass("Editor", editor.display().to_string()),
+        (Ok(editor), None) => Check::warn(
+            "Editor",
+            format!("$EDITOR not set, falling back to {}", editor.display()),
+            "export EDITOR=<your editor> in ~/.zshrc",
+        ),
+        (Err(e), _) => Check::fail(
+            "Editor",
+            e.to_string(),
+            "Install an editor and export EDITOR=<your editor> in ~/.zshrc",
+        ),
+    }
+}
+
+/// Check whether output will be coloured.
+fn check_colour() -> Check {
+    colour_support(
+        std::io::stdout().is_terminal(),
+        env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+        env::var("TERM").ok().as_deref(),
+        env::var("COLORTERM").ok().as_deref(),
+    )
+}
+
+fn colour_support(
+    is_terminal: bool,
+    no_color: bool,
+    term: Option<&str>,
+    colorterm: Option<&str>,
+) -> Check {
+    const NAME: &str = "Colour";
+    if no_color {
+        return Check::warn(NAME, "disabled by NO_COLOR", "unset NO_COLOR");
+    }
+    if !is_terminal {
+        return Check::warn(
+            NAME,
+            "output is not a terminal, so colours are off",
+            "Run cherry2k doctor directly in the terminal to check colours",
+        );
+    }
+    match (term, colorterm) {
+        (None | Some("" | "dumb"), _) => Check::warn(
+            NAME,
+            format!("TERM is {}", term.unwrap_or("unset")),
+            "export TERM=xterm-256color",
+        ),
+        (_, Some("truecolor" | "24bit")) => Check::pass(NAME, "truecolor"),
+        (Some(term), _) if term.contains("256color") => Check::pass(NAME, "256 colours"),
+        (Some(term), _) => Check::pass(NAME, format!("basic colours ({})", term)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_zsh_probe {
+        use super::*;
+
+        #[test]
+        fn reads_markers_between_other_output() {
+            let probe = parse_zsh_probe(
+                "Welcome back!\ncherry2k-loaded=1\ncherry2k-ctrl-g=\"^G\" _cherry2k_ctrl_g_handler\n",
+            );
+            assert_eq!(
+                probe,
+                ZshProbe {
+                    loaded: true,
+                    ctrl_g: Some(CTRL_G_WIDGET.to_string()),
+                }
+            );
+        }
+
+        #[test]
+        fn plugin_not_loaded() {
+            let probe = parse_zsh_probe("cherry2k-loaded=0\ncherry2k-ctrl-g=\"^G\" send-break\n");
+            assert!(!probe.loaded);
+            assert_eq!(probe.ctrl_g.as_deref(), Some("send-break"));
+        }
+
+        #[test]
+        fn no_output() {
+            let probe = parse_zsh_probe("");
+            assert!(!probe.loaded);
+            assert_eq!(probe.ctrl_g, None);
+        }
+    }
+
+    mod colour_support {
+        use super::*;
+
+        #[test]
+        fn no_color_wins() {
+            let check = colour_support(true, true, Some("xterm-256color"), Some("truecolor"));
+            assert_eq!(check.status, Status::Warn);
+            assert!(check.detail.contains("NO_COLOR"));
+        }
+
+        #[test]
+        fn dumb_terminal_warns() {
+            assert_eq!(
+                colour_support(true, false, Some("dumb"), None).status,
+                Status::Warn
+            );
+            assert_eq!(colour_support(true, false, None, None).status, Status::Warn);
+        }
+
+        #[test]
+        fn detects_colour_depth() {
+            let check = colour_support(true, false, Some("xterm-256color"), Some("truecolor"));
+            assert_eq!(check.detail, "truecolor");
+            let check = colour_support(true, false, Some("xterm-256color"), None);
+            assert_eq!(check.detail, "256 colours");
+            let check = colour_support(true, false, Some("xterm"), None);
+            assert_eq!(check.status, Status::Pass);
+        }
+    }
+}
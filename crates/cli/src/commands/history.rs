@@ -0,0 +1,295 @@
+//! Execution history commands.
+//!
+//! Provides commands for auditing AI-suggested commands that were run:
+//! - `list`: Show recent executions, optionally filtered by a search term
+//! - `rerun`: Replay a past execution through the confirmation pipeline
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use cherry2k_core::config::Config;
+use cherry2k_storage::Database;
+use cherry2k_storage::execution::{StoredExecution, get_execution, list_executions};
+
+use cherry2k::signal::setup_cancellation;
+
+use super::pipeline::CommandPipeline;
+
+/// List recorded executions, most recent first.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `search` - Optional substring to filter commands and directories by
+/// * `limit` - Maximum number of executions to show
+pub async fn list(db: &Database, search: Option<&str>, limit: usize) -> Result<()> {
+    let executions = list_executions(db, search, limit)
+        .await
+        .context("Failed to list executions")?;
+
+    if executions.is_empty() {
+        match search {
+            Some(term) => println!("No executed commands match '{}'.", term),
+            None => println!("No executed commands yet."),
+        }
+        return Ok(());
+    }
+
+    println!("{:<6} {:<17} {:<10} Command", "ID", "When", "Status");
+    println!("{}", "-".repeat(70));
+
+    for execution in &executions {
+        println!("{}", format_row(execution));
+    }
+
+    Ok(())
+}
+
+/// Re-run a recorded execution through the confirmation pipeline.
+///
+/// The command runs in the current directory; if that differs from where it
+/// originally ran, the user is told before being asked to confirm.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration (safety settings)
+/// * `db` - The database connection
+/// * `id` - The execution ID to replay
+/// * `working_dir` - The current working directory
+pub async fn rerun(config: &Config, db: &Database, id: i64, working_dir: &Path) -> Result<()> {
+    let Some(execution) = get_execution(db, id)
+        .await
+        .context("Failed to get execution")?
+    else {
+        bail!("Execution not found: {}", id);
+    };
+
+    if Path::new(&execution.working_dir) != working_dir {
+        println!("Note: originally run in {}", execution.working_dir);
+    }
+
+    let cancel_token = setup_cancellation();
+    let pipeline = CommandPipeline {
+        config,
+        db,
+        session_id: execution.session_id.as_deref(),
+        inject_file: None,
+        insert_commands: false,
+    };
+
+    pipeline
+        .handle(
+            &execution.command,
+            Some(&format!("Re-running command #{}", execution.id)),
+            &cancel_token,
+        )
+        .await
+}
+
+/// One line of the history table.
+fn format_row(execution: &StoredExecution) -> String {
+    let command = execution.command.lines().next().unwrap_or_default();
+    // Truncate command to 50 chars
+    let command_truncated = if command.chars().count() > 50 {
+        format!("{}...", command.chars().take(47).collect::<String>())
+    } else {
+        command.to_string()
+    };
+    let edited = if execution.was_edited() {
+        " (edited)"
+    } else {
+        ""
+    };
+
+    format!(
+        "{:<6} {:<17} {:<10} {}{}",
+        execution.id,
+        execution.executed_at.format("%Y-%m-%d %H:%M"),
+        status_label(execution),
+        command_truncated,
+        edited
+    )
+}
+
+/// Short outcome label for the history table.
+fn status_label(execution: &StoredExecution) -> String {
+    if execution.was_cancelled {
+        return "cancelled".to_string();
+    }
+    match execution.exit_code {
+        Some(code) => format!("exit {}", code),
+        None => "signal".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cherry2k_storage::NewExecution;
+    use cherry2k_storage::execution::record_execution;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    async fn setup_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    fn stored(exit_code: Option<i32>, was_cancelled: bool) -> StoredExecution {
+        StoredExecution {
+            id: 1,
+            session_id: None,
+            original_command: "ls".to_string(),
+            command: "ls".to_string(),
+            working_dir: "/tmp".to_string(),
+            exit_code,
+            duration_ms: 0,
+            was_cancelled,
+            executed_at: Utc::now(),
+        }
+    }
+
+    mod status_label {
+        use super::*;
+
+        #[test]
+        fn shows_exit_code() {
+            assert_eq!(status_label(&stored(Some(0), false)), "exit 0");
+            assert_eq!(status_label(&stored(Some(127), false)), "exit 127");
+        }
+
+        #[test]
+        fn cancelled_takes_precedence() {
+            assert_eq!(status_label(&stored(Some(130), true)), "cancelled");
+        }
+
+        #[test]
+        fn missing_exit_code_means_signal() {
+            assert_eq!(status_label(&stored(None, false)), "signal");
+        }
+    }
+
+    mod format_row {
+        use super::*;
+
+        #[test]
+        fn marks_edited_commands() {
+            let mut execution = stored(Some(0), false);
+            execution.command = "ls -la".to_string();
+
+            let row = format_row(&execution);
+
+            assert!(row.starts_with("1 "));
+            assert!(row.contains("exit 0"));
+            assert!(row.ends_with("ls -la (edited)"));
+        }
+
+        #[test]
+        fn shows_first_line_truncated() {
+            let mut execution = stored(Some(0), false);
+            execution.command = format!("echo {}\nsecond line", "x".repeat(60));
+            execution.original_command = execution.command.clone();
+
+            let row = format_row(&execution);
+
+            assert!(row.ends_with(&format!("echo {}...", "x".repeat(42))));
+            assert!(!row.contains("second line"));
+        }
+    }
+
+    mod list {
+        use super::*;
+
+        async fn record(db: &Database, command: &str, working_dir: &str, exit_code: i32) {
+            record_execution(
+                db,
+                NewExecution {
+                    session_id: None,
+                    original_command: command.to_string(),
+                    command: command.to_string(),
+                    working_dir: working_dir.to_string(),
+                    exit_code: Some(exit_code),
+                    duration_ms: 5,
+                    was_cancelled: false,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        async fn rows(db: &Database, search: Option<&str>, limit: usize) -> Vec<String> {
+            list_executions(db, search, limit)
+                .await
+                .unwrap()
+                .iter()
+                .map(format_row)
+                .collect()
+        }
+
+        #[tokio::test]
+        async fn shows_most_recent_first() {
+            let (db, _temp) = setup_db().await;
+            record(&db, "ls", "/tmp", 0).await;
+            record(&db, "cargo test", "/work", 101).await;
+
+            let rows = rows(&db, None, 20).await;
+
+            assert_eq!(rows.len(), 2);
+            assert!(rows[0].starts_with("2 "));
+            assert!(rows[0].contains("exit 101"));
+            assert!(rows[0].ends_with("cargo test"));
+            assert!(rows[1].starts_with("1 "));
+            assert!(rows[1].ends_with("ls"));
+        }
+
+        #[tokio::test]
+        async fn filters_by_command_and_directory() {
+            let (db, _temp) = setup_db().await;
+            record(&db, "ls -la", "/tmp", 0).await;
+            record(&db, "cargo test", "/work", 0).await;
+            record(&db, "git status", "/work/ls-tool", 0).await;
+
+            let rows = rows(&db, Some("ls"), 20).await;
+
+            assert_eq!(rows.len(), 2);
+            assert!(rows[0].ends_with("git status"));
+            assert!(rows[1].ends_with("ls -la"));
+        }
+
+        #[tokio::test]
+        async fn respects_limit() {
+            let (db, _temp) = setup_db().await;
+            for i in 0..3 {
+                record(&db, &format!("echo {}", i), "/tmp", 0).await;
+            }
+
+            let rows = rows(&db, None, 2).await;
+
+            assert_eq!(rows.len(), 2);
+            assert!(rows[0].ends_with("echo 2"));
+        }
+
+        #[tokio::test]
+        async fn succeeds_with_no_executions() {
+            let (db, _temp) = setup_db().await;
+
+            assert!(rows(&db, None, 20).await.is_empty());
+            assert!(list(&db, None, 20).await.is_ok());
+        }
+    }
+
+    mod rerun {
+        use super::*;
+
+        #[tokio::test]
+        async fn errors_for_nonexistent_execution() {
+            let (db, temp_dir) = setup_db().await;
+
+            let result = rerun(&Config::default(), &db, 99, temp_dir.path()).await;
+
+            assert!(result.is_err());
+        }
+    }
+}
@@ -1,8 +1,18 @@
 //! CLI command handlers
 //!
-//! Each subcommand has its own module with a `run` function.
+//! Each subcommand has its own module with a `run` function. Shared
+//! helpers used by several subcommands live alongside them.
 
+pub mod changes;
 pub mod chat;
 pub mod config;
+pub mod db;
+pub mod doctor;
+pub mod history;
+pub mod pipeline;
+pub mod profile;
+pub mod prompt;
 pub mod provider;
+pub mod search;
 pub mod session;
+pub mod transfer;
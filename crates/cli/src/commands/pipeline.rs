@@ -0,0 +1,174 @@
+//! Confirmation pipeline for suggested commands.
+//!
+//! Shared by `chat` (commands suggested by the AI) and `history rerun`
+//! (commands replayed from the execution log). A command is checked against
+//! the blocked patterns, shown to the user, confirmed/edited/inserted, and
+//! then run. Every command that actually runs is recorded in the execution
+//! history.
+
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use cherry2k_core::config::Config;
+use cherry2k_storage::Database;
+use cherry2k_storage::execution::{NewExecution, record_execution};
+use colored::Colorize;
+use tokio_util::sync::CancellationToken;
+
+use cherry2k::confirm::{ConfirmResult, check_blocked_patterns, confirm_command, edit_command};
+use cherry2k::execute::{display_exit_status, execute_command, inject_command};
+use cherry2k::output::display_suggested_command;
+
+/// Settings for taking a suggested command from display to execution.
+pub struct CommandPipeline<'a> {
+    /// Application configuration (safety settings)
+    pub config: &'a Config,
+    /// Database used to record executions
+    pub db: &'a Database,
+    /// Session the command belongs to, if any
+    pub session_id: Option<&'a str>,
+    /// Inject file for handing commands back to the zsh widget
+    pub inject_file: Option<&'a Path>,
+    /// Place commands on the prompt line without asking
+    pub insert_commands: bool,
+}
+
+impl CommandPipeline<'_> {
+    /// Confirm and run (or insert) a suggested command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command as suggested
+    /// * `context` - Optional explanation shown alongside the command
+    /// * `cancel_token` - Cancellation token forwarded to the running command
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if prompting, editing, inserting or spawning the command fails.
+    pub async fn handle(
+        &self,
+        command: &str,
+        context: Option<&str>,
+        cancel_token: &CancellationToken,
+    ) -> Result<()> {
+        // Check for blocked dangerous patterns first
+        if let Some(pattern) = check_blocked_patterns(command, &self.config.safety.blocked_patterns)
+        {
+            print_blocked(pattern);
+            return Ok(());
+        }
+
+        // Display the command with syntax highlighting
+        display_suggested_command(command, context);
+
+        let mut command_to_run = command.to_string();
+
+        if let Some(path) = self.inject_file.filter(|_| self.insert_commands) {
+            // Insert mode: the user reviews the command on their own prompt line
+            insert_command(path, &command_to_run)?;
+        } else if self.config.safety.confirm_commands {
+            // Ask for confirmation
+            loop {
+                match confirm_command(&command_to_run, self.inject_file.is_some())? {
+                    choice @ (ConfirmResult::Yes | ConfirmResult::Insert) => {
+                        // Re-check blocked patterns after edit
+                        if let Some(pattern) = check_blocked_patterns(
+                            &command_to_run,
+                            &self.config.safety.blocked_patterns,
+                        ) {
+                            print_blocked(pattern);
+                            return Ok(());
+                        }
+
+                        match self.inject_file {
+                            Some(path) if choice == ConfirmResult::Insert => {
+                                insert_command(path, &command_to_run)?;
+                            }
+                            _ => self.run(command, &command_to_run, cancel_token).await?,
+                        }
+                        break;
+                    }
+                    ConfirmResult::No => {
+                        println!("Command cancelled.");
+                        break;
+                    }
+                    ConfirmResult::Edit => {
+                        command_to_run = edit_command(&command_to_run)?;
+                        // Re-display the edited command
+                        display_suggested_command(&command_to_run, None);
+                        // Loop continues to re-confirm
+                    }
+                }
+            }
+        } else {
+            // Auto-execute without confirmation (confirm_commands = false)
+            self.run(command, &command_to_run, cancel_token).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Execute a command with signal handling, display results, and record it.
+    async fn run(
+        &self,
+        original_command: &str,
+        command: &str,
+        cancel_token: &CancellationToken,
+    ) -> Result<()> {
+        println!(); // Blank line before execution
+
+        let started = Instant::now();
+
+        // Execute with signal handling
+        let result = execute_command(command, Some(cancel_token.clone())).await?;
+        let duration = started.elapsed();
+
+        // Display exit status
+        display_exit_status(result.status);
+
+        if result.was_cancelled {
+            println!("Command interrupted.");
+        }
+
+        let working_dir = std::env::current_dir().context("Failed to get current directory")?;
+        let execution = NewExecution {
+            session_id: self.session_id.map(str::to_string),
+            original_command: original_command.to_string(),
+            command: command.to_string(),
+            working_dir: working_dir.to_string_lossy().into_owned(),
+            exit_code: result.status.code(),
+            duration_ms: i64::try_from(duration.as_millis()).unwrap_or(i64::MAX),
+            was_cancelled: result.was_cancelled,
+        };
+
+        // The command already ran; failing to log it shouldn't fail the chat
+        if let Err(e) = record_execution(self.db, execution).await {
+            tracing::warn!("Failed to record execution: {}", e);
+        }
+
+        Ok(())
+    }
+}
+
+/// Tell the user a command was blocked by a dangerous pattern.
+fn print_blocked(pattern: &str) {
+    println!();
+    println!(
+        "{} Command matches dangerous pattern: {}",
+        "BLOCKED:".red(),
+        pattern
+    );
+    println!("This command has been blocked for safety reasons.");
+}
+
+/// Hand a command back to the zsh widget instead of running it.
+///
+/// The widget pushes the command onto the editing buffer after cherry2k exits,
+/// so it runs in the user's own shell and lands in their history.
+fn insert_command(path: &Path, command: &str) -> Result<()> {
+    inject_command(path, command)
+        .with_context(|| format!("Failed to write inject file: {}", path.display()))?;
+    println!("Command placed on your prompt line. Press Enter to run it.");
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! Profile management commands.
+//!
+//! Commands for listing, showing, and switching configuration profiles
+//! (`[profiles.<name>]` tables in config). The active profile persists in a
+//! state file, like the active provider.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use cherry2k_core::ConfigError;
+use cherry2k_core::config::{Config, PROFILE_ENV_VAR, load_config_with_profile};
+
+use super::provider::get_state_dir;
+
+/// Name of the state file holding the active profile
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+// ============================================================================
+// State File Management
+// ============================================================================
+
+/// Get the persisted active profile from the state file.
+///
+/// Returns `None` if the state directory cannot be determined or the state
+/// file doesn't exist or cannot be read.
+pub fn get_active_profile() -> Option<String> {
+    let path = get_state_dir()?.join(ACTIVE_PROFILE_FILE);
+    match fs::read_to_string(&path) {
+        Ok(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            tracing::debug!("Failed to read active_profile state: {e}");
+            None
+        }
+    }
+}
+
+/// Persist the active profile, or clear it with `None`.
+fn set_active_profile(name: Option<&str>) -> Result<()> {
+    let state_dir =
+        get_state_dir().ok_or_else(|| anyhow::anyhow!("Could not determine state directory"))?;
+    let path = state_dir.join(ACTIVE_PROFILE_FILE);
+    match name {
+        Some(name) => {
+            fs::create_dir_all(&state_dir).context("Failed to create state directory")?;
+            fs::write(&path, name).context("Failed to write state file")?;
+        }
+        None => match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context("Failed to remove state file");
+            }
+            _ => {}
+        },
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Config Loading
+// ============================================================================
+
+/// Load the configuration with the selected profile applied.
+///
+/// The profile is, in order: `--profile`, `CHERRY2K_PROFILE`, then the one
+/// persisted with `cherry2k profile <name>`. A persisted profile that no
+/// longer exists is ignored with a warning, so it can't lock the user out.
+///
+/// # Errors
+///
+/// Returns an error if the config is invalid or an explicitly selected
+/// profile doesn't exist.
+pub fn load_config(profile: Option<&str>) -> Result<Config> {
+    let explicit = profile
+        .map(str::to_string)
+        .or_else(|| std::env::var(PROFILE_ENV_VAR).ok())
+        .filter(|name| !name.is_empty());
+    if explicit.is_some() {
+        return Ok(load_config_with_profile(explicit.as_deref())?);
+    }
+
+    let persisted = get_active_profile();
+    match load_config_with_profile(persisted.as_deref()) {
+        Err(ConfigError::UnknownProfile { name, .. }) => {
+            tracing::warn!(
+                "Active profile '{}' no longer exists, using the base configuration",
+                name
+            );
+            Ok(load_config_with_profile(None)?)
+        }
+        result => Ok(result?),
+    }
+}
+
+// ============================================================================
+// Command Handlers
+// ============================================================================
+
+/// List the profiles defined in config, marking the active one.
+pub fn run_list(config: &Config) -> Result<()> {
+    if config.profiles.is_empty() {
+        println!("No profiles defined. Add [profiles.<name>] tables to config.toml.");
+        return Ok(());
+    }
+
+    println!("Available profiles:");
+    for name in config.profiles.keys() {
+        let active = config.active_profile.as_deref() == Some(name.as_str());
+        let marker = if active { "*" } else { " " };
+        let active_label = if active { " [active]" } else { "" };
+        println!("  {} {}{}", marker, name, active_label);
+    }
+
+    Ok(())
+}
+
+/// Show the profile in use.
+pub fn run_current(config: &Config) -> Result<()> {
+    match &config.active_profile {
+        Some(name) => println!("Currently using profile: {}", name),
+        None => println!("No profile active (base configuration)"),
+    }
+    Ok(())
+}
+
+/// Switch to a profile for later commands.
+///
+/// Validates that the profile is defined before switching.
+pub fn run_switch(config: &Config, name: &str) -> Result<()> {
+    if !config.profiles.contains_key(name) {
+        let available: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        anyhow::bail!(
+            "Profile '{}' not defined. Available: {}",
+            name,
+            if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            }
+        );
+    }
+
+    set_active_profile(Some(name))?;
+    println!("Switched to profile: {}", name);
+    Ok(())
+}
+
+/// Go back to the base configuration.
+pub fn run_clear() -> Result<()> {
+    set_active_profile(None)?;
+    println!("Profile cleared, using the base configuration");
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Prompt command handler
+//!
+//! Shows the system prompt sent with each request and lists the personas
+//! that can be selected with `--persona`.
+
+use anyhow::{Context, Result};
+use cherry2k::files::load_instructions;
+use cherry2k::persona::{DEFAULT_PERSONA, PersonaSource, list_personas, load_persona};
+use cherry2k_core::config::Config;
+
+/// Print the fully rendered system prompt for the current directory.
+///
+/// Includes the project's standing instructions, if any, exactly as they
+/// are sent after the persona prompt.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `persona` - Persona to render, or `None` for the configured one
+pub fn show(config: &Config, persona: Option<&str>) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let name = persona.or(config.general.persona.as_deref());
+    let persona = load_persona(name, &cwd).context("Failed to load persona")?;
+
+    println!("{}", persona.render(&cwd).trim_end());
+
+    if let Some(instructions) =
+        load_instructions(&cwd).context("Failed to read project instructions")?
+    {
+        println!();
+        println!("{}", instructions.to_system_prompt());
+    }
+    Ok(())
+}
+
+/// List the personas available in the current directory.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration (marks the configured persona)
+pub fn list(config: &Config) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let active = config.general.persona.as_deref().unwrap_or(DEFAULT_PERSONA);
+
+    for (name, source) in list_personas(&cwd) {
+        let marker = if name == active { "*" } else { " " };
+        let source = match source {
+            PersonaSource::BuiltIn => "(built-in)".to_string(),
+            PersonaSource::File(path) => path.display().to_string(),
+        };
+        println!("{} {:<16} {}", marker, name, source);
+    }
+    Ok(())
+}
@@ -18,7 +18,7 @@ use directories::ProjectDirs;
 /// Get the state directory path.
 ///
 /// Uses XDG conventions via the directories crate.
-fn get_state_dir() -> Option<PathBuf> {
+pub(crate) fn get_state_dir() -> Option<PathBuf> {
     ProjectDirs::from("", "", "cherry2k")
         .map(|dirs| dirs.state_dir().unwrap_or(dirs.data_dir()).to_path_buf())
 }
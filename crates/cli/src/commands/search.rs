@@ -0,0 +1,230 @@
+//! Conversation search command.
+//!
+//! Searches message content across all sessions and prints matching
+//! snippets with the matched words highlighted. The top hit can be resumed
+//! directly with `--resume`.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use cherry2k_core::provider::Role;
+use cherry2k_storage::search::{MATCH_END, MATCH_START, search_messages};
+use cherry2k_storage::{Database, SearchFilter};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use colored::Colorize;
+
+/// Options for the search command, as given on the command line.
+#[derive(Debug, Default)]
+pub struct SearchOptions<'a> {
+    /// Only sessions in this directory or below it
+    pub dir: Option<&'a Path>,
+    /// Only messages handled by this provider
+    pub provider: Option<&'a str>,
+    /// Only messages with this role (user, assistant, system)
+    pub role: Option<&'a str>,
+    /// Only messages on or after this date (YYYY-MM-DD)
+    pub since: Option<&'a str>,
+    /// Only messages on or before this date (YYYY-MM-DD)
+    pub until: Option<&'a str>,
+    /// Maximum number of hits to show
+    pub limit: usize,
+    /// Resume the session of the top hit
+    pub resume: bool,
+}
+
+/// Search all conversations and print matching snippets.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `query` - The words to search for
+/// * `options` - Filters and display options
+/// * `working_dir` - The current working directory (used when resuming)
+///
+/// # Returns
+///
+/// Ok(Some(session_id)) if the top hit's session was resumed, Ok(None) otherwise.
+pub async fn run(
+    db: &Database,
+    query: &str,
+    options: &SearchOptions<'_>,
+    working_dir: &Path,
+) -> Result<Option<String>> {
+    let filter = build_filter(options)?;
+
+    let hits = search_messages(db, query, &filter, options.limit)
+        .await
+        .context("Failed to search conversations")?;
+
+    if hits.is_empty() {
+        println!("No messages match '{}'.", query);
+        return Ok(None);
+    }
+
+    for hit in &hits {
+        let provider = hit
+            .provider
+            .as_deref()
+            .map(|p| format!(" via {}", p))
+            .unwrap_or_default();
+        println!(
+            "{} {} {}{}",
+            hit.session_id.cyan(),
+            hit.created_at.format("%Y-%m-%d %H:%M"),
+            hit.role,
+            provider
+        );
+        println!("  {}", hit.working_dir.dimmed());
+        println!("  {}", highlight(&hit.snippet));
+        println!();
+    }
+
+    if options.resume {
+        return super::session::resume(db, Some(&hits[0].session_id), false, working_dir).await;
+    }
+
+    println!("Resume a conversation with: cherry2k resume <session-id>");
+    Ok(None)
+}
+
+/// Convert command-line options into a storage search filter.
+fn build_filter(options: &SearchOptions<'_>) -> Result<SearchFilter> {
+    let role = options.role.map(parse_role).transpose()?;
+    let since = options.since.map(parse_date).transpose()?;
+    // --until is inclusive, so search up to the start of the following day
+    let until = options
+        .until
+        .map(|s| {
+            parse_date(s)?
+                .checked_add_days(Days::new(1))
+                .with_context(|| format!("Date out of range: {}", s))
+        })
+        .transpose()?;
+
+    // Sessions record absolute directories, so resolve `.` and friends
+    let working_dir = options.dir.map(|d| {
+        std::fs::canonicalize(d)
+            .unwrap_or_else(|_| d.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    Ok(SearchFilter {
+        working_dir,
+        provider: options.provider.map(str::to_string),
+        role,
+        since,
+        until,
+    })
+}
+
+/// Parse a role name given on the command line.
+fn parse_role(s: &str) -> Result<Role> {
+    match s.to_lowercase().as_str() {
+        "user" => Ok(Role::User),
+        "assistant" => Ok(Role::Assistant),
+        "system" => Ok(Role::System),
+        _ => bail!("Invalid role '{}': expected user, assistant or system", s),
+    }
+}
+
+/// Parse a YYYY-MM-DD date as midnight UTC.
+fn parse_date(s: &str) -> Result<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}': expected YYYY-MM-DD", s))?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+/// Render a snippet on one line with matched words highlighted.
+fn highlight(snippet: &str) -> String {
+    let flat = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = String::new();
+
+    for (i, part) in flat.split(MATCH_START).enumerate() {
+        match part.split_once(MATCH_END) {
+            Some((matched, rest)) if i > 0 => {
+                out.push_str(&matched.yellow().bold().to_string());
+                out.push_str(rest);
+            }
+            _ => out.push_str(part),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod build_filter {
+        use super::*;
+
+        #[test]
+        fn until_is_inclusive() {
+            let options = SearchOptions {
+                until: Some("2026-03-01"),
+                ..Default::default()
+            };
+
+            let filter = build_filter(&options).unwrap();
+
+            assert_eq!(
+                filter
+                    .until
+                    .unwrap()
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                "2026-03-02 00:00:00"
+            );
+        }
+
+        #[test]
+        fn parses_role_case_insensitively() {
+            let options = SearchOptions {
+                role: Some("Assistant"),
+                ..Default::default()
+            };
+
+            assert_eq!(build_filter(&options).unwrap().role, Some(Role::Assistant));
+        }
+
+        #[test]
+        fn rejects_invalid_role() {
+            let options = SearchOptions {
+                role: Some("robot"),
+                ..Default::default()
+            };
+
+            assert!(build_filter(&options).is_err());
+        }
+
+        #[test]
+        fn rejects_invalid_date() {
+            let options = SearchOptions {
+                since: Some("last tuesday"),
+                ..Default::default()
+            };
+
+            assert!(build_filter(&options).is_err());
+        }
+    }
+
+    mod highlight {
+        use super::*;
+
+        #[test]
+        fn removes_markers_and_newlines() {
+            let snippet = format!("run\n{MATCH_START}cargo{MATCH_END} build");
+
+            let rendered = highlight(&snippet);
+
+            assert!(rendered.starts_with("run "));
+            assert!(rendered.contains("cargo"));
+            assert!(rendered.ends_with(" build"));
+            assert!(!rendered.contains(MATCH_START));
+            assert!(!rendered.contains(MATCH_END));
+            assert!(!rendered.contains('\n'));
+        }
+    }
+}
@@ -1,23 +1,36 @@
 //! Session management commands.
 //!
 //! Provides commands for managing conversation sessions:
-//! - `resume`: List or resume sessions
+//! - `resume`: List or resume sessions (by ID, title or tag)
 //! - `new`: Force create a new session
+//! - `session rename/tag/pin`: Label sessions and protect them from cleanup
+//! - `session pin-message`: Keep a message in the context verbatim
+//! - `session show/fork`: Inspect a session's messages and fork from one
 //! - `clear`: Delete all sessions with confirmation
 
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
 use std::path::Path;
 
 use anyhow::{Context, Result, bail};
-use cherry2k_storage::Database;
-use cherry2k_storage::session::{create_session, get_session, is_valid_session_id, list_sessions};
+use cherry2k_core::ProviderFactory;
+use cherry2k_core::config::Config;
+use cherry2k_core::provider::{ContextBudget, Tokenizer};
+use cherry2k_storage::context::estimate_tokens;
+use cherry2k_storage::message::{get_messages, set_message_pinned};
+use cherry2k_storage::session::{
+    add_session_tags, create_session, find_session_by_name, fork_session, get_session,
+    is_valid_session_id, list_sessions, normalize_tag, remove_session_tags, set_session_pinned,
+    set_session_title,
+};
+use cherry2k_storage::{Database, Session, SessionInfo, StoredMessage};
 
 /// Resume a session or list available sessions.
 ///
 /// # Arguments
 ///
 /// * `db` - The database connection
-/// * `session_id` - Optional specific session ID to resume
+/// * `session_id` - Optional session ID, title or tag to resume
 /// * `list` - If true, list all sessions instead of resuming
 /// * `working_dir` - The current working directory
 ///
@@ -41,15 +54,38 @@ pub async fn resume(
             return Ok(None);
         }
 
+        // Forks are listed under the session they came from
+        let rows: Vec<(String, &SessionInfo)> = tree_order(&sessions)
+            .into_iter()
+            .map(|(index, depth)| {
+                let branch = if depth > 0 {
+                    format!("{}└ ", "  ".repeat(depth - 1))
+                } else {
+                    String::new()
+                };
+                (
+                    format!("{}{}", branch, sessions[index].id),
+                    &sessions[index],
+                )
+            })
+            .collect();
+        let id_width = rows
+            .iter()
+            .map(|(cell, _)| cell.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(22);
+
         println!("Sessions in {}:", working_dir.display());
         println!();
-        println!("{:<22} {:<22} Preview", "ID", "Last Active");
+        println!("{:<id_width$} {:<22} Title / Preview", "ID", "Last Active");
         println!("{}", "-".repeat(70));
 
-        for session in sessions {
+        for (id_cell, session) in rows {
             let preview = session
-                .first_message_preview
+                .title
                 .as_deref()
+                .or(session.first_message_preview.as_deref())
                 .unwrap_or("(no messages)");
             // Truncate preview to 50 chars
             let preview_truncated = if preview.chars().count() > 50 {
@@ -58,35 +94,26 @@ pub async fn resume(
                 preview.to_string()
             };
 
+            // Pad by characters: the tree glyph is wider in bytes than on screen
+            let padding = id_width.saturating_sub(id_cell.chars().count());
             println!(
-                "{:<22} {:<22} {}",
-                session.id,
+                "{}{} {:<22} {}{}{}",
+                id_cell,
+                " ".repeat(padding),
                 session.last_message_at.format("%Y-%m-%d %H:%M"),
-                preview_truncated
+                if session.pinned { "* " } else { "" },
+                preview_truncated,
+                format_tags(&session.tags)
             );
         }
 
         return Ok(None);
     }
 
-    if let Some(id) = session_id {
-        // Validate session ID format to prevent malformed input
-        if !is_valid_session_id(id) {
-            bail!("Invalid session ID format: {}", id);
-        }
-
-        // Resume specific session
-        let session = get_session(db, id).await.context("Failed to get session")?;
-
-        match session {
-            Some(s) => {
-                println!("Resumed session {}", s.id);
-                Ok(Some(s.id))
-            }
-            None => {
-                anyhow::bail!("Session not found: {}", id);
-            }
-        }
+    if let Some(target) = session_id {
+        let session = find(db, target).await?;
+        println!("Resumed session {}", describe(&session));
+        Ok(Some(session.id))
     } else {
         // Get most recent session
         let sessions = list_sessions(db, working_dir, 1)
@@ -106,6 +133,325 @@ pub async fn resume(
     }
 }
 
+/// Set a session's title.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `target` - Session ID, title or tag
+/// * `title` - The new title
+pub async fn rename(db: &Database, target: &str, title: &str) -> Result<()> {
+    let title = title.trim();
+    if title.is_empty() {
+        bail!("Title cannot be empty");
+    }
+
+    let session = find(db, target).await?;
+    set_session_title(db, &session.id, Some(title))
+        .await
+        .context("Failed to rename session")?;
+
+    println!("Renamed session {} to \"{}\"", session.id, title);
+    Ok(())
+}
+
+/// Add or remove tags on a session.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `target` - Session ID, title or tag
+/// * `tags` - Tags to add or remove
+/// * `remove` - If true, remove the tags instead of adding them
+pub async fn tag(db: &Database, target: &str, tags: &[String], remove: bool) -> Result<()> {
+    let tags = parse_tags(tags)?;
+    let session = find(db, target).await?;
+
+    if remove {
+        remove_session_tags(db, &session.id, &tags)
+            .await
+            .context("Failed to remove tags")?;
+    } else {
+        add_session_tags(db, &session.id, &tags)
+            .await
+            .context("Failed to add tags")?;
+    }
+
+    let session = get_session(db, &session.id)
+        .await
+        .context("Failed to get session")?
+        .with_context(|| format!("Session not found: {}", session.id))?;
+
+    if session.tags.is_empty() {
+        println!("Session {} has no tags", session.id);
+    } else {
+        println!("Session {} tags:{}", session.id, format_tags(&session.tags));
+    }
+    Ok(())
+}
+
+/// Pin or unpin a session. Pinned sessions are never cleaned up.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `target` - Session ID, title or tag
+/// * `pinned` - Whether the session should be pinned
+pub async fn pin(db: &Database, target: &str, pinned: bool) -> Result<()> {
+    let session = find(db, target).await?;
+    set_session_pinned(db, &session.id, pinned)
+        .await
+        .context("Failed to update session")?;
+
+    if pinned {
+        println!("Pinned session {}", describe(&session));
+    } else {
+        println!("Unpinned session {}", describe(&session));
+    }
+    Ok(())
+}
+
+/// Pin or unpin a message. Pinned messages are always sent verbatim and are
+/// never summarized.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `message_id` - Message ID (from `session show`)
+/// * `pinned` - Whether the message should be pinned
+pub async fn pin_message(db: &Database, message_id: i64, pinned: bool) -> Result<()> {
+    let message = set_message_pinned(db, message_id, pinned)
+        .await
+        .context("Failed to update message")?
+        .with_context(|| format!("Message not found: {}", message_id))?;
+
+    if pinned {
+        println!(
+            "Pinned message {} in session {}",
+            message.id, message.session_id
+        );
+    } else {
+        println!(
+            "Unpinned message {} in session {}",
+            message.id, message.session_id
+        );
+    }
+    Ok(())
+}
+
+/// Print a session's messages with their IDs (for `session fork --at`),
+/// followed by how much of the context window the session uses.
+///
+/// # Arguments
+///
+/// * `config` - Configuration, for the provider whose context window applies
+/// * `db` - The database connection
+/// * `target` - Session ID, title or tag
+pub async fn show(config: &Config, db: &Database, target: &str) -> Result<()> {
+    let session = find(db, target).await?;
+    let messages = get_messages(db, &session.id)
+        .await
+        .context("Failed to load messages")?;
+
+    println!("Session {}", describe(&session));
+    println!("Directory: {}", session.working_dir);
+    if let Some(parent) = &session.parent_session_id {
+        match session.forked_from_message_id {
+            Some(message_id) => println!("Forked from: {} at message {}", parent, message_id),
+            None => println!("Forked from: {}", parent),
+        }
+    }
+    println!();
+
+    if messages.is_empty() {
+        println!("(no messages)");
+        return Ok(());
+    }
+
+    for message in &messages {
+        let label = if message.is_summary {
+            "summary".to_string()
+        } else {
+            message.role.to_string()
+        };
+        let first_line = message.content.lines().next().unwrap_or("");
+        let preview = if first_line.chars().count() > 60 || message.content.contains('\n') {
+            format!("{}...", first_line.chars().take(57).collect::<String>())
+        } else {
+            first_line.to_string()
+        };
+
+        let preview = match message.summarized_by {
+            Some(summary_id) => format!("{}  (summarized in {})", preview, summary_id),
+            None => preview,
+        };
+        let preview = if message.pinned {
+            format!("{}  (pinned)", preview)
+        } else {
+            preview
+        };
+
+        println!(
+            "{:>6}  {}  {:<9} {}",
+            message.id,
+            message.created_at.format("%Y-%m-%d %H:%M"),
+            label,
+            preview
+        );
+    }
+
+    println!();
+    println!("{}", context_usage(config, &messages));
+
+    Ok(())
+}
+
+/// Describe how much of the answering model's context window the messages
+/// not yet summarized take up.
+fn context_usage(config: &Config, messages: &[StoredMessage]) -> String {
+    let active: Vec<StoredMessage> = messages
+        .iter()
+        .filter(|m| m.summarized_by.is_none())
+        .cloned()
+        .collect();
+
+    // Only the model details are needed, so no API key is read
+    let provider = ProviderFactory::from_config(config)
+        .ok()
+        .and_then(|factory| {
+            super::chat::selected_provider_name(&factory, None)
+                .ok()
+                .and_then(|name| factory.get_unkeyed(&name))
+                .map(|provider| {
+                    let tokens = estimate_tokens(
+                        &active,
+                        Some(provider.provider_id()),
+                        provider.tokenizer(None),
+                    );
+                    let budget = ContextBudget::new(provider.context_window(None));
+                    (tokens, budget.history_tokens(), provider.provider_id())
+                })
+        });
+
+    match provider {
+        Some((tokens, available, provider_id)) => format!(
+            "Context: {} of {} tokens ({}%, {})",
+            tokens,
+            available,
+            (tokens * 100).checked_div(available).unwrap_or(100),
+            provider_id
+        ),
+        None => format!(
+            "Context: ~{} tokens",
+            estimate_tokens(&active, None, Tokenizer::Heuristic)
+        ),
+    }
+}
+
+/// Fork a session into a new one, optionally from an earlier message.
+///
+/// The fork becomes the most recent session in its directory, so the next
+/// chat there continues the fork rather than the original.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `target` - Session ID, title or tag
+/// * `at_message_id` - Last message to copy (from `session show`), or all
+///
+/// # Returns
+///
+/// The ID of the new session.
+pub async fn fork(db: &Database, target: &str, at_message_id: Option<i64>) -> Result<String> {
+    let session = find(db, target).await?;
+
+    let fork_id = fork_session(db, &session.id, at_message_id)
+        .await
+        .context("Failed to fork session")?;
+    let copied = get_messages(db, &fork_id)
+        .await
+        .context("Failed to load forked session")?
+        .len();
+
+    println!(
+        "Forked session {} as {} ({} message(s) copied)",
+        session.id, fork_id, copied
+    );
+    Ok(fork_id)
+}
+
+/// Look up a session by ID, falling back to title or tag.
+async fn find(db: &Database, target: &str) -> Result<Session> {
+    let session = if is_valid_session_id(target) {
+        get_session(db, target)
+            .await
+            .context("Failed to get session")?
+    } else {
+        find_session_by_name(db, target)
+            .await
+            .context("Failed to find session")?
+    };
+
+    session.with_context(|| format!("No session with ID, title or tag '{}'", target))
+}
+
+/// Validate and normalize tags given on the command line.
+fn parse_tags(tags: &[String]) -> Result<Vec<String>> {
+    tags.iter()
+        .map(|t| {
+            normalize_tag(t).with_context(|| {
+                format!(
+                    "Invalid tag '{}': tags cannot be empty or contain spaces or commas",
+                    t
+                )
+            })
+        })
+        .collect()
+}
+
+/// Order sessions as a tree: each fork directly after its parent.
+///
+/// Returns `(index, depth)` pairs. Sessions whose parent is not in the list
+/// are shown as roots; roots and siblings keep their original order.
+fn tree_order(sessions: &[SessionInfo]) -> Vec<(usize, usize)> {
+    let ids: HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for (index, session) in sessions.iter().enumerate() {
+        match session.parent_session_id.as_deref() {
+            Some(parent) if ids.contains(parent) && parent != session.id => {
+                children.entry(parent).or_default().push(index);
+            }
+            _ => roots.push(index),
+        }
+    }
+
+    let mut order = Vec::with_capacity(sessions.len());
+    let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|i| (i, 0)).collect();
+    while let Some((index, depth)) = stack.pop() {
+        order.push((index, depth));
+        if let Some(kids) = children.get(sessions[index].id.as_str()) {
+            stack.extend(kids.iter().rev().map(|&i| (i, depth + 1)));
+        }
+    }
+
+    order
+}
+
+/// Format tags for display as ` #a #b`, or an empty string.
+fn format_tags(tags: &[String]) -> String {
+    tags.iter().map(|t| format!(" #{}", t)).collect()
+}
+
+/// Describe a session as its ID followed by its title, if any.
+fn describe(session: &Session) -> String {
+    match &session.title {
+        Some(title) => format!("{} ({})", session.id, title),
+        None => session.id.clone(),
+    }
+}
+
 /// Create a new session in the current directory.
 ///
 /// # Arguments
@@ -246,5 +592,138 @@ mod tests {
 
             assert!(result.is_err());
         }
+
+        #[tokio::test]
+        async fn resumes_by_title_or_tag() {
+            let (db, temp_dir) = setup_db().await;
+            let working_dir = temp_dir.path();
+            let created_id = new_session(&db, working_dir).await.unwrap();
+
+            rename(&db, &created_id, "Deploy fixes").await.unwrap();
+            tag(&db, &created_id, &["Infra".to_string()], false)
+                .await
+                .unwrap();
+
+            let by_title = resume(&db, Some("deploy fixes"), false, working_dir)
+                .await
+                .unwrap();
+            let by_tag = resume(&db, Some("infra"), false, working_dir)
+                .await
+                .unwrap();
+
+            assert_eq!(by_title, Some(created_id.clone()));
+            assert_eq!(by_tag, Some(created_id));
+        }
+    }
+
+    mod tree_order {
+        use super::*;
+        use chrono::Utc;
+
+        fn info(id: &str, parent: Option<&str>) -> SessionInfo {
+            SessionInfo {
+                id: id.to_string(),
+                created_at: Utc::now(),
+                last_message_at: Utc::now(),
+                first_message_preview: None,
+                title: None,
+                tags: Vec::new(),
+                pinned: false,
+                parent_session_id: parent.map(str::to_string),
+            }
+        }
+
+        #[test]
+        fn places_forks_under_parents() {
+            let sessions = vec![
+                info("fork-b", Some("root")),
+                info("other", None),
+                info("root", None),
+                info("fork-a", Some("root")),
+                info("nested", Some("fork-a")),
+            ];
+
+            let order: Vec<(&str, usize)> = tree_order(&sessions)
+                .into_iter()
+                .map(|(i, depth)| (sessions[i].id.as_str(), depth))
+                .collect();
+
+            assert_eq!(
+                order,
+                vec![
+                    ("other", 0),
+                    ("root", 0),
+                    ("fork-b", 1),
+                    ("fork-a", 1),
+                    ("nested", 2),
+                ]
+            );
+        }
+
+        #[test]
+        fn missing_parent_is_a_root() {
+            let sessions = vec![info("orphan", Some("gone"))];
+
+            assert_eq!(tree_order(&sessions), vec![(0, 0)]);
+        }
+    }
+
+    mod fork {
+        use super::*;
+        use cherry2k_core::provider::Role;
+        use cherry2k_storage::message::save_message;
+
+        #[tokio::test]
+        async fn fork_becomes_the_resumed_session() {
+            let (db, temp_dir) = setup_db().await;
+            let working_dir = temp_dir.path();
+            let parent = new_session(&db, working_dir).await.unwrap();
+            let first = save_message(&db, &parent, Role::User, "hi", None)
+                .await
+                .unwrap();
+            save_message(&db, &parent, Role::Assistant, "hello", None)
+                .await
+                .unwrap();
+
+            let fork_id = fork(&db, &parent, Some(first)).await.unwrap();
+
+            assert_eq!(get_messages(&db, &fork_id).await.unwrap().len(), 1);
+            let resumed = resume(&db, None, false, working_dir).await.unwrap();
+            assert_eq!(resumed, Some(fork_id));
+        }
+    }
+
+    mod labels {
+        use super::*;
+
+        #[tokio::test]
+        async fn pin_and_unpin() {
+            let (db, temp_dir) = setup_db().await;
+            let id = new_session(&db, temp_dir.path()).await.unwrap();
+
+            pin(&db, &id, true).await.unwrap();
+            assert!(get_session(&db, &id).await.unwrap().unwrap().pinned);
+
+            pin(&db, &id, false).await.unwrap();
+            assert!(!get_session(&db, &id).await.unwrap().unwrap().pinned);
+        }
+
+        #[tokio::test]
+        async fn rejects_invalid_tags() {
+            let (db, temp_dir) = setup_db().await;
+            let id = new_session(&db, temp_dir.path()).await.unwrap();
+
+            let result = tag(&db, &id, &["two words".to_string()], false).await;
+
+            assert!(result.is_err());
+        }
+
+        #[tokio::test]
+        async fn rejects_empty_title() {
+            let (db, temp_dir) = setup_db().await;
+            let id = new_session(&db, temp_dir.path()).await.unwrap();
+
+            assert!(rename(&db, &id, "   ").await.is_err());
+        }
     }
 }
@@ -0,0 +1,373 @@
+//! Session export and import commands.
+//!
+//! Provides commands for sharing and moving conversation history:
+//! - `export`: Write one or all sessions as Markdown, JSON or JSON Lines
+//! - `import`: Recreate sessions from a JSON or JSON Lines export
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use cherry2k_core::provider::Role;
+use cherry2k_storage::transfer::{export_all_sessions, export_session, import_session};
+use cherry2k_storage::{Database, SessionTranscript, is_valid_session_id};
+
+/// Export file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Human-readable Markdown transcript
+    Markdown,
+    /// Pretty-printed JSON array of sessions
+    Json,
+    /// One JSON session per line
+    JsonLines,
+}
+
+impl ExportFormat {
+    /// Parse a format name given on the command line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for unknown format names.
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::JsonLines),
+            _ => bail!("Unknown export format '{}': expected md, json or jsonl", s),
+        }
+    }
+}
+
+/// Export one or all sessions.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to export, or `None` to export every session
+/// * `format` - The output format
+/// * `output` - File to write to, or `None` for stdout
+pub async fn export(
+    db: &Database,
+    session_id: Option<&str>,
+    format: ExportFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let transcripts = match session_id {
+        Some(id) => {
+            // Validate session ID format to prevent malformed input
+            if !is_valid_session_id(id) {
+                bail!("Invalid session ID format: {}", id);
+            }
+            vec![
+                export_session(db, id)
+                    .await
+                    .context("Failed to export session")?,
+            ]
+        }
+        None => export_all_sessions(db)
+            .await
+            .context("Failed to export sessions")?,
+    };
+
+    let rendered = render(&transcripts, format)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "Exported {} session(s) to {}",
+                transcripts.len(),
+                path.display()
+            );
+        }
+        None => io::stdout().write_all(rendered.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// Import sessions from a JSON or JSON Lines export.
+///
+/// Sessions whose ID already exists are imported under a new ID.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `path` - The export file to read
+pub async fn import(db: &Database, path: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let transcripts =
+        parse(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    if transcripts.is_empty() {
+        println!("No sessions found in {}", path.display());
+        return Ok(());
+    }
+
+    for transcript in transcripts {
+        let imported = import_session(db, transcript)
+            .await
+            .context("Failed to import session")?;
+
+        if imported.was_renamed() {
+            println!(
+                "Imported session {} as {} ({} messages, original ID already in use)",
+                imported.original_id, imported.id, imported.message_count
+            );
+        } else {
+            println!(
+                "Imported session {} ({} messages)",
+                imported.id, imported.message_count
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Render transcripts in the requested format.
+fn render(transcripts: &[SessionTranscript], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(transcripts)),
+        ExportFormat::Json => {
+            let mut json = serde_json::to_string_pretty(transcripts)?;
+            json.push('\n');
+            Ok(json)
+        }
+        ExportFormat::JsonLines => {
+            let mut lines = String::new();
+            for transcript in transcripts {
+                lines.push_str(&serde_json::to_string(transcript)?);
+                lines.push('\n');
+            }
+            Ok(lines)
+        }
+    }
+}
+
+/// Render transcripts as Markdown, one section per session.
+fn render_markdown(transcripts: &[SessionTranscript]) -> String {
+    let mut out = String::new();
+
+    for (i, transcript) in transcripts.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\n---\n\n");
+        }
+
+        let _ = writeln!(out, "# Session {}", transcript.id);
+        out.push('\n');
+        if let Some(title) = &transcript.title {
+            let _ = writeln!(out, "- **Title:** {}", title);
+        }
+        if !transcript.tags.is_empty() {
+            let _ = writeln!(out, "- **Tags:** {}", transcript.tags.join(", "));
+        }
+        let _ = writeln!(out, "- **Directory:** `{}`", transcript.working_dir);
+        let _ = writeln!(
+            out,
+            "- **Started:** {}",
+            transcript.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        let _ = writeln!(
+            out,
+            "- **Last message:** {}",
+            transcript.last_message_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+
+        for message in &transcript.messages {
+            let heading = if message.is_summary {
+                "Summary of earlier conversation".to_string()
+            } else {
+                let role = match message.role {
+                    Role::System => "System",
+                    Role::User => "User",
+                    Role::Assistant => "Assistant",
+                };
+                match &message.provider {
+                    Some(provider) => format!("{} ({})", role, provider),
+                    None => role.to_string(),
+                }
+            };
+
+            out.push('\n');
+            let _ = writeln!(
+                out,
+                "## {} — {}",
+                heading,
+                message.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            out.push('\n');
+            out.push_str(message.content.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Parse a JSON array, a single JSON session, or JSON Lines.
+fn parse(content: &str) -> Result<Vec<SessionTranscript>> {
+    let trimmed = content.trim_start();
+
+    if trimmed.starts_with('#') {
+        bail!("Markdown exports cannot be imported; export with --format json or jsonl");
+    }
+
+    if trimmed.starts_with('[') {
+        return Ok(serde_json::from_str(trimmed)?);
+    }
+
+    // A single pretty-printed session spans several lines, so try it whole first
+    if let Ok(transcript) = serde_json::from_str::<SessionTranscript>(trimmed) {
+        return Ok(vec![transcript]);
+    }
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("Invalid session on line {}", i + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cherry2k_storage::TranscriptMessage;
+    use chrono::{TimeZone, Utc};
+
+    fn transcript(id: &str) -> SessionTranscript {
+        let at = Utc.with_ymd_and_hms(2026, 2, 1, 9, 30, 0).unwrap();
+        SessionTranscript {
+            id: id.to_string(),
+            working_dir: "/projects/demo".to_string(),
+            created_at: at,
+            last_message_at: at,
+            title: Some("Running tests".to_string()),
+            tags: vec!["ci".to_string(), "rust".to_string()],
+            pinned: false,
+            parent_session_id: None,
+            forked_from_message: None,
+            messages: vec![
+                TranscriptMessage {
+                    role: Role::System,
+                    content: "Earlier: set up CI".to_string(),
+                    is_summary: true,
+                    provider: None,
+                    token_count: None,
+                    summarized_by: None,
+                    pinned: false,
+                    created_at: at,
+                },
+                TranscriptMessage {
+                    role: Role::User,
+                    content: "How do I run tests?".to_string(),
+                    is_summary: false,
+                    provider: Some("anthropic".to_string()),
+                    token_count: None,
+                    summarized_by: None,
+                    pinned: false,
+                    created_at: at,
+                },
+            ],
+        }
+    }
+
+    mod export_format {
+        use super::*;
+
+        #[test]
+        fn parses_known_formats() {
+            assert_eq!(ExportFormat::parse("md").unwrap(), ExportFormat::Markdown);
+            assert_eq!(ExportFormat::parse("json").unwrap(), ExportFormat::Json);
+            assert_eq!(
+                ExportFormat::parse("jsonl").unwrap(),
+                ExportFormat::JsonLines
+            );
+        }
+
+        #[test]
+        fn rejects_unknown_format() {
+            assert!(ExportFormat::parse("html").is_err());
+        }
+    }
+
+    mod render_markdown {
+        use super::*;
+
+        #[test]
+        fn includes_metadata_and_messages() {
+            let md = render_markdown(&[transcript("2026-02-01-0930-000-aaaa")]);
+
+            assert!(md.starts_with("# Session 2026-02-01-0930-000-aaaa\n"));
+            assert!(md.contains("- **Title:** Running tests"));
+            assert!(md.contains("- **Tags:** ci, rust"));
+            assert!(md.contains("- **Directory:** `/projects/demo`"));
+            assert!(md.contains("## Summary of earlier conversation — 2026-02-01 09:30:00 UTC"));
+            assert!(md.contains("## User (anthropic) — 2026-02-01 09:30:00 UTC"));
+            assert!(md.contains("How do I run tests?"));
+        }
+
+        #[test]
+        fn separates_sessions() {
+            let md = render_markdown(&[
+                transcript("2026-02-01-0930-000-aaaa"),
+                transcript("2026-02-01-0930-000-bbbb"),
+            ]);
+
+            assert_eq!(md.matches("\n---\n").count(), 1);
+        }
+    }
+
+    mod parse {
+        use super::*;
+
+        #[test]
+        fn round_trips_json() {
+            let sessions = vec![transcript("2026-02-01-0930-000-aaaa")];
+            let json = render(&sessions, ExportFormat::Json).unwrap();
+
+            assert_eq!(parse(&json).unwrap(), sessions);
+        }
+
+        #[test]
+        fn round_trips_json_lines() {
+            let sessions = vec![
+                transcript("2026-02-01-0930-000-aaaa"),
+                transcript("2026-02-01-0930-000-bbbb"),
+            ];
+            let jsonl = render(&sessions, ExportFormat::JsonLines).unwrap();
+
+            assert_eq!(parse(&jsonl).unwrap(), sessions);
+        }
+
+        #[test]
+        fn accepts_single_session_object() {
+            let json =
+                serde_json::to_string_pretty(&transcript("2026-02-01-0930-000-aaaa")).unwrap();
+
+            assert_eq!(parse(&json).unwrap().len(), 1);
+        }
+
+        #[test]
+        fn rejects_markdown() {
+            let md = render_markdown(&[transcript("2026-02-01-0930-000-aaaa")]);
+
+            assert!(parse(&md).is_err());
+        }
+
+        #[test]
+        fn reports_bad_line() {
+            let err = parse("{\"id\": 1}\n").unwrap_err();
+
+            assert!(format!("{err:#}").contains("line 1"));
+        }
+    }
+}
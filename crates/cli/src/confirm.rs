@@ -19,6 +19,8 @@ pub enum ConfirmResult {
     No,
     /// User wants to edit before confirming
     Edit,
+    /// User wants the command placed on the shell prompt instead of run
+    Insert,
 }
 
 /// Prompt the user for confirmation.
@@ -28,6 +30,8 @@ pub enum ConfirmResult {
 /// - n/N/no -> ConfirmResult::No
 /// - e/E/edit -> ConfirmResult::Edit
 ///
+/// Never returns [`ConfirmResult::Insert`]; see [`confirm_command`] for that.
+///
 /// # Arguments
 /// * `prompt` - The question to ask
 /// * `allow_edit` - Whether to show the (e)dit option
@@ -41,10 +45,21 @@ pub enum ConfirmResult {
 ///     ConfirmResult::Yes => println!("Proceeding..."),
 ///     ConfirmResult::No => println!("Cancelled."),
 ///     ConfirmResult::Edit => println!("Editing..."),
+///     ConfirmResult::Insert => unreachable!("insert is only offered for commands"),
 /// }
 /// ```
 pub fn confirm(prompt: &str, allow_edit: bool) -> io::Result<ConfirmResult> {
-    let options = if allow_edit { "[y/n/e]" } else { "[y/n]" };
+    prompt_choice(prompt, allow_edit, false)
+}
+
+/// Shared prompt loop for [`confirm`] and [`confirm_command`].
+fn prompt_choice(prompt: &str, allow_edit: bool, allow_insert: bool) -> io::Result<ConfirmResult> {
+    let options = match (allow_edit, allow_insert) {
+        (true, true) => "[y/n/e/i]",
+        (true, false) => "[y/n/e]",
+        (false, true) => "[y/n/i]",
+        (false, false) => "[y/n]",
+    };
 
     for _ in 0..MAX_RETRIES {
         print!("{} {} ", prompt, options);
@@ -58,17 +73,12 @@ pub fn confirm(prompt: &str, allow_edit: bool) -> io::Result<ConfirmResult> {
             "y" | "yes" => return Ok(ConfirmResult::Yes),
             "n" | "no" => return Ok(ConfirmResult::No),
             "e" | "edit" if allow_edit => return Ok(ConfirmResult::Edit),
+            "i" | "insert" if allow_insert => return Ok(ConfirmResult::Insert),
             "" => {
                 // Empty input defaults to No for safety
                 return Ok(ConfirmResult::No);
             }
-            _ => {
-                if allow_edit {
-                    eprintln!("Please enter 'y' for yes, 'n' for no, or 'e' to edit.");
-                } else {
-                    eprintln!("Please enter 'y' for yes or 'n' for no.");
-                }
-            }
+            _ => eprintln!("{}", invalid_input_hint(allow_edit, allow_insert)),
         }
     }
 
@@ -77,16 +87,36 @@ pub fn confirm(prompt: &str, allow_edit: bool) -> io::Result<ConfirmResult> {
     Ok(ConfirmResult::No)
 }
 
+/// Build the hint shown after an unrecognised answer.
+fn invalid_input_hint(allow_edit: bool, allow_insert: bool) -> String {
+    let mut choices = vec!["'y' for yes", "'n' for no"];
+    if allow_edit {
+        choices.push("'e' to edit");
+    }
+    if allow_insert {
+        choices.push("'i' to insert into your prompt");
+    }
+    let last = choices.pop().unwrap_or_default();
+    let separator = if choices.len() > 1 { ", or" } else { " or" };
+    format!("Please enter {}{} {}.", choices.join(", "), separator, last)
+}
+
 /// Confirm a potentially dangerous command before execution.
 ///
 /// Displays the command and asks for confirmation.
-/// Returns the user's choice (Yes, No, or Edit).
-pub fn confirm_command(command: &str) -> io::Result<ConfirmResult> {
+/// Returns the user's choice (Yes, No, Edit, or Insert).
+///
+/// # Arguments
+/// * `command` - The command to confirm
+/// * `allow_insert` - Whether to show the (i)nsert option, which places the
+///   command on the user's shell prompt instead of running it. Only offered
+///   when the zsh widget has provided an injection file.
+pub fn confirm_command(command: &str, allow_insert: bool) -> io::Result<ConfirmResult> {
     println!();
     println!("Suggested command:");
     println!("  {}", command);
     println!();
-    confirm("Run this?", true)
+    prompt_choice("Run this?", true, allow_insert)
 }
 
 /// Prompt user to edit a command.
@@ -182,5 +212,19 @@ mod tests {
         assert_eq!(ConfirmResult::No, ConfirmResult::No);
         assert_eq!(ConfirmResult::Edit, ConfirmResult::Edit);
         assert_ne!(ConfirmResult::Yes, ConfirmResult::No);
+        assert_ne!(ConfirmResult::Insert, ConfirmResult::Yes);
+    }
+
+    #[test]
+    fn test_invalid_input_hint_lists_choices() {
+        assert_eq!(
+            invalid_input_hint(false, false),
+            "Please enter 'y' for yes or 'n' for no."
+        );
+        assert_eq!(
+            invalid_input_hint(true, false),
+            "Please enter 'y' for yes, 'n' for no, or 'e' to edit."
+        );
+        assert!(invalid_input_hint(true, true).ends_with("or 'i' to insert into your prompt."));
     }
 }
@@ -0,0 +1,84 @@
+//! Command injection into the user's zsh prompt.
+//!
+//! Instead of running a suggested command itself, cherry2k can hand the final
+//! command back to the zsh widget, which pushes it onto the editing buffer
+//! with `print -z`. The command then runs in the user's real shell, with their
+//! aliases, functions and environment, and is recorded in `$HISTFILE` like any
+//! other command once the user presses Enter.
+//!
+//! The widget creates an empty temp file, passes its path via
+//! `--inject-file`, and reads it back after cherry2k exits. An empty file
+//! means nothing should be inserted.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Write a command to the injection file for the zsh widget to pick up.
+///
+/// The command is written verbatim (no trailing newline) so the widget can
+/// place it into `BUFFER` exactly as shown to the user.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+/// use cherry2k::execute::inject_command;
+///
+/// inject_command(Path::new("/tmp/cherry2k-inject"), "ls -la").unwrap();
+/// ```
+pub fn inject_command(path: &Path, command: &str) -> io::Result<()> {
+    fs::write(path, command.trim_end_matches(['\n', '\r']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn writes_command_to_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("inject");
+
+        inject_command(&path, "git status").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "git status");
+    }
+
+    #[test]
+    fn strips_trailing_newlines() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("inject");
+
+        inject_command(&path, "echo hi\n\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "echo hi");
+    }
+
+    #[test]
+    fn preserves_multiline_commands() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("inject");
+        let command = "for f in *.txt; do\n  echo $f\ndone";
+
+        inject_command(&path, command).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), command);
+    }
+
+    #[test]
+    fn overwrites_previous_content() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("inject");
+        fs::write(&path, "old command").unwrap();
+
+        inject_command(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+}
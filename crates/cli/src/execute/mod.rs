@@ -10,6 +10,7 @@
 //! - Streams stderr line-by-line in red
 //! - Forwards Ctrl+C to child process via SIGINT
 //! - Uses `kill_on_drop(true)` for cleanup safety
+//! - Can hand commands back to the zsh widget instead of running them
 //!
 //! # Example
 //!
@@ -22,8 +23,10 @@
 //! }
 //! ```
 
+mod inject;
 mod output;
 mod runner;
 
+pub use inject::inject_command;
 pub use output::display_exit_status;
 pub use runner::{CommandResult, execute_command};
@@ -0,0 +1,146 @@
+//! Per-project standing instructions
+//!
+//! A project can keep instructions for every conversation in
+//! `.cherry2k/instructions.md` at its root (e.g. "this repo uses nightly
+//! Rust, never suggest sudo"). The root is found with [`find_project_root`];
+//! outside a git repository the current directory is used.
+//!
+//! The instructions are sent as a system message with each request, so they
+//! are never summarized away.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::reader::{FileReader, ReadResult};
+use super::scope::find_project_root;
+
+/// Location of the instructions file, relative to the project root
+pub const INSTRUCTIONS_FILE: &str = ".cherry2k/instructions.md";
+
+/// Standing instructions loaded from a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectInstructions {
+    /// The instructions file
+    pub path: PathBuf,
+    /// The instructions, trimmed
+    pub content: String,
+}
+
+impl ProjectInstructions {
+    /// Format the instructions as a system prompt.
+    pub fn to_system_prompt(&self) -> String {
+        format!(
+            "Standing instructions for this project (from {}). Always follow them:\n\n{}",
+            self.path.display(),
+            self.content
+        )
+    }
+}
+
+/// Load the instructions of the project containing `start_path`.
+///
+/// # Arguments
+///
+/// * `start_path` - Directory to start from (typically cwd)
+///
+/// # Returns
+///
+/// The instructions, or `None` if the project has no instructions file or
+/// it is empty.
+///
+/// # Errors
+///
+/// Returns an error if the file exists but cannot be read, is binary or is
+/// too large.
+pub fn load_instructions(start_path: &Path) -> io::Result<Option<ProjectInstructions>> {
+    let root = find_project_root(start_path).unwrap_or_else(|| start_path.to_path_buf());
+    let path = root.join(INSTRUCTIONS_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let content = match FileReader::read_file(&path)? {
+        ReadResult::Content(content) => content,
+        ReadResult::TooLarge { size, .. } => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is too large ({} bytes)", path.display(), size),
+            ));
+        }
+        ReadResult::Binary { .. } => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a text file", path.display()),
+            ));
+        }
+        ReadResult::Error { error, .. } => return Err(io::Error::other(error)),
+    };
+
+    let content = content.trim();
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(ProjectInstructions {
+        path,
+        content: content.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_instructions(root: &Path, content: &str) {
+        fs::create_dir_all(root.join(".cherry2k")).unwrap();
+        fs::write(root.join(INSTRUCTIONS_FILE), content).unwrap();
+    }
+
+    #[test]
+    fn finds_instructions_at_repository_root() {
+        let temp = TempDir::new().unwrap();
+        git2::Repository::init(temp.path()).unwrap();
+        write_instructions(temp.path(), "Use nightly Rust.\n");
+        let subdir = temp.path().join("src");
+        fs::create_dir(&subdir).unwrap();
+
+        let instructions = load_instructions(&subdir).unwrap().unwrap();
+        assert_eq!(instructions.content, "Use nightly Rust.");
+        assert!(instructions.path.ends_with(INSTRUCTIONS_FILE));
+    }
+
+    #[test]
+    fn falls_back_to_start_directory_outside_git() {
+        let temp = TempDir::new().unwrap();
+        write_instructions(temp.path(), "Never suggest sudo.");
+
+        let instructions = load_instructions(temp.path()).unwrap().unwrap();
+        assert_eq!(instructions.content, "Never suggest sudo.");
+    }
+
+    #[test]
+    fn missing_file_is_none() {
+        let temp = TempDir::new().unwrap();
+        assert!(load_instructions(temp.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_file_is_none() {
+        let temp = TempDir::new().unwrap();
+        write_instructions(temp.path(), "  \n\n");
+        assert!(load_instructions(temp.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn system_prompt_includes_content() {
+        let instructions = ProjectInstructions {
+            path: PathBuf::from("/repo/.cherry2k/instructions.md"),
+            content: "Use nightly Rust.".to_string(),
+        };
+        let prompt = instructions.to_system_prompt();
+        assert!(prompt.contains("/repo/.cherry2k/instructions.md"));
+        assert!(prompt.ends_with("Use nightly Rust."));
+    }
+}
@@ -7,23 +7,32 @@
 //! - [`detector`] - Detect file references in user messages
 //! - [`reader`] - Safe file reading with size and binary checks
 //! - [`diff`] - Unified diff generation with colored output
+//! - [`instructions`] - Per-project standing instructions
 //! - [`writer`] - File writing with approval flow
+//! - [`transaction`] - All-or-nothing writes across several files
 //! - [`proposal`] - Extract file write proposals from AI responses
+//! - [`patch`] - Apply diff and search/replace edits from AI responses
 //! - [`scope`] - Project scope detection and validation
 //! - [`security`] - Secrets detection and path validation
 
 mod detector;
 mod diff;
+mod instructions;
+mod patch;
 mod proposal;
 mod reader;
 mod scope;
 mod security;
+mod transaction;
 mod writer;
 
 pub use detector::{detect_file_references, is_file_reference};
 pub use diff::{display_new_file_preview, generate_diff, has_changes};
+pub use instructions::{INSTRUCTIONS_FILE, ProjectInstructions, load_instructions};
+pub use patch::{EditHunk, FileEdit, HunkLine, extract_file_edits};
 pub use proposal::{extract_file_proposals, FileProposal};
 pub use reader::{FileReader, ReadResult};
 pub use scope::{find_project_root, ProjectScope};
 pub use security::{is_secrets_file, validate_write_path, ValidationResult};
+pub use transaction::WriteTransaction;
 pub use writer::{write_file_with_approval, write_multiple_files, WriteResult};
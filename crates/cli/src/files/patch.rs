@@ -0,0 +1,827 @@
+//! Patch-style file edits in AI responses
+//!
+//! Rewriting a whole file is slow, expensive and gets truncated on large
+//! files, so the AI may instead send only the changed parts, in either of
+//! two formats:
+//! - Unified diffs in a fenced code block (`--- a/path` / `+++ b/path`)
+//! - Search/replace blocks, preceded by the file path:
+//!
+//! ```text
+//! src/main.rs
+//! <<<<<<< SEARCH
+//! lines from the current file
+//! =======
+//! replacement lines
+//! >>>>>>> REPLACE
+//! ```
+//!
+//! An edit is applied to the current file content to produce a regular
+//! [`FileProposal`], so it is previewed and written like a whole file.
+//! Each hunk is located exactly first, then ignoring trailing whitespace,
+//! then ignoring indentation, and finally with up to two lines of diff
+//! context dropped from either end. If a hunk still doesn't match, or a
+//! search block without line numbers matches in more than one place, the
+//! edit fails and the file is left alone.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+
+use super::proposal::{FileProposal, resolve_path};
+
+/// Most context lines dropped from each end of a hunk that doesn't match
+const MAX_CONTEXT_FUZZ: usize = 2;
+
+/// Lines of a failed hunk shown in the error
+const MAX_ERROR_LINES: usize = 5;
+
+static FENCED_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)```[^\n]*\n(.*?)```").unwrap());
+
+static HUNK_HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^@@\s*-(\d+)(?:,\d+)?").unwrap());
+
+static SEARCH_START: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^<{5,9} SEARCH\s*$").unwrap());
+
+static SEARCH_DIVIDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^={5,9}\s*$").unwrap());
+
+static REPLACE_END: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^>{5,9} REPLACE\s*$").unwrap());
+
+/// Edits to one file extracted from an AI response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEdit {
+    /// Target file path (relative or absolute)
+    pub path: PathBuf,
+    /// Changes to apply, in order
+    pub hunks: Vec<EditHunk>,
+}
+
+/// One change within a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditHunk {
+    /// Lines of the hunk, in order
+    pub lines: Vec<HunkLine>,
+    /// Line (1-based) the hunk starts at in the original file, if known
+    pub line_hint: Option<usize>,
+}
+
+/// A line of an [`EditHunk`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    /// Unchanged line that locates the hunk
+    Context(String),
+    /// Line removed from the file
+    Remove(String),
+    /// Line added to the file
+    Add(String),
+}
+
+impl HunkLine {
+    fn text(&self) -> &str {
+        match self {
+            Self::Context(text) | Self::Remove(text) | Self::Add(text) => text,
+        }
+    }
+}
+
+impl FileEdit {
+    /// Apply the edit to the current content of the file.
+    ///
+    /// A missing file is treated as empty, so an edit can create a file.
+    ///
+    /// # Returns
+    ///
+    /// A proposal with the complete new content of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or a hunk doesn't match it.
+    pub fn apply(&self) -> Result<FileProposal> {
+        let original = match fs::read_to_string(&self.path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()));
+            }
+        };
+
+        let content = apply_hunks(original.as_deref().unwrap_or_default(), &self.hunks)
+            .with_context(|| format!("Could not apply edit to {}", self.path.display()))?;
+
+        Ok(FileProposal {
+            path: self.path.clone(),
+            content,
+            is_new: original.is_none(),
+        })
+    }
+}
+
+/// Extract patch-style edits from an AI response.
+///
+/// Finds unified diffs in fenced code blocks and search/replace blocks.
+/// Several edits to the same file are merged, in the order they appear.
+///
+/// # Arguments
+/// * `response` - The AI response text to parse
+/// * `cwd` - Current working directory for resolving relative paths
+///
+/// # Returns
+/// One edit per file, in order of first appearance
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use cherry2k::files::extract_file_edits;
+///
+/// let response = "src/main.rs\n<<<<<<< SEARCH\nold\n=======\nnew\n>>>>>>> REPLACE\n";
+///
+/// let edits = extract_file_edits(response, Path::new("/project"));
+/// assert_eq!(edits.len(), 1);
+/// assert_eq!(edits[0].path, Path::new("/project/src/main.rs"));
+/// ```
+pub fn extract_file_edits(response: &str, cwd: &Path) -> Vec<FileEdit> {
+    let mut edits: Vec<FileEdit> = Vec::new();
+    let found = extract_unified_diffs(response, cwd)
+        .into_iter()
+        .chain(extract_search_replace(response, cwd));
+
+    for edit in found {
+        match edits.iter_mut().find(|existing| existing.path == edit.path) {
+            Some(existing) => existing.hunks.extend(edit.hunks),
+            None => edits.push(edit),
+        }
+    }
+
+    edits
+}
+
+/// Whether a code block holds a patch rather than a whole file.
+pub(super) fn is_edit_block(content: &str) -> bool {
+    SEARCH_START.is_match(content) || looks_like_diff(content)
+}
+
+/// Apply hunks to file content.
+///
+/// Line endings and the trailing newline of the original are kept.
+fn apply_hunks(original: &str, hunks: &[EditHunk]) -> Result<String> {
+    let newline = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    // Lines added minus lines removed by earlier hunks, to adjust line hints
+    let mut offset: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let hint = hunk
+            .line_hint
+            .map(|line| (line.saturating_sub(1) as isize + offset).max(0) as usize);
+
+        let found = match locate(&lines, hunk, hint) {
+            Lookup::Found(found) => found,
+            Lookup::Ambiguous(starts) => {
+                bail!("{}", ambiguous_message(hunk, &starts, index, hunks.len()))
+            }
+            Lookup::NotFound => bail!("{}", mismatch_message(hunk, index, hunks.len())),
+        };
+
+        let added = found.replacement.len();
+        offset += added as isize - found.len as isize;
+        lines.splice(found.start..found.start + found.len, found.replacement);
+    }
+
+    let mut content = lines.join(newline);
+    if !content.is_empty() && (original.is_empty() || original.ends_with('\n')) {
+        content.push_str(newline);
+    }
+    Ok(content)
+}
+
+/// Where a hunk applies and what replaces the matched lines
+struct Located {
+    start: usize,
+    len: usize,
+    replacement: Vec<String>,
+}
+
+/// Outcome of looking for a hunk in the file
+enum Lookup {
+    Found(Located),
+    /// Several places match and there is no line hint to choose between
+    /// them; holds the 0-based start of each
+    Ambiguous(Vec<usize>),
+    NotFound,
+}
+
+/// How closely hunk lines must match file lines
+#[derive(Debug, Clone, Copy)]
+enum Tolerance {
+    Exact,
+    TrailingWhitespace,
+    Indentation,
+}
+
+impl Tolerance {
+    fn matches(self, file_line: &str, hunk_line: &str) -> bool {
+        match self {
+            Self::Exact => file_line == hunk_line,
+            Self::TrailingWhitespace => file_line.trim_end() == hunk_line.trim_end(),
+            Self::Indentation => file_line.trim() == hunk_line.trim(),
+        }
+    }
+}
+
+/// Find where a hunk applies, trying looser matches until one is found.
+///
+/// With a line hint the nearest match wins. Without one (search/replace
+/// blocks), the first tolerance that matches must match exactly one place.
+fn locate(lines: &[String], hunk: &EditHunk, hint: Option<usize>) -> Lookup {
+    let leading = hunk
+        .lines
+        .iter()
+        .take_while(|line| matches!(line, HunkLine::Context(_)))
+        .count();
+    let trailing = hunk
+        .lines
+        .iter()
+        .rev()
+        .take_while(|line| matches!(line, HunkLine::Context(_)))
+        .count();
+
+    let mut tried = Vec::new();
+    for fuzz in 0..=MAX_CONTEXT_FUZZ {
+        let front = fuzz.min(leading);
+        let back = fuzz.min(trailing).min(hunk.lines.len() - front);
+        if tried.contains(&(front, back)) {
+            continue;
+        }
+        tried.push((front, back));
+
+        let body = &hunk.lines[front..hunk.lines.len() - back];
+        let old: Vec<&str> = body
+            .iter()
+            .filter(|line| !matches!(line, HunkLine::Add(_)))
+            .map(HunkLine::text)
+            .collect();
+        let hint = hint.map(|line| line + front);
+
+        if old.is_empty() {
+            // Pure insertion, only when the hunk had no context to begin with
+            if fuzz > 0 {
+                continue;
+            }
+            let start = hint.unwrap_or(lines.len()).min(lines.len());
+            return Lookup::Found(Located {
+                start,
+                len: 0,
+                replacement: replacement(body, &[], None),
+            });
+        }
+        if old.len() > lines.len() {
+            continue;
+        }
+
+        for tolerance in [
+            Tolerance::Exact,
+            Tolerance::TrailingWhitespace,
+            Tolerance::Indentation,
+        ] {
+            let candidates: Vec<usize> = (0..=lines.len() - old.len())
+                .filter(|&start| {
+                    old.iter()
+                        .zip(&lines[start..])
+                        .all(|(hunk_line, file_line)| tolerance.matches(file_line, hunk_line))
+                })
+                .collect();
+
+            let chosen = match hint {
+                Some(hint) => candidates.iter().min_by_key(|&&start| start.abs_diff(hint)),
+                None if candidates.len() > 1 => return Lookup::Ambiguous(candidates),
+                None => candidates.first(),
+            };
+
+            if let Some(&start) = chosen {
+                let matched = &lines[start..start + old.len()];
+                let reindent = matches!(tolerance, Tolerance::Indentation)
+                    .then(|| indent_change(&old, matched))
+                    .flatten();
+                return Lookup::Found(Located {
+                    start,
+                    len: old.len(),
+                    replacement: replacement(body, matched, reindent.as_ref()),
+                });
+            }
+        }
+    }
+
+    Lookup::NotFound
+}
+
+/// Build the lines that replace a match.
+///
+/// Context lines are taken from the file, so loosely matched context keeps
+/// its original whitespace. Added lines are re-indented when the hunk was
+/// matched ignoring indentation.
+fn replacement(
+    body: &[HunkLine],
+    matched: &[String],
+    reindent: Option<&IndentChange>,
+) -> Vec<String> {
+    let mut matched = matched.iter();
+    let mut lines = Vec::new();
+
+    for line in body {
+        match line {
+            HunkLine::Context(_) => {
+                if let Some(file_line) = matched.next() {
+                    lines.push(file_line.clone());
+                }
+            }
+            HunkLine::Remove(_) => {
+                matched.next();
+            }
+            HunkLine::Add(text) => lines.push(match reindent {
+                Some(change) => change.apply(text),
+                None => text.clone(),
+            }),
+        }
+    }
+
+    lines
+}
+
+/// Difference between the indentation of a hunk and the file
+enum IndentChange {
+    Add(String),
+    Remove(String),
+}
+
+impl IndentChange {
+    fn apply(&self, line: &str) -> String {
+        if line.trim().is_empty() {
+            return line.to_string();
+        }
+        match self {
+            Self::Add(prefix) => format!("{}{}", prefix, line),
+            Self::Remove(prefix) => line
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(line)
+                .to_string(),
+        }
+    }
+}
+
+/// Compare the indentation of the first non-blank hunk line with the file.
+fn indent_change(old: &[&str], matched: &[String]) -> Option<IndentChange> {
+    let (hunk_line, file_line) = old
+        .iter()
+        .zip(matched)
+        .find(|(hunk_line, _)| !hunk_line.trim().is_empty())?;
+    let hunk_indent = leading_whitespace(hunk_line);
+    let file_indent = leading_whitespace(file_line);
+
+    if let Some(extra) = file_indent.strip_prefix(hunk_indent) {
+        (!extra.is_empty()).then(|| IndentChange::Add(extra.to_string()))
+    } else {
+        hunk_indent
+            .strip_prefix(file_indent)
+            .map(|extra| IndentChange::Remove(extra.to_string()))
+    }
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Describe a hunk that couldn't be located.
+fn mismatch_message(hunk: &EditHunk, index: usize, count: usize) -> String {
+    let expected: Vec<&str> = hunk
+        .lines
+        .iter()
+        .filter(|line| !matches!(line, HunkLine::Add(_)))
+        .map(HunkLine::text)
+        .collect();
+
+    let mut message = format!("hunk {} of {} doesn't match the file", index + 1, count);
+    if let Some(line) = hunk.line_hint {
+        message.push_str(&format!(" (expected near line {})", line));
+    }
+    message.push_str("; looked for:");
+    for line in expected.iter().take(MAX_ERROR_LINES) {
+        message.push_str("\n    ");
+        message.push_str(line);
+    }
+    if expected.len() > MAX_ERROR_LINES {
+        message.push_str(&format!(
+            "\n    ({} more lines)",
+            expected.len() - MAX_ERROR_LINES
+        ));
+    }
+    message
+}
+
+/// Describe a hunk that matches in several places.
+fn ambiguous_message(hunk: &EditHunk, starts: &[usize], index: usize, count: usize) -> String {
+    let lines: Vec<String> = starts.iter().map(|start| (start + 1).to_string()).collect();
+    let first = hunk
+        .lines
+        .iter()
+        .find(|line| !matches!(line, HunkLine::Add(_)))
+        .map(HunkLine::text)
+        .unwrap_or_default();
+
+    format!(
+        "hunk {} of {} is ambiguous: it matches at lines {}; include more surrounding lines \
+         so it matches once (starts with: {})",
+        index + 1,
+        count,
+        lines.join(", "),
+        first.trim()
+    )
+}
+
+/// Whether text contains a unified diff file header.
+fn looks_like_diff(content: &str) -> bool {
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .windows(2)
+        .any(|pair| pair[0].starts_with("--- ") && pair[1].starts_with("+++ "))
+}
+
+/// Extract unified diffs from fenced code blocks.
+fn extract_unified_diffs(response: &str, cwd: &Path) -> Vec<FileEdit> {
+    FENCED_BLOCK
+        .captures_iter(response)
+        .filter_map(|cap| cap.get(1))
+        .filter(|content| looks_like_diff(content.as_str()))
+        .flat_map(|content| parse_unified_diff(content.as_str(), cwd))
+        .collect()
+}
+
+/// Parse a unified diff into one edit per file.
+///
+/// Line counts in hunk headers are ignored, since models often get them
+/// wrong; a hunk ends at the first line that isn't part of it. Deleted
+/// files (`+++ /dev/null`) are skipped.
+fn parse_unified_diff(diff: &str, cwd: &Path) -> Vec<FileEdit> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut edits = Vec::new();
+    let mut edit: Option<FileEdit> = None;
+    let mut hunk: Option<EditHunk> = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        if line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "))
+        {
+            finish_hunk(&mut edit, &mut hunk);
+            edits.extend(edit.take().filter(|edit| !edit.hunks.is_empty()));
+            edit = header_path(&lines[i + 1][4..]).map(|path| FileEdit {
+                path: resolve_path(path, cwd),
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            finish_hunk(&mut edit, &mut hunk);
+            hunk = Some(EditHunk {
+                lines: Vec::new(),
+                line_hint: HUNK_HEADER
+                    .captures(line)
+                    .and_then(|cap| cap[1].parse().ok()),
+            });
+        } else if let Some(current) = hunk.as_mut() {
+            match line.chars().next() {
+                Some(' ') => current.lines.push(HunkLine::Context(line[1..].to_string())),
+                Some('-') => current.lines.push(HunkLine::Remove(line[1..].to_string())),
+                Some('+') => current.lines.push(HunkLine::Add(line[1..].to_string())),
+                // "\ No newline at end of file"
+                Some('\\') => {}
+                // Blank context lines often lose their leading space
+                None => current.lines.push(HunkLine::Context(String::new())),
+                Some(_) => finish_hunk(&mut edit, &mut hunk),
+            }
+        }
+        i += 1;
+    }
+
+    finish_hunk(&mut edit, &mut hunk);
+    edits.extend(edit.filter(|edit| !edit.hunks.is_empty()));
+    edits
+}
+
+/// Add a finished hunk to its edit, dropping hunks that change nothing.
+fn finish_hunk(edit: &mut Option<FileEdit>, hunk: &mut Option<EditHunk>) {
+    let Some(mut finished) = hunk.take() else {
+        return;
+    };
+    // Trailing blank context is usually just the blank line after the diff
+    while matches!(finished.lines.last(), Some(HunkLine::Context(text)) if text.is_empty()) {
+        finished.lines.pop();
+    }
+    let changes = finished
+        .lines
+        .iter()
+        .any(|line| !matches!(line, HunkLine::Context(_)));
+    if let Some(edit) = edit.as_mut()
+        && changes
+    {
+        edit.hunks.push(finished);
+    }
+}
+
+/// Path from a `---`/`+++` header, without `a/`/`b/` prefixes or timestamps.
+fn header_path(header: &str) -> Option<&str> {
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    if path.is_empty() || path == "/dev/null" {
+        return None;
+    }
+    Some(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path),
+    )
+}
+
+/// Extract search/replace blocks.
+///
+/// The file path is taken from the line before `<<<<<<< SEARCH`, or from
+/// the enclosing code fence (```rust src/main.rs). A block without a path
+/// belongs to the same file as the previous one.
+fn extract_search_replace(response: &str, cwd: &Path) -> Vec<FileEdit> {
+    let lines: Vec<&str> = response.lines().collect();
+    let mut edits = Vec::new();
+    let mut fence_path: Option<&str> = None;
+    let mut last_path: Option<&str> = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim_start().starts_with("```") {
+            fence_path = fence_inline_path(line);
+        }
+        if !SEARCH_START.is_match(line) {
+            i += 1;
+            continue;
+        }
+
+        let path = lines[..i]
+            .iter()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .and_then(|previous| path_line(previous))
+            .or(fence_path)
+            .or(last_path);
+
+        let Some(divider) = (i + 1..lines.len()).find(|&j| SEARCH_DIVIDER.is_match(lines[j]))
+        else {
+            break;
+        };
+        let Some(end) = (divider + 1..lines.len()).find(|&j| REPLACE_END.is_match(lines[j])) else {
+            break;
+        };
+
+        if let Some(path) = path {
+            let hunk_lines = lines[i + 1..divider]
+                .iter()
+                .map(|line| HunkLine::Remove(line.to_string()))
+                .chain(
+                    lines[divider + 1..end]
+                        .iter()
+                        .map(|line| HunkLine::Add(line.to_string())),
+                )
+                .collect();
+            edits.push(FileEdit {
+                path: resolve_path(path, cwd),
+                hunks: vec![EditHunk {
+                    lines: hunk_lines,
+                    line_hint: None,
+                }],
+            });
+            last_path = Some(path);
+        }
+        i = end + 1;
+    }
+
+    edits
+}
+
+/// Path given after the language of a code fence.
+fn fence_inline_path(fence: &str) -> Option<&str> {
+    let mut words = fence.trim().trim_start_matches('`').split_whitespace();
+    words.next()?;
+    words.next()
+}
+
+/// Interpret a line as a file path: a single word, optionally in backticks
+/// or bold, that looks like a path.
+fn path_line(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.starts_with("```") {
+        return fence_inline_path(line);
+    }
+    let path = line
+        .trim_matches(|c| c == '`' || c == '*')
+        .trim_end_matches(':');
+    let path_like = !path.is_empty()
+        && !path.contains(char::is_whitespace)
+        && (path.contains('/') || path.contains('.'))
+        && !REPLACE_END.is_match(line);
+    path_like.then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn apply(original: &str, response: &str) -> Result<String> {
+        let edits = extract_file_edits(response, Path::new("/project"));
+        assert_eq!(edits.len(), 1, "expected one edit in {:?}", edits);
+        apply_hunks(original, &edits[0].hunks)
+    }
+
+    mod search_replace {
+        use super::*;
+
+        #[test]
+        fn replaces_matching_lines() {
+            let original = "fn main() {\n    println!(\"Hello\");\n}\n";
+            let response = "src/main.rs\n<<<<<<< SEARCH\n    println!(\"Hello\");\n=======\n    println!(\"Hi\");\n>>>>>>> REPLACE\n";
+
+            assert_eq!(
+                apply(original, response).unwrap(),
+                "fn main() {\n    println!(\"Hi\");\n}\n"
+            );
+        }
+
+        #[test]
+        fn path_from_code_fence() {
+            let response =
+                "```rust src/lib.rs\n<<<<<<< SEARCH\na\n=======\nb\n>>>>>>> REPLACE\n```\n";
+            let edits = extract_file_edits(response, Path::new("/project"));
+
+            assert_eq!(edits.len(), 1);
+            assert_eq!(edits[0].path, Path::new("/project/src/lib.rs"));
+        }
+
+        #[test]
+        fn blocks_for_one_file_are_merged() {
+            let response = "`a.txt`\n<<<<<<< SEARCH\none\n=======\n1\n>>>>>>> REPLACE\n\n<<<<<<< SEARCH\nthree\n=======\n3\n>>>>>>> REPLACE\n\nb.txt\n<<<<<<< SEARCH\nx\n=======\ny\n>>>>>>> REPLACE\n";
+            let edits = extract_file_edits(response, Path::new("/project"));
+
+            assert_eq!(edits.len(), 2);
+            assert_eq!(edits[0].path, Path::new("/project/a.txt"));
+            assert_eq!(edits[0].hunks.len(), 2);
+            assert_eq!(
+                apply_hunks("one\ntwo\nthree\n", &edits[0].hunks).unwrap(),
+                "1\ntwo\n3\n"
+            );
+        }
+
+        #[test]
+        fn matches_ignoring_indentation_and_reindents() {
+            let original = "impl Foo {\n    fn bar() {\n        old();\n    }\n}\n";
+            let response = "src/foo.rs\n<<<<<<< SEARCH\nfn bar() {\n    old();\n}\n=======\nfn bar() {\n    new();\n}\n>>>>>>> REPLACE\n";
+
+            assert_eq!(
+                apply(original, response).unwrap(),
+                "impl Foo {\n    fn bar() {\n        new();\n    }\n}\n"
+            );
+        }
+
+        #[test]
+        fn ambiguous_search_fails_with_candidates() {
+            let original = "fn a() {\n    Ok(())\n}\n\nfn b() {\n    Ok(())\n}\n";
+            let response = "src/lib.rs\n<<<<<<< SEARCH\nOk(())\n=======\nOk(1)\n>>>>>>> REPLACE\n";
+            let err = apply(original, response).unwrap_err().to_string();
+
+            assert!(err.contains("ambiguous"), "{}", err);
+            assert!(err.contains("lines 2, 6"), "{}", err);
+        }
+
+        #[test]
+        fn empty_search_creates_file() {
+            let response = "notes.md\n<<<<<<< SEARCH\n=======\n# Notes\n>>>>>>> REPLACE\n";
+            assert_eq!(apply("", response).unwrap(), "# Notes\n");
+        }
+
+        #[test]
+        fn missing_text_fails_clearly() {
+            let response = "a.txt\n<<<<<<< SEARCH\nnot there\n=======\nnew\n>>>>>>> REPLACE\n";
+            let err = apply("one\ntwo\n", response).unwrap_err().to_string();
+
+            assert!(err.contains("hunk 1 of 1 doesn't match"), "{}", err);
+            assert!(err.contains("not there"), "{}", err);
+        }
+    }
+
+    mod unified_diff {
+        use super::*;
+
+        #[test]
+        fn applies_hunks() {
+            let original = "a\nb\nc\nd\ne\nf\ng\n";
+            let response = "```diff\n--- a/letters.txt\n+++ b/letters.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -5,3 +5,4 @@\n e\n f\n+F\n g\n```\n";
+
+            assert_eq!(
+                apply(original, response).unwrap(),
+                "a\nB\nc\nd\ne\nf\nF\ng\n"
+            );
+        }
+
+        #[test]
+        fn wrong_line_numbers_and_counts_are_tolerated() {
+            let original = "a\nb\nc\nd\n";
+            let response =
+                "```diff\n--- a/x.txt\n+++ b/x.txt\n@@ -40,7 +40,9 @@\n c\n-d\n+D\n```\n";
+
+            assert_eq!(apply(original, response).unwrap(), "a\nb\nc\nD\n");
+        }
+
+        #[test]
+        fn drops_context_that_does_not_match() {
+            let original = "a\nb\nc\nd\ne\n";
+            let response = "```diff\n--- a/x.txt\n+++ b/x.txt\n@@ -1,5 +1,5 @@\n stale\n b\n-c\n+C\n d\n stale\n```\n";
+
+            assert_eq!(apply(original, response).unwrap(), "a\nb\nC\nd\ne\n");
+        }
+
+        #[test]
+        fn hint_picks_nearest_of_repeated_lines() {
+            let original = "x\nend\nx\nend\n";
+            let response =
+                "```diff\n--- a/x.txt\n+++ b/x.txt\n@@ -3,2 +3,2 @@\n-x\n+y\n end\n```\n";
+
+            assert_eq!(apply(original, response).unwrap(), "x\nend\ny\nend\n");
+        }
+
+        #[test]
+        fn new_file_from_dev_null() {
+            let response =
+                "```diff\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n```\n";
+            assert_eq!(apply("", response).unwrap(), "one\ntwo\n");
+        }
+
+        #[test]
+        fn keeps_crlf_line_endings() {
+            let response = "```diff\n--- a/x.txt\n+++ b/x.txt\n@@ -1 +1 @@\n-a\n+b\n```\n";
+            assert_eq!(apply("a\r\nc\r\n", response).unwrap(), "b\r\nc\r\n");
+        }
+
+        #[test]
+        fn mismatch_names_the_hunk() {
+            let response = "```diff\n--- a/x.txt\n+++ b/x.txt\n@@ -1 +1 @@\n-a\n+b\n@@ -9 +9 @@\n-zzz\n+y\n```\n";
+            let err = apply("a\nc\n", response).unwrap_err().to_string();
+
+            assert!(err.contains("hunk 2 of 2"), "{}", err);
+            assert!(err.contains("near line 9"), "{}", err);
+        }
+
+        #[test]
+        fn deleted_files_are_skipped() {
+            let response = "```diff\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n```\n";
+            assert!(extract_file_edits(response, Path::new("/project")).is_empty());
+        }
+    }
+
+    #[test]
+    fn apply_reads_current_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        fs::write(&path, "one\ntwo\n").unwrap();
+
+        let response = format!(
+            "{}\n<<<<<<< SEARCH\ntwo\n=======\n2\n>>>>>>> REPLACE\n",
+            path.display()
+        );
+        let edits = extract_file_edits(&response, temp_dir.path());
+        let proposal = edits[0].apply().unwrap();
+
+        assert_eq!(proposal.content, "one\n2\n");
+        assert!(!proposal.is_new);
+        // Applying only proposes; the file is unchanged
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn detects_edit_blocks() {
+        assert!(is_edit_block(
+            "<<<<<<< SEARCH\na\n=======\nb\n>>>>>>> REPLACE\n"
+        ));
+        assert!(is_edit_block("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n"));
+        assert!(!is_edit_block("fn main() {}\n"));
+    }
+}
@@ -10,6 +10,8 @@ use std::sync::LazyLock;
 
 use regex::Regex;
 
+use super::patch::is_edit_block;
+
 /// A file write proposal extracted from AI response
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileProposal {
@@ -28,9 +30,8 @@ static FENCED_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?s)```(?:\w+)?(?:[ \t]+([^\n]+))?\n(.*?)```").unwrap()
 });
 
-static FILENAME_COMMENT: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"(?m)^//\s*filename:\s*(.+?)\s*$").unwrap()
-});
+static FILENAME_COMMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^//\s*filename:\s*(.+?)\s*$").unwrap());
 
 static FILE_MARKER: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?s)---\s*FILE:\s*([^\n]+?)\s*---\n(.*?)(?:---\s*END FILE\s*---|```)").unwrap()
@@ -82,11 +83,19 @@ pub fn extract_file_proposals(response: &str, cwd: &Path) -> Vec<FileProposal> {
         let inline_path = cap.get(1).map(|m| m.as_str().trim());
         let content = cap.get(2).map(|m| m.as_str()).unwrap_or("");
 
+        // Diffs and search/replace blocks are handled by `extract_file_edits`
+        if is_edit_block(content) {
+            continue;
+        }
+
         // Try inline filename first (```rust path/to/file.rs)
         if let Some(path_str) = inline_path
-            && (path_str.contains('/') || path_str.contains('\\')
-                || path_str.ends_with(".rs") || path_str.ends_with(".toml")
-                || path_str.ends_with(".md") || path_str.ends_with(".txt"))
+            && (path_str.contains('/')
+                || path_str.contains('\\')
+                || path_str.ends_with(".rs")
+                || path_str.ends_with(".toml")
+                || path_str.ends_with(".md")
+                || path_str.ends_with(".txt"))
             && let Some(proposal) = create_proposal(path_str, content, cwd)
         {
             proposals.push(proposal);
@@ -94,11 +103,7 @@ pub fn extract_file_proposals(response: &str, cwd: &Path) -> Vec<FileProposal> {
         }
 
         // Try filename comment in first two lines
-        let first_two_lines: String = content
-            .lines()
-            .take(2)
-            .collect::<Vec<_>>()
-            .join("\n");
+        let first_two_lines: String = content.lines().take(2).collect::<Vec<_>>().join("\n");
 
         if let Some(cap) = FILENAME_COMMENT.captures(&first_two_lines)
             && let Some(path_match) = cap.get(1)
@@ -126,12 +131,7 @@ fn create_proposal(path_str: &str, content: &str, cwd: &Path) -> Option<FileProp
         return None;
     }
 
-    let path = if path_str.starts_with('/') {
-        PathBuf::from(path_str)
-    } else {
-        cwd.join(path_str)
-    };
-
+    let path = resolve_path(path_str, cwd);
     let is_new = !path.exists();
 
     Some(FileProposal {
@@ -141,6 +141,15 @@ fn create_proposal(path_str: &str, content: &str, cwd: &Path) -> Option<FileProp
     })
 }
 
+/// Resolve a proposed path against the current directory
+pub(super) fn resolve_path(path_str: &str, cwd: &Path) -> PathBuf {
+    if path_str.starts_with('/') {
+        PathBuf::from(path_str)
+    } else {
+        cwd.join(path_str)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,7 +264,10 @@ fn test() {}
         let proposals = extract_file_proposals(response, cwd);
 
         assert_eq!(proposals.len(), 1);
-        assert_eq!(proposals[0].path, Path::new("/absolute/path/relative/file.rs"));
+        assert_eq!(
+            proposals[0].path,
+            Path::new("/absolute/path/relative/file.rs")
+        );
     }
 
     #[test]
@@ -281,8 +293,8 @@ new file content
         let proposals = extract_file_proposals(&response, temp_dir.path());
 
         assert_eq!(proposals.len(), 2);
-        assert_eq!(proposals[0].is_new, false); // existing file
-        assert_eq!(proposals[1].is_new, true);  // new file
+        assert!(!proposals[0].is_new); // existing file
+        assert!(proposals[1].is_new); // new file
     }
 
     #[test]
@@ -311,6 +323,31 @@ fn test() {}
         assert_eq!(proposals.len(), 0);
     }
 
+    #[test]
+    fn test_edit_blocks_ignored() {
+        let response = r#"
+```rust src/lib.rs
+<<<<<<< SEARCH
+fn old() {}
+=======
+fn new() {}
+>>>>>>> REPLACE
+```
+
+```diff src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-fn main() {}
++fn main() { run() }
+```
+"#;
+        let cwd = Path::new("/project");
+        let proposals = extract_file_proposals(response, cwd);
+
+        assert_eq!(proposals.len(), 0);
+    }
+
     #[test]
     fn test_filename_comment_in_second_line() {
         let response = r#"
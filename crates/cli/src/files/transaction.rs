@@ -0,0 +1,379 @@
+//! All-or-nothing writes across several files
+//!
+//! Each file is first staged: its new content is written to a temporary file
+//! in the target's directory. Only when every file is staged are they renamed
+//! into place, and if any rename fails the files already replaced are
+//! restored, so a failed or cancelled batch never leaves a project
+//! half-modified. Files can also be staged for removal, which undoing the
+//! creation of a file needs.
+
+use std::fs;
+use std::io::Write;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use tempfile::NamedTempFile;
+
+/// A file staged for writing
+struct StagedWrite {
+    /// Where the file goes (symlinks resolved)
+    path: PathBuf,
+    /// New content, next to `path`; `None` removes the file
+    temp: Option<NamedTempFile>,
+    /// Content before the write, `None` for a new file
+    original: Option<Vec<u8>>,
+}
+
+/// A set of file writes that succeed or fail together.
+///
+/// Dropping a transaction without committing it discards the staged files
+/// and any directories created for them.
+///
+/// # Example
+/// ```no_run
+/// use std::path::Path;
+/// use cherry2k::files::WriteTransaction;
+///
+/// let mut transaction = WriteTransaction::new();
+/// transaction.stage(Path::new("src/lib.rs"), "pub mod api;\n").unwrap();
+/// transaction.stage(Path::new("src/api.rs"), "pub fn get() {}\n").unwrap();
+/// let written = transaction.commit().unwrap();
+/// assert_eq!(written.len(), 2);
+/// ```
+#[derive(Default)]
+pub struct WriteTransaction {
+    staged: Vec<StagedWrite>,
+    /// Directories created while staging, outermost first
+    created_dirs: Vec<PathBuf>,
+}
+
+impl WriteTransaction {
+    /// Create an empty transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of staged files.
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Whether no file is staged.
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Stage a file write, creating its parent directories.
+    ///
+    /// Nothing at `path` changes until [`commit`](Self::commit).
+    ///
+    /// # Errors
+    /// Returns error if the directory can't be created, the current content
+    /// can't be read, or the temporary file can't be written
+    pub fn stage(&mut self, path: &Path, content: &str) -> Result<()> {
+        // Write through symlinks instead of replacing them
+        let path = if path.is_symlink() {
+            fs::canonicalize(path)
+                .with_context(|| format!("Failed to resolve symlink {}", path.display()))?
+        } else {
+            path.to_path_buf()
+        };
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        self.create_dirs(&parent)?;
+
+        let original = match fs::read(&path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        let mut temp = NamedTempFile::new_in(&parent)
+            .with_context(|| format!("Failed to stage {}", path.display()))?;
+        temp.write_all(content.as_bytes())
+            .and_then(|()| temp.as_file().sync_all())
+            .with_context(|| format!("Failed to stage {}", path.display()))?;
+        if original.is_some() {
+            // Keep the mode of the file being replaced
+            let permissions = fs::metadata(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .permissions();
+            fs::set_permissions(temp.path(), permissions)
+                .with_context(|| format!("Failed to stage {}", path.display()))?;
+        }
+
+        self.staged.push(StagedWrite {
+            path,
+            temp: Some(temp),
+            original,
+        });
+        Ok(())
+    }
+
+    /// Stage the removal of a file.
+    ///
+    /// A file that doesn't exist is left alone. Directories that become
+    /// empty are kept.
+    ///
+    /// # Errors
+    /// Returns error if the file can't be read
+    pub fn stage_removal(&mut self, path: &Path) -> Result<()> {
+        let original = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        self.staged.push(StagedWrite {
+            path: path.to_path_buf(),
+            temp: None,
+            original: Some(original),
+        });
+        Ok(())
+    }
+
+    /// Move every staged file into place and remove those staged for removal.
+    ///
+    /// # Returns
+    /// The changed paths, in staging order
+    ///
+    /// # Errors
+    /// Returns a single error naming the file that failed. Files replaced
+    /// before it are restored, and the error also lists any that couldn't be.
+    pub fn commit(mut self) -> Result<Vec<PathBuf>> {
+        let staged = mem::take(&mut self.staged);
+        let mut committed: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::with_capacity(staged.len());
+
+        for write in staged {
+            let StagedWrite {
+                path,
+                temp,
+                original,
+            } = write;
+            let result = match temp {
+                Some(temp) => temp.persist(&path).map(drop).map_err(|e| e.error),
+                None => fs::remove_file(&path),
+            };
+            if let Err(e) = result {
+                let failed = anyhow!(e).context(format!("Failed to write {}", path.display()));
+                let unrestored = rollback(&committed);
+                self.remove_created_dirs();
+                return Err(rollback_error(failed, committed.len(), &unrestored));
+            }
+            committed.push((path, original));
+        }
+
+        // The directories now hold written files
+        self.created_dirs.clear();
+        Ok(committed.into_iter().map(|(path, _)| path).collect())
+    }
+
+    /// Create `dir` and any missing ancestors, remembering which were new.
+    fn create_dirs(&mut self, dir: &Path) -> Result<()> {
+        let missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        self.created_dirs.extend(missing.into_iter().rev());
+        Ok(())
+    }
+
+    /// Remove directories created while staging, innermost first.
+    ///
+    /// Only empty directories are removed, so files written there by
+    /// someone else survive.
+    fn remove_created_dirs(&mut self) {
+        for dir in mem::take(&mut self.created_dirs).iter().rev() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+impl Drop for WriteTransaction {
+    fn drop(&mut self) {
+        // Staged temporary files delete themselves
+        self.staged.clear();
+        self.remove_created_dirs();
+    }
+}
+
+/// Undo committed writes, newest first.
+///
+/// # Returns
+/// The files that couldn't be restored
+fn rollback(committed: &[(PathBuf, Option<Vec<u8>>)]) -> Vec<PathBuf> {
+    let mut unrestored = Vec::new();
+    for (path, original) in committed.iter().rev() {
+        let restored = match original {
+            Some(bytes) => restore(path, bytes),
+            None => fs::remove_file(path).map_err(anyhow::Error::from),
+        };
+        if let Err(e) = restored {
+            tracing::error!("Failed to restore {}: {:#}", path.display(), e);
+            unrestored.push(path.clone());
+        }
+    }
+    unrestored
+}
+
+/// Put original content back, replacing the file in one step.
+fn restore(path: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut temp = NamedTempFile::new_in(dir)?;
+    temp.write_all(bytes)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp.path(), metadata.permissions())?;
+    }
+    temp.persist(path)?;
+    Ok(())
+}
+
+/// Describe a failed commit and what the rollback did.
+fn rollback_error(
+    failed: anyhow::Error,
+    committed: usize,
+    unrestored: &[PathBuf],
+) -> anyhow::Error {
+    if unrestored.is_empty() {
+        failed.context(format!(
+            "No files were changed ({} write(s) rolled back)",
+            committed
+        ))
+    } else {
+        let paths: Vec<String> = unrestored
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        failed.context(format!(
+            "Rollback incomplete, these files may hold the new content: {}",
+            paths.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn commit_writes_all_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let existing = temp_dir.path().join("existing.txt");
+        let new = temp_dir.path().join("src/new.txt");
+        fs::write(&existing, "old").unwrap();
+
+        let mut transaction = WriteTransaction::new();
+        transaction.stage(&existing, "updated").unwrap();
+        transaction.stage(&new, "created").unwrap();
+        // Staging changes nothing yet
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!new.exists());
+
+        let written = transaction.commit().unwrap();
+
+        assert_eq!(written, vec![existing.clone(), new.clone()]);
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "updated");
+        assert_eq!(fs::read_to_string(&new).unwrap(), "created");
+    }
+
+    #[test]
+    fn failed_write_rolls_back_earlier_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let existing = temp_dir.path().join("existing.txt");
+        let new = temp_dir.path().join("nested/new.txt");
+        let blocked = temp_dir.path().join("blocked");
+        fs::write(&existing, "old").unwrap();
+
+        let mut transaction = WriteTransaction::new();
+        transaction.stage(&existing, "updated").unwrap();
+        transaction.stage(&new, "created").unwrap();
+        transaction.stage(&blocked, "oops").unwrap();
+        // A file can't replace a non-empty directory
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("keep.txt"), "keep").unwrap();
+        let err = transaction.commit().unwrap_err();
+
+        let message = format!("{:#}", err);
+        assert!(message.contains("No files were changed"), "{message}");
+        assert!(message.contains("blocked"), "{message}");
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!new.exists());
+        assert!(!temp_dir.path().join("nested").exists());
+        assert_eq!(
+            fs::read_to_string(blocked.join("keep.txt")).unwrap(),
+            "keep"
+        );
+    }
+
+    #[test]
+    fn dropping_discards_staged_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let new = temp_dir.path().join("a/b/new.txt");
+
+        let mut transaction = WriteTransaction::new();
+        transaction.stage(&new, "created").unwrap();
+        assert_eq!(transaction.len(), 1);
+        drop(transaction);
+
+        assert!(!new.exists());
+        assert!(!temp_dir.path().join("a").exists());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn removal_is_rolled_back_with_the_rest() {
+        let temp_dir = TempDir::new().unwrap();
+        let removed = temp_dir.path().join("removed.txt");
+        let blocked = temp_dir.path().join("blocked");
+        fs::write(&removed, "keep me").unwrap();
+
+        let mut transaction = WriteTransaction::new();
+        transaction.stage_removal(&removed).unwrap();
+        transaction
+            .stage_removal(&temp_dir.path().join("missing.txt"))
+            .unwrap();
+        transaction.stage(&blocked, "oops").unwrap();
+        assert_eq!(transaction.len(), 2);
+        fs::create_dir_all(blocked.join("inner")).unwrap();
+
+        assert!(transaction.commit().is_err());
+        assert_eq!(fs::read_to_string(&removed).unwrap(), "keep me");
+
+        let mut transaction = WriteTransaction::new();
+        transaction.stage_removal(&removed).unwrap();
+        assert_eq!(transaction.commit().unwrap(), vec![removed.clone()]);
+        assert!(!removed.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("run.sh");
+        let link = temp_dir.path().join("link.sh");
+        fs::write(&script, "echo old").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        symlink(&script, &link).unwrap();
+
+        let mut transaction = WriteTransaction::new();
+        transaction.stage(&link, "echo new").unwrap();
+        transaction.commit().unwrap();
+
+        assert!(link.is_symlink());
+        assert_eq!(fs::read_to_string(&script).unwrap(), "echo new");
+        let mode = fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o755);
+    }
+}
@@ -1,6 +1,8 @@
 //! File writing with user approval flow
 //!
 //! Provides safe file writing with diff preview and [y/n/e] confirmation.
+//! Files are replaced atomically, and several files are written all or
+//! nothing.
 
 use std::fs;
 use std::io;
@@ -8,8 +10,8 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
-use crate::confirm::{confirm, ConfirmResult};
-use crate::files::{display_new_file_preview, generate_diff, has_changes};
+use crate::confirm::{ConfirmResult, confirm};
+use crate::files::{WriteTransaction, display_new_file_preview, generate_diff, has_changes};
 
 /// Result of a file write operation
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -56,73 +58,87 @@ pub fn write_file_with_approval(
     new_content: &str,
     auto_write: bool,
 ) -> Result<WriteResult> {
+    match review_file(path, new_content, auto_write)? {
+        Review::Unchanged => Ok(WriteResult::Skipped),
+        Review::Rejected => Ok(WriteResult::Cancelled),
+        Review::Approved(content) => {
+            write_file(path, &content)?;
+            eprintln!("Wrote {}", path.display());
+            Ok(WriteResult::Written {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+}
+
+/// Outcome of reviewing one file
+enum Review {
+    /// Write this content (possibly edited by the user)
+    Approved(String),
+    /// The user declined the write
+    Rejected,
+    /// The file already has this content
+    Unchanged,
+}
+
+/// Show a file's diff and ask whether to write it, without writing.
+fn review_file(path: &Path, new_content: &str, auto_write: bool) -> Result<Review> {
     // Read existing content (empty string if new file)
     let old_content = fs::read_to_string(path).unwrap_or_default();
 
     // Check if there are any changes
     if !has_changes(&old_content, new_content) {
         eprintln!("No changes detected in {}", path.display());
-        return Ok(WriteResult::Skipped);
+        return Ok(Review::Unchanged);
     }
 
     // Display diff or new file preview
-    if old_content.is_empty() {
-        // New file
-        display_new_file_preview(new_content, &path.display().to_string());
-    } else {
-        // Existing file - show diff
-        let diff = generate_diff(&old_content, new_content, &path.display().to_string());
-        println!("{}", diff);
-    }
+    show_changes(path, &old_content, new_content);
 
     // Auto-write mode bypasses confirmation
     if auto_write {
-        write_file(path, new_content)?;
-        eprintln!("Wrote {}", path.display());
-        return Ok(WriteResult::Written {
-            path: path.to_path_buf(),
-        });
+        return Ok(Review::Approved(new_content.to_string()));
     }
 
     // Approval loop with edit support
     let mut content = new_content.to_string();
     loop {
         match confirm("Write this file?", true)? {
-            ConfirmResult::Yes => {
-                write_file(path, &content)?;
-                eprintln!("Wrote {}", path.display());
-                return Ok(WriteResult::Written {
-                    path: path.to_path_buf(),
-                });
-            }
-            ConfirmResult::No => {
+            ConfirmResult::Yes => return Ok(Review::Approved(content)),
+            // Insert is only offered for shell commands, never for file writes
+            ConfirmResult::No | ConfirmResult::Insert => {
                 eprintln!("Cancelled write to {}", path.display());
-                return Ok(WriteResult::Cancelled);
+                return Ok(Review::Rejected);
             }
             ConfirmResult::Edit => {
                 // Open in $EDITOR
-                content = edit::edit(&content)
-                    .context("Failed to open editor")?;
+                content = edit::edit(&content).context("Failed to open editor")?;
 
                 // Re-display diff with edited content
                 println!();
                 eprintln!("Updated diff after editing:");
-                if old_content.is_empty() {
-                    display_new_file_preview(&content, &path.display().to_string());
-                } else {
-                    let diff = generate_diff(&old_content, &content, &path.display().to_string());
-                    println!("{}", diff);
-                }
+                show_changes(path, &old_content, &content);
                 // Loop continues to ask for confirmation again
             }
         }
     }
 }
 
+/// Print a new file preview, or a diff against the current content.
+fn show_changes(path: &Path, old_content: &str, new_content: &str) {
+    if old_content.is_empty() {
+        display_new_file_preview(new_content, &path.display().to_string());
+    } else {
+        let diff = generate_diff(old_content, new_content, &path.display().to_string());
+        println!("{}", diff);
+    }
+}
+
 /// Write multiple files with batch or step-by-step approval.
 ///
 /// Shows all diffs first, then offers to write all at once, cancel all,
-/// or process files one at a time with individual approval.
+/// or review files one at a time. Approved files are written together in a
+/// [`WriteTransaction`]: if any write fails, none of the files change.
 ///
 /// # Arguments
 /// * `files` - Vector of (path, content) tuples
@@ -132,7 +148,8 @@ pub fn write_file_with_approval(
 /// Vector of WriteResult for each file, in the same order as input
 ///
 /// # Errors
-/// Returns error if file I/O fails
+/// Returns error if file I/O fails, after rolling back any files already
+/// written
 ///
 /// # Example
 /// ```no_run
@@ -155,82 +172,99 @@ pub fn write_multiple_files(
 
     // Show all diffs first
     eprintln!("\n{} file(s) to write:\n", files.len());
+    let mut changed = Vec::with_capacity(files.len());
     for (path, new_content) in files {
         let old_content = fs::read_to_string(path).unwrap_or_default();
 
         if !has_changes(&old_content, new_content) {
             eprintln!("Skipping {} (no changes)", path.display());
+            changed.push(false);
             continue;
         }
+        changed.push(true);
 
         eprintln!("─────────────────────────────────────");
-        if old_content.is_empty() {
-            display_new_file_preview(new_content, &path.display().to_string());
-        } else {
-            let diff = generate_diff(&old_content, new_content, &path.display().to_string());
-            println!("{}", diff);
-        }
+        show_changes(path, &old_content, new_content);
     }
     eprintln!("─────────────────────────────────────\n");
 
-    // Auto-write mode
-    if auto_write {
-        let mut results = Vec::new();
-        for (path, content) in files {
-            let result = write_file_with_approval(path, content, true)?;
-            results.push(result);
-        }
-        return Ok(results);
-    }
-
-    // Prompt for batch or step-by-step processing
-    print!("Write all files? [y/n/step] ");
-    io::Write::flush(&mut io::stdout())?;
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let choice = input.trim().to_lowercase();
-
-    match choice.as_str() {
-        "y" | "yes" => {
-            // Write all files
-            let mut results = Vec::new();
-            for (path, content) in files {
-                write_file(path, content)?;
-                eprintln!("Wrote {}", path.display());
-                results.push(WriteResult::Written {
-                    path: path.clone(),
-                });
-            }
-            Ok(results)
-        }
+    let choice = if auto_write {
+        "y".to_string()
+    } else {
+        // Prompt for batch or step-by-step processing
+        print!("Write all files? [y/n/step] ");
+        io::Write::flush(&mut io::stdout())?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        input.trim().to_lowercase()
+    };
+
+    // Collect the content to write for each file, writing nothing yet
+    let reviews = match choice.as_str() {
+        "y" | "yes" => files
+            .iter()
+            .zip(&changed)
+            .map(|((_, content), &changed)| {
+                if changed {
+                    Review::Approved(content.clone())
+                } else {
+                    Review::Unchanged
+                }
+            })
+            .collect(),
         "step" => {
-            // Process each file individually
-            let mut results = Vec::new();
+            // Review each file individually
+            let mut reviews = Vec::with_capacity(files.len());
             for (path, content) in files {
-                let result = write_file_with_approval(path, content, false)?;
-                results.push(result);
+                reviews.push(review_file(path, content, false)?);
             }
-            Ok(results)
+            reviews
         }
         _ => {
             // Default to cancel
             eprintln!("Cancelled all writes");
-            Ok(vec![WriteResult::Cancelled; files.len()])
+            return Ok(vec![WriteResult::Cancelled; files.len()]);
+        }
+    };
+
+    let mut transaction = WriteTransaction::new();
+    for ((path, _), review) in files.iter().zip(&reviews) {
+        if let Review::Approved(content) = review {
+            transaction
+                .stage(path, content)
+                .context("No files were changed")?;
         }
     }
+    if !transaction.is_empty() {
+        let count = transaction.len();
+        transaction
+            .commit()
+            .with_context(|| format!("Failed to write {} file(s)", count))?;
+    }
+
+    let results = files
+        .iter()
+        .zip(reviews)
+        .map(|((path, _), review)| match review {
+            Review::Approved(_) => {
+                eprintln!("Wrote {}", path.display());
+                WriteResult::Written { path: path.clone() }
+            }
+            Review::Rejected => WriteResult::Cancelled,
+            Review::Unchanged => WriteResult::Skipped,
+        })
+        .collect();
+    Ok(results)
 }
 
-/// Internal helper to write file with parent directory creation.
+/// Internal helper to write a single file atomically, creating parent
+/// directories.
 fn write_file(path: &Path, content: &str) -> Result<()> {
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
-    }
-
-    fs::write(path, content)
-        .with_context(|| format!("Failed to write file {}", path.display()))
+    let mut transaction = WriteTransaction::new();
+    transaction.stage(path, content)?;
+    transaction.commit()?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -353,9 +387,7 @@ mod tests {
         // Create file with content
         fs::write(&file_path, content).unwrap();
 
-        let files = vec![
-            (file_path, content.to_string()),
-        ];
+        let files = vec![(file_path, content.to_string())];
 
         // This should handle the "no changes" case gracefully
         let results = write_multiple_files(&files, true).unwrap();
@@ -364,4 +396,23 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0], WriteResult::Skipped);
     }
+
+    #[test]
+    fn test_write_multiple_files_rolls_back_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.txt");
+        let blocked = temp_dir.path().join("blocked");
+        fs::write(&first, "old").unwrap();
+        // A directory can't be replaced by a file
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("inner.txt"), "inner").unwrap();
+
+        let files = vec![
+            (first.clone(), "new".to_string()),
+            (blocked, "content".to_string()),
+        ];
+
+        assert!(write_multiple_files(&files, true).is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "old");
+    }
 }
@@ -10,6 +10,7 @@
 //! - [`files`] - File detection and safe reading
 //! - [`intent`] - AI response intent detection
 //! - [`output`] - Terminal output formatting (markdown, spinner, streaming)
+//! - [`persona`] - User-defined system prompts
 //! - [`signal`] - Ctrl+C signal handling with confirmation
 
 pub mod confirm;
@@ -17,4 +18,5 @@ pub mod execute;
 pub mod files;
 pub mod intent;
 pub mod output;
+pub mod persona;
 pub mod signal;
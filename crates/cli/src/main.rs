@@ -22,6 +22,10 @@ struct Cli {
     #[arg(short, long, default_value = "info")]
     log_level: String,
 
+    /// Configuration profile to use (overrides CHERRY2K_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -38,9 +42,46 @@ enum Commands {
         /// Path to JSON file with shell context (for zsh integration)
         #[arg(long)]
         context_file: Option<PathBuf>,
+        /// File to write a chosen command to for placement on the zsh prompt
+        /// line (for zsh integration)
+        #[arg(long)]
+        inject_file: Option<PathBuf>,
+        /// Place suggested commands on the prompt line instead of asking to run them
+        /// (requires --inject-file)
+        #[arg(long, requires = "inject_file")]
+        insert: bool,
+        /// Answer as this persona (a prompt file in the prompts directory)
+        #[arg(long)]
+        persona: Option<String>,
+    },
+    /// Regenerate the last answer, replacing it
+    Retry {
+        /// Answer with this provider instead of the active one
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Answer with this model instead of the provider's configured one
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Answer as this persona (use the one the question was asked with)
+        #[arg(long)]
+        persona: Option<String>,
+    },
+    /// Edit the last question in $EDITOR and resend it, replacing the old answer
+    EditLast {
+        /// Answer as this persona (use the one the question was asked with)
+        #[arg(long)]
+        persona: Option<String>,
+    },
+    /// Show current configuration, or trust project config files
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// Show the system prompt or list personas
+    Prompt {
+        #[command(subcommand)]
+        action: PromptAction,
     },
-    /// Show current configuration
-    Config,
     /// Show or switch AI providers
     Provider {
         /// Provider to switch to (omit to show current)
@@ -49,18 +90,130 @@ enum Commands {
         #[arg(short, long)]
         list: bool,
     },
+    /// Show or switch configuration profiles
+    Profile {
+        /// Profile to switch to (omit to show current)
+        name: Option<String>,
+        /// List all defined profiles
+        #[arg(short, long)]
+        list: bool,
+        /// Go back to the base configuration
+        #[arg(long, conflicts_with = "name")]
+        clear: bool,
+    },
     /// Resume a previous session or list sessions
     Resume {
         /// List all sessions instead of resuming
         #[arg(short, long)]
         list: bool,
-        /// Specific session ID to resume
+        /// Session ID, title or tag to resume
         session_id: Option<String>,
     },
+    /// Name, tag, pin, inspect or fork a session
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
     /// Start a new session (ignoring any existing session)
     New,
+    /// Export sessions as Markdown, JSON or JSON Lines
+    Export {
+        /// Session ID to export
+        #[arg(required_unless_present = "all")]
+        session_id: Option<String>,
+        /// Export every session
+        #[arg(long, conflicts_with = "session_id")]
+        all: bool,
+        /// Output format: md, json or jsonl
+        #[arg(short, long, default_value = "md")]
+        format: String,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import sessions from a JSON or JSON Lines export
+    Import {
+        /// Export file to import
+        file: PathBuf,
+    },
+    /// Search all conversations
+    Search {
+        /// Words to search for
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+        /// Only sessions in this directory or below it
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+        /// Only messages handled by this provider
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Only messages from this role (user, assistant, system)
+        #[arg(short, long)]
+        role: Option<String>,
+        /// Only messages on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only messages on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Resume the session of the top result
+        #[arg(long)]
+        resume: bool,
+    },
+    /// List or search commands that were run, or re-run one
+    History {
+        /// Only show commands (or directories) containing this text
+        #[arg(short, long)]
+        search: Option<String>,
+        /// Maximum number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+    },
+    /// List files changed by AI responses, or show one batch with diffs
+    Changes {
+        /// Batch ID to show with diffs
+        id: Option<i64>,
+        /// Maximum number of batches to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Revert the last (or a given) batch of AI file changes
+    Undo {
+        /// Batch ID to undo (defaults to the most recent one made from this directory)
+        id: Option<i64>,
+        /// Overwrite files that were edited since the changes were written
+        #[arg(long)]
+        force: bool,
+    },
     /// Delete all sessions
     Clear,
+    /// Maintain the session database
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+    /// Check config, providers, database and shell setup
+    Doctor,
+    /// Summarize a session's older messages (run in the background after chat)
+    #[command(hide = true)]
+    Summarize {
+        /// Session ID to summarize
+        session_id: String,
+        /// Provider to summarize with
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Model whose context window sets the threshold
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Title the session first if it has no title yet
+        #[arg(long)]
+        title: bool,
+    },
     /// Test Sentry integration (sends a test event)
     SentryTest {
         /// Trigger a panic to test panic handling
@@ -69,6 +222,131 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// Re-run a previously executed command (asks for confirmation again)
+    Rerun {
+        /// Execution ID from `cherry2k history`
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionAction {
+    /// Set a session's title
+    Rename {
+        /// Session ID, title or tag
+        session: String,
+        /// The new title
+        #[arg(required = true, num_args = 1..)]
+        title: Vec<String>,
+    },
+    /// Add or remove session tags
+    Tag {
+        /// Session ID, title or tag
+        session: String,
+        /// Tags to add (or remove with --remove)
+        #[arg(required = true, num_args = 1..)]
+        tags: Vec<String>,
+        /// Remove the tags instead of adding them
+        #[arg(short, long)]
+        remove: bool,
+    },
+    /// List a session's messages with their IDs
+    Show {
+        /// Session ID, title or tag
+        session: String,
+    },
+    /// Copy a session into a new one to explore an alternative
+    Fork {
+        /// Session ID, title or tag
+        session: String,
+        /// Last message to copy (see `session show`); defaults to all
+        #[arg(long)]
+        at: Option<i64>,
+    },
+    /// Pin a session so it is never cleaned up
+    Pin {
+        /// Session ID, title or tag
+        session: String,
+        /// Unpin the session instead
+        #[arg(long)]
+        unpin: bool,
+    },
+    /// Pin a message so it is always sent verbatim and never summarized
+    PinMessage {
+        /// Message ID (see `session show`)
+        message_id: i64,
+        /// Unpin the message instead
+        #[arg(long)]
+        unpin: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print a setting, e.g. `general.log_level` (API keys are redacted)
+    Get {
+        /// Dotted key of the setting
+        key: String,
+    },
+    /// Change a setting in config.toml, keeping its comments
+    Set {
+        /// Dotted key of the setting
+        key: String,
+        /// New value (TOML syntax, or plain text for strings)
+        value: String,
+    },
+    /// Edit config.toml in $EDITOR, saving only if it is valid
+    Edit,
+    /// Check config files for errors and unknown settings
+    Validate {
+        /// Treat unknown settings as errors
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Print the JSON Schema of config.toml for editor completion
+    Schema,
+    /// Show which config files are read
+    Path,
+    /// Let project config files set API keys, endpoints and safety settings
+    Trust {
+        /// Config file or its directory (default: the files for this directory)
+        path: Option<PathBuf>,
+        /// Stop trusting the files instead
+        #[arg(long)]
+        revoke: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PromptAction {
+    /// Print the fully rendered system prompt
+    Show {
+        /// Persona to render instead of the configured one
+        #[arg(long)]
+        persona: Option<String>,
+    },
+    /// List available personas
+    List,
+}
+
+#[derive(Subcommand)]
+enum DbAction {
+    /// Apply pending schema migrations (backs up the database first)
+    Migrate {
+        /// Show pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete sessions outside the retention policy in config.toml
+    Prune {
+        /// Show which sessions would be deleted without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
 /// Initialize Sentry error tracking.
 ///
 /// Returns a guard that must be kept alive for the duration of the program.
@@ -130,8 +408,33 @@ async fn run() -> Result<()> {
         .with(sentry::integrations::tracing::layer())
         .init();
 
+    // Editing and checking config files must work while the config is broken
+    if let Commands::Config {
+        action: Some(action),
+    } = &cli.command
+    {
+        match action {
+            ConfigAction::Set { key, value } => return commands::config::set(key, value),
+            ConfigAction::Edit => return commands::config::edit(),
+            ConfigAction::Validate { strict } => {
+                return commands::config::validate(cli.profile.as_deref(), *strict);
+            }
+            ConfigAction::Schema => return commands::config::schema(),
+            ConfigAction::Path => return commands::config::path(),
+            ConfigAction::Trust { path, revoke } => {
+                return commands::config::trust(path.as_deref(), *revoke);
+            }
+            ConfigAction::Get { .. } => {}
+        }
+    }
+
+    // Diagnosing a broken config must not fail on loading it
+    if let Commands::Doctor = cli.command {
+        return commands::doctor::run(cli.profile.as_deref()).await;
+    }
+
     // Load configuration
-    let config = cherry2k_core::config::load_config()?;
+    let config = commands::profile::load_config(cli.profile.as_deref())?;
     tracing::debug!("Configuration loaded: {:?}", config.general);
 
     // Dispatch to command handlers
@@ -140,12 +443,49 @@ async fn run() -> Result<()> {
             message,
             plain,
             context_file,
+            inject_file,
+            insert,
+            persona,
         } => {
-            commands::chat::run(&config, &message, plain, context_file.as_deref()).await?;
+            commands::chat::run(
+                &config,
+                &message,
+                plain,
+                context_file.as_deref(),
+                inject_file.as_deref(),
+                insert,
+                persona.as_deref(),
+            )
+            .await?;
         }
-        Commands::Config => {
-            commands::config::run(&config)?;
+        Commands::Retry {
+            provider,
+            model,
+            persona,
+        } => {
+            commands::chat::retry(
+                &config,
+                provider.as_deref(),
+                model.as_deref(),
+                persona.as_deref(),
+            )
+            .await?;
         }
+        Commands::EditLast { persona } => {
+            commands::chat::edit_last(&config, persona.as_deref()).await?;
+        }
+        Commands::Config { action } => match action {
+            Some(ConfigAction::Get { key }) => commands::config::get(&config, &key)?,
+            // Handled before the configuration is loaded
+            Some(_) => unreachable!("config file actions return early"),
+            None => commands::config::run(&config)?,
+        },
+        Commands::Prompt { action } => match action {
+            PromptAction::Show { persona } => {
+                commands::prompt::show(&config, persona.as_deref())?;
+            }
+            PromptAction::List => commands::prompt::list(&config)?,
+        },
         Commands::Provider { name, list } => {
             if list {
                 commands::provider::run_list(&config)?;
@@ -155,6 +495,17 @@ async fn run() -> Result<()> {
                 commands::provider::run_current(&config)?;
             }
         }
+        Commands::Profile { name, list, clear } => {
+            if list {
+                commands::profile::run_list(&config)?;
+            } else if clear {
+                commands::profile::run_clear()?;
+            } else if let Some(profile_name) = name {
+                commands::profile::run_switch(&config, &profile_name)?;
+            } else {
+                commands::profile::run_current(&config)?;
+            }
+        }
         Commands::Resume { list, session_id } => {
             let db = Database::open()
                 .await
@@ -162,6 +513,33 @@ async fn run() -> Result<()> {
             let working_dir = std::env::current_dir().context("Failed to get current directory")?;
             commands::session::resume(&db, session_id.as_deref(), list, &working_dir).await?;
         }
+        Commands::Session { action } => {
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            match action {
+                SessionAction::Rename { session, title } => {
+                    commands::session::rename(&db, &session, &title.join(" ")).await?;
+                }
+                SessionAction::Tag {
+                    session,
+                    tags,
+                    remove,
+                } => commands::session::tag(&db, &session, &tags, remove).await?,
+                SessionAction::Show { session } => {
+                    commands::session::show(&config, &db, &session).await?;
+                }
+                SessionAction::Fork { session, at } => {
+                    commands::session::fork(&db, &session, at).await?;
+                }
+                SessionAction::Pin { session, unpin } => {
+                    commands::session::pin(&db, &session, !unpin).await?;
+                }
+                SessionAction::PinMessage { message_id, unpin } => {
+                    commands::session::pin_message(&db, message_id, !unpin).await?;
+                }
+            }
+        }
         Commands::New => {
             let db = Database::open()
                 .await
@@ -169,12 +547,115 @@ async fn run() -> Result<()> {
             let working_dir = std::env::current_dir().context("Failed to get current directory")?;
             commands::session::new_session(&db, &working_dir).await?;
         }
+        Commands::Export {
+            session_id,
+            all: _,
+            format,
+            output,
+        } => {
+            let format = commands::transfer::ExportFormat::parse(&format)?;
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            commands::transfer::export(&db, session_id.as_deref(), format, output.as_deref())
+                .await?;
+        }
+        Commands::Import { file } => {
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            commands::transfer::import(&db, &file).await?;
+        }
+        Commands::Search {
+            query,
+            dir,
+            provider,
+            role,
+            since,
+            until,
+            limit,
+            resume,
+        } => {
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            let working_dir = std::env::current_dir().context("Failed to get current directory")?;
+            let options = commands::search::SearchOptions {
+                dir: dir.as_deref(),
+                provider: provider.as_deref(),
+                role: role.as_deref(),
+                since: since.as_deref(),
+                until: until.as_deref(),
+                limit,
+                resume,
+            };
+            commands::search::run(&db, &query.join(" "), &options, &working_dir).await?;
+        }
+        Commands::History {
+            search,
+            limit,
+            action,
+        } => {
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            match action {
+                Some(HistoryAction::Rerun { id }) => {
+                    let working_dir =
+                        std::env::current_dir().context("Failed to get current directory")?;
+                    commands::history::rerun(&config, &db, id, &working_dir).await?;
+                }
+                None => commands::history::list(&db, search.as_deref(), limit).await?,
+            }
+        }
+        Commands::Changes { id, limit } => {
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            match id {
+                Some(id) => commands::changes::show(&db, id).await?,
+                None => commands::changes::list(&db, limit).await?,
+            }
+        }
+        Commands::Undo { id, force } => {
+            let db = Database::open()
+                .await
+                .context("Failed to open session database")?;
+            let working_dir = std::env::current_dir().context("Failed to get current directory")?;
+            commands::changes::undo(&config, &db, id, force, &working_dir).await?;
+        }
         Commands::Clear => {
             let db = Database::open()
                 .await
                 .context("Failed to open session database")?;
             commands::session::clear(&db).await?;
         }
+        Commands::Db { action } => match action {
+            DbAction::Migrate { dry_run } => commands::db::migrate(dry_run).await?,
+            DbAction::Prune { dry_run } => {
+                let db = Database::open()
+                    .await
+                    .context("Failed to open session database")?;
+                commands::db::prune(&db, &config.retention, dry_run).await?;
+            }
+        },
+        // Handled before the configuration is loaded
+        Commands::Doctor => unreachable!("doctor returns early"),
+        Commands::Summarize {
+            session_id,
+            provider,
+            model,
+            title,
+        } => {
+            commands::chat::summarize(
+                &config,
+                &session_id,
+                provider.as_deref(),
+                model.as_deref(),
+                title,
+            )
+            .await?;
+        }
         Commands::SentryTest { panic } => {
             if std::env::var("SENTRY_DSN").is_err() {
                 println!("SENTRY_DSN not set - Sentry is inactive");
@@ -0,0 +1,305 @@
+//! Personas: user-defined system prompts
+//!
+//! A persona is a prompt template in a `prompts` directory, named after the
+//! file (`prompts/reviewer.md` is the `reviewer` persona). Personas are
+//! looked up in the project first and then in the config directory:
+//!
+//! 1. `<project root>/.cherry2k/prompts/<name>.md`
+//! 2. `~/.config/cherry2k/prompts/<name>.md` (next to `config.toml`)
+//!
+//! The `default` persona is the built-in command mode prompt unless a
+//! `default.md` overrides it. Templates are rendered with
+//! [`render_prompt`], so they can use `{os}`, `{shell}`, `{cwd}`,
+//! `{project_root}` and `{git_branch}`.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cherry2k_core::config::get_config_path;
+use cherry2k_core::provider::{COMMAND_MODE_PROMPT, PromptVars, render_prompt};
+
+use crate::files::find_project_root;
+
+/// Name of the built-in persona
+pub const DEFAULT_PERSONA: &str = "default";
+
+/// Directory holding persona files, inside the config or `.cherry2k` directory
+const PROMPTS_DIR: &str = "prompts";
+
+/// Extension of persona files
+const PROMPT_EXTENSION: &str = "md";
+
+/// Where a persona's template comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersonaSource {
+    /// The compiled-in command mode prompt
+    BuiltIn,
+    /// A prompt file
+    File(PathBuf),
+}
+
+/// A named system prompt template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Persona {
+    /// Persona name
+    pub name: String,
+    /// Where the template was found
+    pub source: PersonaSource,
+    /// The unrendered template
+    pub template: String,
+}
+
+impl Persona {
+    /// Render the persona's template for a working directory.
+    pub fn render(&self, cwd: &Path) -> String {
+        render_prompt(&self.template, &prompt_vars(cwd))
+    }
+}
+
+/// Load a persona by name.
+///
+/// # Arguments
+///
+/// * `name` - Persona name, or `None` for the default persona
+/// * `cwd` - Working directory (selects the project's personas)
+///
+/// # Errors
+///
+/// Returns an error if the name is invalid, no persona has that name, or its
+/// file cannot be read.
+pub fn load_persona(name: Option<&str>, cwd: &Path) -> io::Result<Persona> {
+    let name = name.unwrap_or(DEFAULT_PERSONA);
+    if !is_valid_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Invalid persona name '{}' (use letters, digits, '-' and '_')",
+                name
+            ),
+        ));
+    }
+
+    let file_name = format!("{}.{}", name, PROMPT_EXTENSION);
+    for dir in prompt_dirs(cwd) {
+        let path = dir.join(&file_name);
+        if path.is_file() {
+            let template = fs::read_to_string(&path)?;
+            return Ok(Persona {
+                name: name.to_string(),
+                source: PersonaSource::File(path),
+                template,
+            });
+        }
+    }
+
+    if name == DEFAULT_PERSONA {
+        return Ok(Persona {
+            name: name.to_string(),
+            source: PersonaSource::BuiltIn,
+            template: COMMAND_MODE_PROMPT.to_string(),
+        });
+    }
+
+    let available: Vec<String> = list_personas(cwd).into_keys().collect();
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "Persona '{}' not found (available: {})",
+            name,
+            available.join(", ")
+        ),
+    ))
+}
+
+/// List every persona available in a working directory.
+///
+/// # Returns
+///
+/// Persona names mapped to where each one comes from. Project personas hide
+/// config directory personas of the same name.
+pub fn list_personas(cwd: &Path) -> BTreeMap<String, PersonaSource> {
+    let mut personas = BTreeMap::new();
+    personas.insert(DEFAULT_PERSONA.to_string(), PersonaSource::BuiltIn);
+
+    // Lowest priority first, so higher priority directories overwrite
+    for dir in prompt_dirs(cwd).into_iter().rev() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(PROMPT_EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str())
+                && is_valid_name(name)
+            {
+                personas.insert(name.to_string(), PersonaSource::File(path));
+            }
+        }
+    }
+    personas
+}
+
+/// Gather the values of the template variables for a working directory.
+pub fn prompt_vars(cwd: &Path) -> PromptVars {
+    let project_root = project_root(cwd);
+    let shell = env::var("SHELL")
+        .ok()
+        .and_then(|shell| {
+            Path::new(&shell)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+
+    PromptVars {
+        os: env::consts::OS.to_string(),
+        shell,
+        cwd: cwd.display().to_string(),
+        project_root: project_root.display().to_string(),
+        git_branch: git_branch(cwd).unwrap_or_default(),
+    }
+}
+
+/// Directories searched for persona files, highest priority first.
+fn prompt_dirs(cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![project_root(cwd).join(".cherry2k").join(PROMPTS_DIR)];
+    if let Some(config_dir) = get_config_path().parent() {
+        dirs.push(config_dir.join(PROMPTS_DIR));
+    }
+    dirs
+}
+
+/// The current branch of the repository containing `cwd`.
+///
+/// Also names the branch of a repository without commits yet, whose `HEAD`
+/// points at a branch that doesn't exist.
+fn git_branch(cwd: &Path) -> Option<String> {
+    let repo = git2::Repository::discover(cwd).ok()?;
+    let head = repo.find_reference("HEAD").ok()?;
+    match head.symbolic_target() {
+        Some(target) => Some(
+            target
+                .strip_prefix("refs/heads/")
+                .unwrap_or(target)
+                .to_string(),
+        ),
+        // Detached HEAD
+        None => head.shorthand().map(str::to_string),
+    }
+}
+
+/// The project root for `cwd`, without the trailing separator git reports.
+fn project_root(cwd: &Path) -> PathBuf {
+    find_project_root(cwd)
+        .map(|root| root.components().collect())
+        .unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Persona names double as file names, so keep them to a safe character set.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_persona(root: &Path, name: &str, content: &str) -> PathBuf {
+        let dir = root.join(".cherry2k").join(PROMPTS_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.md", name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn default_persona_is_built_in() {
+        let temp = TempDir::new().unwrap();
+        let persona = load_persona(None, temp.path()).unwrap();
+        assert_eq!(persona.name, DEFAULT_PERSONA);
+        assert_eq!(persona.source, PersonaSource::BuiltIn);
+        assert_eq!(persona.template, COMMAND_MODE_PROMPT);
+    }
+
+    #[test]
+    fn project_file_overrides_default() {
+        let temp = TempDir::new().unwrap();
+        let path = write_persona(temp.path(), "default", "Be terse.");
+
+        let persona = load_persona(None, temp.path()).unwrap();
+        assert_eq!(persona.source, PersonaSource::File(path));
+        assert_eq!(persona.template, "Be terse.");
+    }
+
+    #[test]
+    fn loads_named_persona() {
+        let temp = TempDir::new().unwrap();
+        write_persona(temp.path(), "reviewer", "Review code on {os}.");
+
+        let persona = load_persona(Some("reviewer"), temp.path()).unwrap();
+        assert_eq!(
+            persona.render(temp.path()),
+            format!("Review code on {}.", env::consts::OS)
+        );
+    }
+
+    #[test]
+    fn unknown_persona_lists_available() {
+        let temp = TempDir::new().unwrap();
+        write_persona(temp.path(), "reviewer", "Review code.");
+
+        let err = load_persona(Some("pirate"), temp.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("reviewer"));
+    }
+
+    #[test]
+    fn rejects_path_like_names() {
+        let temp = TempDir::new().unwrap();
+        let err = load_persona(Some("../secrets"), temp.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn lists_project_personas() {
+        let temp = TempDir::new().unwrap();
+        write_persona(temp.path(), "reviewer", "Review code.");
+        fs::write(
+            temp.path()
+                .join(".cherry2k")
+                .join(PROMPTS_DIR)
+                .join("notes.txt"),
+            "not a persona",
+        )
+        .unwrap();
+
+        let personas = list_personas(temp.path());
+        assert!(personas.contains_key(DEFAULT_PERSONA));
+        assert!(personas.contains_key("reviewer"));
+        assert!(!personas.contains_key("notes"));
+    }
+
+    #[test]
+    fn vars_include_git_branch() {
+        let temp = TempDir::new().unwrap();
+        let repo = git2::Repository::init(temp.path()).unwrap();
+        repo.set_head("refs/heads/feature-x").unwrap();
+
+        let vars = prompt_vars(temp.path());
+        assert_eq!(vars.git_branch, "feature-x");
+        assert_eq!(
+            PathBuf::from(&vars.project_root),
+            temp.path().canonicalize().unwrap()
+        );
+        assert!(!vars.project_root.ends_with('/'));
+    }
+}
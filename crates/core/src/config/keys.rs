@@ -0,0 +1,270 @@
+//! Reading and writing config values by dotted key
+//!
+//! Keys name a setting by its table path, e.g. `general.log_level` or
+//! `profiles.home.ollama.model`. Reads go through the loaded [`Config`], so
+//! they show the effective value; writes edit the config file in place with
+//! a format-preserving editor, keeping comments and layout.
+
+use toml::Value;
+use toml_edit::{DocumentMut, Item, Table};
+
+use super::loader::validate_config_str;
+use super::types::Config;
+use crate::error::ConfigError;
+
+/// Shown instead of API keys
+const REDACTED: &str = "<redacted>";
+
+/// Splits a dotted key, rejecting empty segments.
+fn split_key(key: &str) -> Result<Vec<&str>, ConfigError> {
+    let segments: Vec<&str> = key.split('.').map(str::trim).collect();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(ConfigError::InvalidValue {
+            field: key.to_string(),
+            reason: "expected a dotted key like general.log_level".to_string(),
+        });
+    }
+    Ok(segments)
+}
+
+impl Config {
+    /// Looks up a setting by dotted key.
+    ///
+    /// API keys are redacted, also inside tables.
+    ///
+    /// # Returns
+    ///
+    /// The value (a table for a section), or `None` if the setting is unset
+    /// or doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidValue`] if the key is malformed.
+    pub fn get(&self, key: &str) -> Result<Option<Value>, ConfigError> {
+        let segments = split_key(key)?;
+        let mut value =
+            Value::try_from(self).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        redact_api_keys(&mut value);
+
+        for segment in segments {
+            match value {
+                Value::Table(mut table) => match table.remove(segment) {
+                    Some(next) => value = next,
+                    None => return Ok(None),
+                },
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Replaces every `api_key` value with a placeholder.
+fn redact_api_keys(value: &mut Value) {
+    if let Value::Table(table) = value {
+        for (key, child) in table.iter_mut() {
+            if key == "api_key" {
+                *child = Value::String(REDACTED.to_string());
+            } else {
+                redact_api_keys(child);
+            }
+        }
+    }
+}
+
+/// Sets a value in config file contents, keeping comments and formatting.
+///
+/// The value is parsed as TOML when possible (`true`, `8192`,
+/// `["rm -rf /"]`) and taken as a string otherwise, so `gpt-4o` needs no
+/// quotes. Missing tables are created.
+///
+/// # Arguments
+///
+/// * `content` - Current contents of the config file (may be empty)
+/// * `key` - Dotted key of the setting
+/// * `value` - New value
+///
+/// # Returns
+///
+/// The new file contents.
+///
+/// # Errors
+///
+/// Returns [`ConfigError::ParseError`] if the contents aren't valid TOML,
+/// and [`ConfigError::InvalidValue`] if the key is malformed, a parent of
+/// the key isn't a table, or the result isn't a valid configuration.
+pub fn set_config_value(content: &str, key: &str, value: &str) -> Result<String, ConfigError> {
+    let segments = split_key(key)?;
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| ConfigError::ParseError(e.to_string()))?;
+
+    let (last, parents) = segments
+        .split_last()
+        .expect("split_key returns at least one segment");
+    let mut table = doc.as_table_mut();
+    for (depth, segment) in parents.iter().enumerate() {
+        let item = table
+            .entry(segment)
+            .or_insert_with(|| Item::Table(Table::new()));
+        table = item
+            .as_table_mut()
+            .ok_or_else(|| ConfigError::InvalidValue {
+                field: key.to_string(),
+                reason: format!("{} is not a table", parents[..=depth].join(".")),
+            })?;
+    }
+
+    let new_value = value
+        .parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| toml_edit::Value::from(value));
+    if table.get(last).is_some_and(Item::is_table_like) {
+        return Err(ConfigError::InvalidValue {
+            field: key.to_string(),
+            reason: "is a table; set one of its keys instead".to_string(),
+        });
+    }
+    match table.get_mut(last).and_then(Item::as_value_mut) {
+        // Keep the whitespace and trailing comment around the old value
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = new_value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(last, Item::Value(new_value));
+        }
+    }
+
+    let updated = doc.to_string();
+    let unknown = validate_config_str(&updated).map_err(|e| ConfigError::InvalidValue {
+        field: key.to_string(),
+        reason: e.to_string(),
+    })?;
+    // Catch typos instead of writing a setting that would be ignored
+    if unknown
+        .iter()
+        .any(|unknown| key == unknown || key.starts_with(&format!("{}.", unknown)))
+    {
+        return Err(ConfigError::InvalidValue {
+            field: key.to_string(),
+            reason: "unknown setting".to_string(),
+        });
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OpenAiConfig;
+
+    mod get {
+        use super::*;
+
+        #[test]
+        fn reads_effective_values() {
+            let config = Config::default();
+            assert_eq!(
+                config.get("general.log_level").unwrap(),
+                Some(Value::String("info".to_string()))
+            );
+            assert_eq!(
+                config.get("safety.confirm_commands").unwrap(),
+                Some(Value::Boolean(true))
+            );
+        }
+
+        #[test]
+        fn unset_or_unknown_is_none() {
+            let config = Config::default();
+            assert_eq!(config.get("openai.model").unwrap(), None);
+            assert_eq!(config.get("general.nope").unwrap(), None);
+            assert_eq!(config.get("general.log_level.deeper").unwrap(), None);
+        }
+
+        #[test]
+        fn redacts_api_keys() {
+            let config = Config {
+                openai: Some(OpenAiConfig {
+                    api_key: Some("sk-secret".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            assert_eq!(
+                config.get("openai.api_key").unwrap(),
+                Some(Value::String(REDACTED.to_string()))
+            );
+            let section = config.get("openai").unwrap().unwrap().to_string();
+            assert!(!section.contains("sk-secret"));
+        }
+
+        #[test]
+        fn rejects_malformed_key() {
+            assert!(Config::default().get("general..log_level").is_err());
+        }
+    }
+
+    mod set_config_value {
+        use super::*;
+
+        #[test]
+        fn preserves_comments() {
+            let content = "# My config\n[general]\n# chatty\nlog_level = \"debug\" # for now\n";
+            let updated = set_config_value(content, "general.log_level", "warn").unwrap();
+            assert_eq!(
+                updated,
+                "# My config\n[general]\n# chatty\nlog_level = \"warn\" # for now\n"
+            );
+        }
+
+        #[test]
+        fn creates_missing_tables() {
+            let updated = set_config_value("", "ollama.model", "llama3.2:1b").unwrap();
+            assert!(updated.contains("[ollama]"));
+            assert!(updated.contains("model = \"llama3.2:1b\""));
+        }
+
+        #[test]
+        fn parses_toml_values() {
+            let updated = set_config_value("", "safety.confirm_commands", "false").unwrap();
+            assert!(updated.contains("confirm_commands = false"));
+
+            let updated = set_config_value("", "openai.context_window", "128000").unwrap();
+            assert!(updated.contains("context_window = 128000"));
+        }
+
+        #[test]
+        fn rejects_invalid_result() {
+            let err = set_config_value("", "retention.max_age_days", "forever").unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidValue { .. }));
+        }
+
+        #[test]
+        fn rejects_unknown_setting() {
+            let err = set_config_value("", "safety.confirm_comands", "false").unwrap_err();
+            assert!(err.to_string().contains("unknown setting"));
+            let err = set_config_value("", "antropic.model", "claude").unwrap_err();
+            assert!(err.to_string().contains("unknown setting"));
+        }
+
+        #[test]
+        fn rejects_non_table_parent() {
+            let err = set_config_value(
+                "[general]\nlog_level = \"info\"\n",
+                "general.log_level.x",
+                "1",
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("general.log_level is not a table"));
+        }
+
+        #[test]
+        fn rejects_replacing_a_table() {
+            let err =
+                set_config_value("[general]\nlog_level = \"info\"\n", "general", "1").unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidValue { .. }));
+        }
+    }
+}
@@ -1,33 +1,58 @@
 //! Configuration loading logic for Cherry2K
 
+use crate::config::project::{
+    ProjectConfigFile, TrustStore, find_project_configs, merge_project_table, merge_tables,
+};
 use crate::config::types::*;
+use crate::config::validate::{check_table, deserialize_tracking_unknown};
 use crate::error::ConfigError;
 use directories::ProjectDirs;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Environment variable selecting a profile
+pub const PROFILE_ENV_VAR: &str = "CHERRY2K_PROFILE";
+
+/// File name of the trusted project list, next to the config file
+const TRUST_STORE_FILE: &str = "trusted_projects.toml";
 
 /// Load configuration from file and environment variables.
 ///
 /// Priority (highest to lowest):
 /// 1. Environment variables (OPENAI_API_KEY, ANTHROPIC_API_KEY, etc.)
-/// 2. Config file (~/.config/cherry2k/config.toml or CHERRY2K_CONFIG_PATH)
-/// 3. Compiled defaults
+/// 2. The selected profile (`[profiles.<name>]`, from CHERRY2K_PROFILE)
+/// 3. Project config files (`.cherry2k.toml` from the current directory up
+///    to the project root, nearest first), restricted unless trusted
+/// 4. Config file (~/.config/cherry2k/config.toml or CHERRY2K_CONFIG_PATH)
+/// 5. Compiled defaults
 ///
 /// # Errors
-/// Returns ConfigError if config file exists but is malformed.
+/// Returns ConfigError if a config file exists but is malformed, or if
+/// CHERRY2K_PROFILE names a profile that isn't defined.
 /// Missing config file is NOT an error - defaults are used.
 pub fn load_config() -> Result<Config, ConfigError> {
-    // Find config file path
-    let config_path = get_config_path();
+    let profile = env::var(PROFILE_ENV_VAR).ok().filter(|p| !p.is_empty());
+    load_config_with_profile(profile.as_deref())
+}
 
-    // Load from file if exists
-    let mut config = if config_path.exists() {
-        let content = fs::read_to_string(&config_path).map_err(ConfigError::ReadError)?;
-        toml::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))?
-    } else {
-        Config::default()
-    };
+/// Load configuration with a profile applied.
+///
+/// Same as [`load_config`], but with the profile given explicitly instead
+/// of read from CHERRY2K_PROFILE.
+///
+/// # Arguments
+/// * `profile` - Profile to apply over the config files, or `None` for none
+///
+/// # Errors
+/// Returns ConfigError if a config file is malformed or the profile isn't
+/// defined.
+pub fn load_config_with_profile(profile: Option<&str>) -> Result<Config, ConfigError> {
+    let cwd = env::current_dir().ok();
+    let trust = TrustStore::load(&get_trust_store_path())?;
+
+    let mut config = load_layered(&get_config_path(), cwd.as_deref(), &trust, profile)?;
 
     // Apply environment variable overrides
     apply_env_overrides(&mut config);
@@ -35,6 +60,160 @@ pub fn load_config() -> Result<Config, ConfigError> {
     Ok(config)
 }
 
+/// Load the user config file, merge the project files over it and apply
+/// the profile.
+fn load_layered(
+    config_path: &Path,
+    cwd: Option<&Path>,
+    trust: &TrustStore,
+    profile: Option<&str>,
+) -> Result<Config, ConfigError> {
+    let mut table = if config_path.exists() {
+        read_table(config_path)?
+    } else {
+        Table::new()
+    };
+
+    let mut project_files = Vec::new();
+    for path in cwd.map(find_project_configs).unwrap_or_default() {
+        let content = fs::read_to_string(&path).map_err(ConfigError::ReadError)?;
+        let project = parse_table(&path, &content)?;
+        let trusted = trust.is_trusted(&path, &content);
+        let ignored = merge_project_table(&mut table, project, trusted);
+        if !ignored.is_empty() {
+            tracing::warn!(
+                "Ignoring {} in untrusted {} (run `cherry2k config trust` to allow)",
+                ignored.join(", "),
+                path.display()
+            );
+        }
+        project_files.push(ProjectConfigFile {
+            path,
+            trusted,
+            ignored,
+        });
+    }
+
+    if let Some(name) = profile {
+        apply_profile(&mut table, name)?;
+    }
+
+    let mut config: Config = Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| ConfigError::ParseError(e.to_string()))?;
+    // Each file was checked on its own, but the merge can still combine
+    // into something invalid (e.g. a profile's provider name)
+    config.check()?;
+    config.project_files = project_files;
+    config.active_profile = profile.map(str::to_string);
+    Ok(config)
+}
+
+/// Merge `[profiles.<name>]` over the rest of the config.
+fn apply_profile(table: &mut Table, name: &str) -> Result<(), ConfigError> {
+    let profiles = match table.get("profiles") {
+        Some(Value::Table(profiles)) => profiles,
+        _ => &Table::new(),
+    };
+    let Some(Value::Table(overrides)) = profiles.get(name) else {
+        let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+        return Err(ConfigError::UnknownProfile {
+            name: name.to_string(),
+            available: if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            },
+        });
+    };
+
+    let mut overrides = overrides.clone();
+    // Profiles don't nest
+    overrides.remove("profiles");
+    merge_tables(table, overrides);
+    Ok(())
+}
+
+/// Read a config file as a TOML table.
+fn read_table(path: &Path) -> Result<Table, ConfigError> {
+    let content = fs::read_to_string(path).map_err(ConfigError::ReadError)?;
+    parse_table(path, &content)
+}
+
+/// Parse config file contents, naming the file in errors.
+///
+/// The contents are validated first, so type errors point at the offending
+/// line too. Unknown keys are logged as warnings.
+fn parse_table(path: &Path, content: &str) -> Result<Table, ConfigError> {
+    for key in validate_in_file(path, content)? {
+        tracing::warn!("Unknown setting '{}' in {}", key, path.display());
+    }
+    toml::from_str(content)
+        .map_err(|e: toml::de::Error| ConfigError::ParseError(format!("{}: {}", path.display(), e)))
+}
+
+/// Check config file contents without loading them.
+///
+/// Errors give the line and column of the problem. Profiles are checked as
+/// well, though their errors name the profile rather than a line. Values
+/// are checked with [`Config::check`].
+///
+/// # Returns
+/// The dotted keys of unknown settings, which are otherwise ignored.
+///
+/// # Errors
+/// Returns ConfigError::ParseError or ConfigError::InvalidValue describing
+/// the first problem found.
+pub fn validate_config_str(content: &str) -> Result<Vec<String>, ConfigError> {
+    let parse_error = |e: toml::de::Error| ConfigError::ParseError(e.to_string());
+    let deserializer = toml::Deserializer::parse(content).map_err(parse_error)?;
+    let (config, mut unknown) = deserialize_tracking_unknown(deserializer).map_err(parse_error)?;
+    config.check()?;
+
+    for (name, profile) in config.profiles {
+        let in_profile = |e| match e {
+            ConfigError::ParseError(message) => {
+                ConfigError::ParseError(format!("profiles.{}: {}", name, message))
+            }
+            ConfigError::InvalidValue { field, reason } => ConfigError::InvalidValue {
+                field: format!("profiles.{}.{}", name, field),
+                reason,
+            },
+            other => other,
+        };
+        let profile_unknown = check_table(Value::Table(profile)).map_err(in_profile)?;
+        unknown.extend(
+            profile_unknown
+                .into_iter()
+                .map(|key| format!("profiles.{}.{}", name, key)),
+        );
+    }
+    Ok(unknown)
+}
+
+/// Check a config file without loading it.
+///
+/// # Returns
+/// The dotted keys of unknown settings.
+///
+/// # Errors
+/// Returns ConfigError if the file cannot be read or is invalid; parse
+/// errors name the file, line and column.
+pub fn validate_config_file(path: &Path) -> Result<Vec<String>, ConfigError> {
+    let content = fs::read_to_string(path).map_err(ConfigError::ReadError)?;
+    validate_in_file(path, &content)
+}
+
+/// [`validate_config_str`], naming the file in parse errors.
+fn validate_in_file(path: &Path, content: &str) -> Result<Vec<String>, ConfigError> {
+    validate_config_str(content).map_err(|e| match e {
+        ConfigError::ParseError(message) => {
+            ConfigError::ParseError(format!("{}: {}", path.display(), message))
+        }
+        other => other,
+    })
+}
+
 /// Get the config file path.
 /// Uses CHERRY2K_CONFIG_PATH if set, otherwise ~/.config/cherry2k/config.toml
 pub fn get_config_path() -> PathBuf {
@@ -50,6 +229,15 @@ pub fn get_config_path() -> PathBuf {
     }
 }
 
+/// Get the path of the list of trusted project config files.
+/// Kept next to the config file.
+pub fn get_trust_store_path() -> PathBuf {
+    get_config_path()
+        .parent()
+        .map(|dir| dir.join(TRUST_STORE_FILE))
+        .unwrap_or_else(|| PathBuf::from(TRUST_STORE_FILE))
+}
+
 /// Apply environment variable overrides to config.
 fn apply_env_overrides(config: &mut Config) {
     // Log level override
@@ -120,6 +308,7 @@ fn apply_env_overrides(config: &mut Config) {
 #[allow(unsafe_code)] // Required for env::set_var/remove_var in Rust 2024
 mod tests {
     use super::*;
+    use crate::config::project::PROJECT_CONFIG_FILE;
     use serial_test::serial;
     use std::io::Write;
     use tempfile::NamedTempFile;
@@ -197,6 +386,70 @@ confirm_commands = false
         }
     }
 
+    #[test]
+    #[serial]
+    fn test_retention_config_parsing() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[retention]
+max_sessions_per_dir = 50
+max_db_size_mb = 200
+"#
+        )
+        .unwrap();
+        file.flush().unwrap();
+
+        // SAFETY: Test environment, single-threaded test execution
+        unsafe {
+            env::set_var("CHERRY2K_CONFIG_PATH", file.path().to_str().unwrap());
+        }
+        let config = load_config().unwrap();
+        // Unset fields keep their defaults
+        assert_eq!(config.retention.max_age_days, 30);
+        assert_eq!(config.retention.max_sessions_per_dir, 50);
+        assert_eq!(config.retention.max_db_size_mb, 200);
+        // SAFETY: Cleanup after test
+        unsafe {
+            env::remove_var("CHERRY2K_CONFIG_PATH");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_context_config_parsing() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[context]
+summarizer_provider = "ollama"
+summarizer_model = "llama3.2:1b"
+"#
+        )
+        .unwrap();
+        file.flush().unwrap();
+
+        // SAFETY: Test environment, single-threaded test execution
+        unsafe {
+            env::set_var("CHERRY2K_CONFIG_PATH", file.path().to_str().unwrap());
+        }
+        let config = load_config().unwrap();
+        assert_eq!(
+            config.context.summarizer_provider.as_deref(),
+            Some("ollama")
+        );
+        assert_eq!(
+            config.context.summarizer_model.as_deref(),
+            Some("llama3.2:1b")
+        );
+        // SAFETY: Cleanup after test
+        unsafe {
+            env::remove_var("CHERRY2K_CONFIG_PATH");
+        }
+    }
+
     #[test]
     #[serial]
     fn test_invalid_toml_returns_error() {
@@ -240,4 +493,194 @@ confirm_commands = false
             env::remove_var("OPENAI_MODEL");
         }
     }
+
+    #[test]
+    fn test_project_config_layered_over_user_config() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let user = temp.path().join("config.toml");
+        fs::write(
+            &user,
+            "[general]\nlog_level = \"debug\"\n[openai]\napi_key = \"user-key\"\n",
+        )
+        .unwrap();
+
+        let repo = temp.path().join("repo");
+        let nested = repo.join("app");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(repo.join(".git")).unwrap();
+        fs::write(
+            repo.join(PROJECT_CONFIG_FILE),
+            "[general]\ndefault_provider = \"ollama\"\npersona = \"reviewer\"\n",
+        )
+        .unwrap();
+        fs::write(
+            nested.join(PROJECT_CONFIG_FILE),
+            "[general]\npersona = \"frontend\"\n[openai]\napi_key = \"project-key\"\n",
+        )
+        .unwrap();
+
+        let config = load_layered(&user, Some(&nested), &TrustStore::default(), None).unwrap();
+        assert_eq!(config.general.log_level, "debug");
+        assert_eq!(config.general.default_provider, "ollama");
+        // The nearest file wins
+        assert_eq!(config.general.persona.as_deref(), Some("frontend"));
+        // Untrusted files cannot set API keys
+        assert_eq!(
+            config.openai.as_ref().unwrap().api_key.as_deref(),
+            Some("user-key")
+        );
+        assert_eq!(config.project_files.len(), 2);
+        assert_eq!(config.project_files[1].ignored, vec!["openai.api_key"]);
+    }
+
+    #[test]
+    fn test_trusted_project_config_is_unrestricted() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp.path().join(".git")).unwrap();
+        let project = temp.path().join(PROJECT_CONFIG_FILE);
+        let content = "[safety]\nconfirm_commands = false\n";
+        fs::write(&project, content).unwrap();
+
+        let untrusted = load_layered(
+            &temp.path().join("missing.toml"),
+            Some(temp.path()),
+            &TrustStore::default(),
+            None,
+        )
+        .unwrap();
+        assert!(untrusted.safety.confirm_commands);
+        assert!(!untrusted.project_files[0].trusted);
+
+        let mut trust = TrustStore::default();
+        trust.trust(&project, content);
+        let trusted = load_layered(
+            &temp.path().join("missing.toml"),
+            Some(temp.path()),
+            &trust,
+            None,
+        )
+        .unwrap();
+        assert!(!trusted.safety.confirm_commands);
+        assert!(trusted.project_files[0].trusted);
+    }
+
+    #[test]
+    fn test_invalid_project_config_names_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp.path().join(".git")).unwrap();
+        fs::write(temp.path().join(PROJECT_CONFIG_FILE), "not toml {{").unwrap();
+
+        let err = load_layered(
+            &temp.path().join("missing.toml"),
+            Some(temp.path()),
+            &TrustStore::default(),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains(PROJECT_CONFIG_FILE));
+    }
+
+    #[test]
+    fn test_profile_overrides_sections() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let user = temp.path().join("config.toml");
+        fs::write(
+            &user,
+            r#"
+[general]
+default_provider = "openai"
+log_level = "debug"
+
+[profiles.home.general]
+default_provider = "ollama"
+
+[profiles.home.safety]
+confirm_commands = false
+
+[profiles.home.ollama]
+model = "qwen2.5-coder"
+"#,
+        )
+        .unwrap();
+
+        let base = load_layered(&user, None, &TrustStore::default(), None).unwrap();
+        assert_eq!(base.general.default_provider, "openai");
+        assert!(base.safety.confirm_commands);
+        assert!(base.active_profile.is_none());
+        assert!(base.profiles.contains_key("home"));
+
+        let home = load_layered(&user, None, &TrustStore::default(), Some("home")).unwrap();
+        assert_eq!(home.general.default_provider, "ollama");
+        // Settings the profile doesn't mention are kept
+        assert_eq!(home.general.log_level, "debug");
+        assert!(!home.safety.confirm_commands);
+        assert_eq!(home.ollama.as_ref().unwrap().model, "qwen2.5-coder");
+        assert_eq!(home.active_profile.as_deref(), Some("home"));
+    }
+
+    #[test]
+    fn test_unknown_profile_lists_available() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let user = temp.path().join("config.toml");
+        fs::write(&user, "[profiles.work.general]\npersona = \"reviewer\"\n").unwrap();
+
+        let err = load_layered(&user, None, &TrustStore::default(), Some("home")).unwrap_err();
+        match err {
+            ConfigError::UnknownProfile { name, available } => {
+                assert_eq!(name, "home");
+                assert_eq!(available, "work");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_selects_profile() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "[profiles.work.general]\nlog_level = \"warn\"").unwrap();
+        file.flush().unwrap();
+
+        // SAFETY: Test environment, single-threaded test execution
+        unsafe {
+            env::set_var("CHERRY2K_CONFIG_PATH", file.path().to_str().unwrap());
+            env::set_var(PROFILE_ENV_VAR, "work");
+        }
+        let config = load_config().unwrap();
+        assert_eq!(config.general.log_level, "warn");
+        assert_eq!(config.active_profile.as_deref(), Some("work"));
+        // SAFETY: Cleanup after test
+        unsafe {
+            env::remove_var("CHERRY2K_CONFIG_PATH");
+            env::remove_var(PROFILE_ENV_VAR);
+        }
+    }
+
+    #[test]
+    fn test_validation_errors_have_locations() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(
+            &path,
+            "[general]\nlog_level = \"info\"\n\n[retention]\nmax_age_days = \"a month\"\n",
+        )
+        .unwrap();
+
+        let err = validate_config_file(&path).unwrap_err().to_string();
+        assert!(err.contains("config.toml"), "{err}");
+        assert!(err.contains("line 5"), "{err}");
+    }
+
+    #[test]
+    fn test_validation_checks_profiles() {
+        let err = validate_config_str("[profiles.home.safety]\nconfirm_commands = \"no\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("profiles.home"), "{err}");
+        assert!(validate_config_str("[profiles.home.safety]\nconfirm_commands = false\n").is_ok());
+
+        let unknown =
+            validate_config_str("[profiles.home.safety]\nconfirm_comands = false\n").unwrap();
+        assert_eq!(unknown, vec!["profiles.home.safety.confirm_comands"]);
+    }
 }
@@ -2,13 +2,19 @@
 //!
 //! This module provides configuration loading with support for:
 //! - TOML configuration files (~/.config/cherry2k/config.toml)
+//! - Project-local `.cherry2k.toml` files, restricted unless trusted
+//! - Named profiles (`[profiles.<name>]`) overriding any section
+//! - API keys read from a command, file or the system keyring
+//! - Unknown-key warnings, value checks and a JSON Schema of the file
 //! - Environment variable overrides (OPENAI_API_KEY, ANTHROPIC_API_KEY, etc.)
 //! - Sensible defaults when no configuration is provided
 //!
 //! # Priority (highest to lowest)
 //! 1. Environment variables
-//! 2. Config file
-//! 3. Compiled defaults
+//! 2. The selected profile
+//! 3. Project config files (nearest to the working directory first)
+//! 4. Config file
+//! 5. Compiled defaults
 //!
 //! # Example
 //! ```no_run
@@ -18,8 +24,22 @@
 //! println!("Default provider: {}", config.general.default_provider);
 //! ```
 
+mod keys;
 mod loader;
+mod project;
+mod secret;
 mod types;
+mod validate;
 
-pub use loader::{get_config_path, load_config};
-pub use types::{AnthropicConfig, Config, GeneralConfig, OllamaConfig, OpenAiConfig, SafetyConfig};
+pub use keys::set_config_value;
+pub use loader::{
+    PROFILE_ENV_VAR, get_config_path, get_trust_store_path, load_config, load_config_with_profile,
+    validate_config_file, validate_config_str,
+};
+pub use project::{PROJECT_CONFIG_FILE, ProjectConfigFile, TrustStore, find_project_configs};
+pub use secret::{ApiKeySource, KEYRING_SERVICE};
+pub use types::{
+    AnthropicConfig, Config, ContextConfig, GeneralConfig, OllamaConfig, OpenAiConfig,
+    RetentionConfig, SafetyConfig,
+};
+pub use validate::config_schema;
@@ -0,0 +1,431 @@
+//! Project-local configuration files
+//!
+//! A project can carry a `.cherry2k.toml` with the same layout as the user
+//! config. Files are discovered from the working directory up to the project
+//! root (the nearest directory containing `.git`) and merged over the user
+//! config, the file nearest to the working directory winning.
+//!
+//! A project file comes with the repository, so it is not trusted by default.
+//! Until the user trusts it with `cherry2k config trust`, it cannot:
+//! - set API keys or change where requests are sent (`api_key*`,
+//!   `base_url`, `host`); `api_key_cmd` would also run any command
+//! - turn off confirmations (`safety.confirm_*` may only be set to `true`)
+//! - remove blocked patterns (`safety.blocked_patterns` is added to the
+//!   user's list instead of replacing it)
+//! - define profiles (`[profiles.*]` could otherwise do all of the above)
+//! - change session retention (`[retention]` prunes sessions of every
+//!   directory, not just this project's)
+//!
+//! Trust is recorded per file together with a SHA-256 of its contents, so a
+//! trusted file that changes is restricted again until it is re-trusted.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use toml::{Table, Value};
+
+use super::types::SafetyConfig;
+use crate::error::ConfigError;
+
+/// File name of project configuration files
+pub const PROJECT_CONFIG_FILE: &str = ".cherry2k.toml";
+
+/// Tables an untrusted project file may not set at all.
+const RESTRICTED_TABLES: &[&str] = &["profiles", "retention"];
+
+/// Settings an untrusted project file may not set, as `(table, key)`.
+const RESTRICTED_KEYS: &[(&str, &str)] = &[
+    ("openai", "api_key"),
+    ("openai", "api_key_cmd"),
+    ("openai", "api_key_file"),
+    ("openai", "api_key_keyring"),
+    ("openai", "base_url"),
+    ("anthropic", "api_key"),
+    ("anthropic", "api_key_cmd"),
+    ("anthropic", "api_key_file"),
+    ("anthropic", "api_key_keyring"),
+    ("ollama", "host"),
+];
+
+/// Safety switches an untrusted project file may only turn on.
+const CONFIRM_KEYS: &[&str] = &["confirm_commands", "confirm_file_writes"];
+
+/// A project configuration file that was merged into the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectConfigFile {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Whether the user trusts the file's current contents
+    pub trusted: bool,
+    /// Settings ignored because the file is not trusted (e.g. `openai.api_key`)
+    pub ignored: Vec<String>,
+}
+
+/// Finds the project configuration files that apply to a directory.
+///
+/// Walks up from `start` to the project root (the nearest directory with a
+/// `.git`), or to the filesystem root outside a repository.
+///
+/// # Returns
+///
+/// The files found, farthest first, so later files take precedence.
+pub fn find_project_configs(start: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for dir in start.ancestors() {
+        let candidate = dir.join(PROJECT_CONFIG_FILE);
+        if candidate.is_file() {
+            found.push(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    found.reverse();
+    found
+}
+
+/// Merges a project file's settings over `base`.
+///
+/// Untrusted files are restricted as described in the module docs.
+///
+/// # Returns
+///
+/// The settings that were ignored, as dotted keys.
+pub(crate) fn merge_project_table(
+    base: &mut Table,
+    mut project: Table,
+    trusted: bool,
+) -> Vec<String> {
+    if trusted {
+        merge_tables(base, project);
+        return Vec::new();
+    }
+
+    let mut ignored = Vec::new();
+    for (section, key) in RESTRICTED_KEYS {
+        if let Some(Value::Table(table)) = project.get_mut(*section)
+            && table.remove(*key).is_some()
+        {
+            ignored.push(format!("{section}.{key}"));
+        }
+    }
+
+    for table in RESTRICTED_TABLES {
+        if project.remove(*table).is_some() {
+            ignored.push((*table).to_string());
+        }
+    }
+
+    if let Some(Value::Table(safety)) = project.get_mut("safety") {
+        for key in CONFIRM_KEYS {
+            if matches!(safety.get(*key), Some(Value::Boolean(false))) {
+                safety.remove(*key);
+                ignored.push(format!("safety.{key}"));
+            }
+        }
+
+        // Add to the blocked patterns rather than replace them
+        if let Some(Value::Array(patterns)) = safety.remove("blocked_patterns") {
+            let existing = base
+                .entry("safety")
+                .or_insert_with(|| Value::Table(Table::new()));
+            if let Value::Table(existing) = existing {
+                match existing.get_mut("blocked_patterns") {
+                    Some(Value::Array(list)) => list.extend(patterns),
+                    _ => {
+                        // Keep the compiled-in defaults when the user config has none
+                        let mut list: Vec<Value> = SafetyConfig::default()
+                            .blocked_patterns
+                            .into_iter()
+                            .map(Value::String)
+                            .collect();
+                        list.extend(patterns);
+                        existing.insert("blocked_patterns".to_string(), Value::Array(list));
+                    }
+                }
+            }
+        }
+    }
+
+    merge_tables(base, project);
+    ignored
+}
+
+/// Recursively merges `overlay` into `base`; overlay values win.
+pub(crate) fn merge_tables(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Files the user trusts, stored next to the user config.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    /// Trusted files with the hash of their trusted contents
+    #[serde(default, rename = "project")]
+    projects: Vec<TrustedFile>,
+}
+
+/// A trusted project configuration file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrustedFile {
+    path: PathBuf,
+    sha256: String,
+}
+
+impl TrustStore {
+    /// Loads the trust store, or an empty one if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content)
+            .map_err(|e| ConfigError::ParseError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Writes the trust store, creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = toml::to_string(self).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Whether `file` is trusted with exactly these contents.
+    #[must_use]
+    pub fn is_trusted(&self, file: &Path, content: &str) -> bool {
+        let hash = content_hash(content);
+        self.projects
+            .iter()
+            .any(|trusted| trusted.path == file && trusted.sha256 == hash)
+    }
+
+    /// Trusts `file` with its current contents, replacing any earlier entry.
+    pub fn trust(&mut self, file: &Path, content: &str) {
+        self.revoke(file);
+        self.projects.push(TrustedFile {
+            path: file.to_path_buf(),
+            sha256: content_hash(content),
+        });
+    }
+
+    /// Stops trusting `file`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the file was trusted.
+    pub fn revoke(&mut self, file: &Path) -> bool {
+        let before = self.projects.len();
+        self.projects.retain(|trusted| trusted.path != file);
+        self.projects.len() != before
+    }
+}
+
+/// SHA-256 of a file's contents, hex encoded.
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn table(toml: &str) -> Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    mod find_project_configs {
+        use super::*;
+
+        #[test]
+        fn stops_at_project_root() {
+            let temp = TempDir::new().unwrap();
+            let root = temp.path().join("repo");
+            let nested = root.join("crates").join("core");
+            fs::create_dir_all(&nested).unwrap();
+            fs::create_dir(root.join(".git")).unwrap();
+            fs::write(temp.path().join(PROJECT_CONFIG_FILE), "").unwrap();
+            fs::write(root.join(PROJECT_CONFIG_FILE), "").unwrap();
+            fs::write(root.join("crates").join(PROJECT_CONFIG_FILE), "").unwrap();
+
+            let found = find_project_configs(&nested);
+            assert_eq!(
+                found,
+                vec![
+                    root.join(PROJECT_CONFIG_FILE),
+                    root.join("crates").join(PROJECT_CONFIG_FILE),
+                ]
+            );
+        }
+
+        #[test]
+        fn none_found() {
+            let temp = TempDir::new().unwrap();
+            fs::create_dir(temp.path().join(".git")).unwrap();
+            assert!(find_project_configs(temp.path()).is_empty());
+        }
+    }
+
+    mod merge_project_table {
+        use super::*;
+
+        #[test]
+        fn trusted_file_overrides_everything() {
+            let mut base = table("[openai]\napi_key = \"user\"\nmodel = \"gpt-4o\"");
+            let project =
+                table("[openai]\napi_key = \"project\"\n[safety]\nconfirm_commands = false");
+
+            let ignored = merge_project_table(&mut base, project, true);
+            assert!(ignored.is_empty());
+            assert_eq!(base["openai"]["api_key"].as_str(), Some("project"));
+            assert_eq!(base["openai"]["model"].as_str(), Some("gpt-4o"));
+            assert_eq!(base["safety"]["confirm_commands"].as_bool(), Some(false));
+        }
+
+        #[test]
+        fn untrusted_file_cannot_set_keys_or_endpoints() {
+            let mut base = table("[openai]\napi_key = \"user\"");
+            let project = table(
+                "[openai]\napi_key = \"project\"\nbase_url = \"https://evil.example\"\nmodel = \"gpt-4.1\"\n[ollama]\nhost = \"http://evil.example\"",
+            );
+
+            let ignored = merge_project_table(&mut base, project, false);
+            assert_eq!(
+                ignored,
+                vec!["openai.api_key", "openai.base_url", "ollama.host"]
+            );
+            assert_eq!(base["openai"]["api_key"].as_str(), Some("user"));
+            assert!(base["openai"].get("base_url").is_none());
+            assert_eq!(base["openai"]["model"].as_str(), Some("gpt-4.1"));
+        }
+
+        #[test]
+        fn untrusted_file_cannot_change_retention() {
+            let mut base = table("[retention]\nmax_age_days = 90");
+            let project = table("[retention]\nmax_age_days = 1\nmax_sessions_per_dir = 1");
+
+            let ignored = merge_project_table(&mut base, project, false);
+            assert_eq!(ignored, vec!["retention"]);
+            assert_eq!(base["retention"]["max_age_days"].as_integer(), Some(90));
+            assert!(base["retention"].get("max_sessions_per_dir").is_none());
+        }
+
+        #[test]
+        fn untrusted_file_can_only_tighten_confirmations() {
+            let mut base = table("[safety]\nconfirm_file_writes = false");
+            let project = table("[safety]\nconfirm_commands = false\nconfirm_file_writes = true");
+
+            let ignored = merge_project_table(&mut base, project, false);
+            assert_eq!(ignored, vec!["safety.confirm_commands"]);
+            assert!(base["safety"].get("confirm_commands").is_none());
+            assert_eq!(base["safety"]["confirm_file_writes"].as_bool(), Some(true));
+        }
+
+        #[test]
+        fn untrusted_file_cannot_define_profiles() {
+            let mut base = Table::new();
+            let project = table("[profiles.yolo.safety]\nconfirm_commands = false");
+
+            let ignored = merge_project_table(&mut base, project, false);
+            assert_eq!(ignored, vec!["profiles"]);
+            assert!(base.get("profiles").is_none());
+        }
+
+        #[test]
+        fn untrusted_blocked_patterns_are_added() {
+            let mut base = table("[safety]\nblocked_patterns = [\"rm -rf /\"]");
+            let project = table("[safety]\nblocked_patterns = [\"terraform destroy\"]");
+
+            merge_project_table(&mut base, project, false);
+            let patterns: Vec<&str> = base["safety"]["blocked_patterns"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(Value::as_str)
+                .collect();
+            assert_eq!(patterns, vec!["rm -rf /", "terraform destroy"]);
+        }
+
+        #[test]
+        fn untrusted_blocked_patterns_keep_defaults() {
+            let mut base = Table::new();
+            let project = table("[safety]\nblocked_patterns = [\"terraform destroy\"]");
+
+            merge_project_table(&mut base, project, false);
+            let patterns = base["safety"]["blocked_patterns"].as_array().unwrap();
+            let defaults = SafetyConfig::default().blocked_patterns;
+            assert_eq!(patterns.len(), defaults.len() + 1);
+        }
+    }
+
+    mod trust_store {
+        use super::*;
+
+        #[test]
+        fn trust_is_tied_to_contents() {
+            let file = Path::new("/repo/.cherry2k.toml");
+            let mut store = TrustStore::default();
+            store.trust(file, "a = 1");
+
+            assert!(store.is_trusted(file, "a = 1"));
+            assert!(!store.is_trusted(file, "a = 2"));
+            assert!(!store.is_trusted(Path::new("/other/.cherry2k.toml"), "a = 1"));
+        }
+
+        #[test]
+        fn revoke_removes_trust() {
+            let file = Path::new("/repo/.cherry2k.toml");
+            let mut store = TrustStore::default();
+            store.trust(file, "a = 1");
+
+            assert!(store.revoke(file));
+            assert!(!store.is_trusted(file, "a = 1"));
+            assert!(!store.revoke(file));
+        }
+
+        #[test]
+        fn round_trips_through_file() {
+            let temp = TempDir::new().unwrap();
+            let path = temp.path().join("nested").join("trusted_projects.toml");
+            let file = Path::new("/repo/.cherry2k.toml");
+
+            let mut store = TrustStore::default();
+            store.trust(file, "a = 1");
+            store.save(&path).unwrap();
+
+            let loaded = TrustStore::load(&path).unwrap();
+            assert!(loaded.is_trusted(file, "a = 1"));
+        }
+
+        #[test]
+        fn missing_file_is_empty() {
+            let temp = TempDir::new().unwrap();
+            let store = TrustStore::load(&temp.path().join("missing.toml")).unwrap();
+            assert!(!store.is_trusted(Path::new("/repo/.cherry2k.toml"), ""));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! API key sources
+//!
+//! Besides a plaintext `api_key` (or the provider's environment variable), a
+//! provider section can point at where the key is kept:
+//!
+//! ```toml
+//! [openai]
+//! api_key_cmd = "pass show openai"        # stdout of a shell command
+//! api_key_file = "~/.secrets/openai"      # contents of a file
+//! api_key_keyring = "openai"              # system keyring, service "cherry2k"
+//! ```
+//!
+//! Sources are only read when the provider is constructed, never while
+//! loading or displaying the config. If several are set, the first of
+//! `api_key`, `api_key_cmd`, `api_key_file`, `api_key_keyring` wins, so an
+//! exported `OPENAI_API_KEY` still takes precedence.
+//!
+//! On Linux the keyring is the Secret Service (GNOME Keyring, KWallet). A
+//! key can be stored with
+//! `secret-tool store --label='cherry2k openai' service cherry2k username openai`.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::ConfigError;
+
+/// Keyring service name under which API keys are looked up
+pub const KEYRING_SERVICE: &str = "cherry2k";
+
+/// Where a provider's API key comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeySource<'a> {
+    /// Not configured
+    None,
+    /// Plaintext in config or from the environment
+    Plain(&'a str),
+    /// Standard output of a shell command
+    Command(&'a str),
+    /// Contents of a file
+    File(&'a Path),
+    /// System keyring entry (account name under [`KEYRING_SERVICE`])
+    Keyring(&'a str),
+}
+
+impl<'a> ApiKeySource<'a> {
+    /// Picks the source from a provider section's fields, by precedence.
+    #[must_use]
+    pub fn select(
+        api_key: Option<&'a str>,
+        cmd: Option<&'a str>,
+        file: Option<&'a Path>,
+        keyring: Option<&'a str>,
+    ) -> Self {
+        if let Some(key) = api_key {
+            Self::Plain(key)
+        } else if let Some(cmd) = cmd {
+            Self::Command(cmd)
+        } else if let Some(file) = file {
+            Self::File(file)
+        } else if let Some(account) = keyring {
+            Self::Keyring(account)
+        } else {
+            Self::None
+        }
+    }
+
+    /// Reads the key.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Config section, for error messages (e.g. `openai`)
+    ///
+    /// # Returns
+    ///
+    /// The trimmed key, or `None` if no source is configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidValue`] if the command fails, the file
+    /// or keyring entry can't be read, or the key is empty.
+    pub fn resolve(&self, section: &str) -> Result<Option<String>, ConfigError> {
+        let (field, key) = match *self {
+            Self::None => return Ok(None),
+            Self::Plain(key) => return Ok(Some(key.to_string())),
+            Self::Command(cmd) => ("api_key_cmd", run_key_command(cmd)),
+            Self::File(path) => ("api_key_file", read_key_file(path)),
+            Self::Keyring(account) => ("api_key_keyring", read_keyring(account)),
+        };
+        let invalid = |reason: String| ConfigError::InvalidValue {
+            field: format!("{}.{}", section, field),
+            reason,
+        };
+
+        let key = key.map_err(invalid)?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(invalid("the API key is empty".to_string()));
+        }
+        Ok(Some(key.to_string()))
+    }
+}
+
+/// Describes the source without revealing the key.
+impl fmt::Display for ApiKeySource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "not set"),
+            Self::Plain(_) => write!(f, "configured"),
+            Self::Command(cmd) => write!(f, "from command `{}`", cmd),
+            Self::File(path) => write!(f, "from file {}", path.display()),
+            Self::Keyring(account) => {
+                write!(f, "from keyring ({}/{})", KEYRING_SERVICE, account)
+            }
+        }
+    }
+}
+
+/// Runs a key command through the shell and returns its output.
+fn run_key_command(cmd: &str) -> Result<String, String> {
+    let output = shell_command(cmd)
+        .output()
+        .map_err(|e| format!("failed to run `{}`: {}", cmd, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut reason = format!("`{}` failed ({})", cmd, output.status);
+        if !stderr.trim().is_empty() {
+            reason.push_str(": ");
+            reason.push_str(stderr.trim());
+        }
+        return Err(reason);
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("`{}` printed invalid UTF-8", cmd))
+}
+
+#[cfg(unix)]
+fn shell_command(cmd: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(cmd: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(cmd);
+    command
+}
+
+/// Reads a key file, expanding a leading `~`.
+fn read_key_file(path: &Path) -> Result<String, String> {
+    let path = expand_home(path);
+    fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))
+}
+
+/// Reads a key from the system keyring.
+///
+/// Runs on its own thread: the Secret Service client blocks on a runtime of
+/// its own, which panics on a thread already driving the tokio runtime.
+fn read_keyring(account: &str) -> Result<String, String> {
+    let lookup = {
+        let account = account.to_string();
+        std::thread::spawn(move || {
+            keyring::Entry::new(KEYRING_SERVICE, &account).and_then(|entry| entry.get_password())
+        })
+    };
+    lookup
+        .join()
+        .map_err(|_| "keyring lookup panicked".to_string())?
+        .map_err(|e| format!("keyring entry {}/{}: {}", KEYRING_SERVICE, account, e))
+}
+
+/// Expands a leading `~` to the home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => directories::BaseDirs::new()
+            .map(|dirs| dirs.home_dir().join(rest))
+            .unwrap_or_else(|| path.to_path_buf()),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    mod select {
+        use super::*;
+
+        #[test]
+        fn plaintext_key_wins() {
+            let source = ApiKeySource::select(Some("sk-1"), Some("echo sk-2"), None, None);
+            assert_eq!(source, ApiKeySource::Plain("sk-1"));
+        }
+
+        #[test]
+        fn command_before_file_and_keyring() {
+            let source = ApiKeySource::select(
+                None,
+                Some("pass show openai"),
+                Some(Path::new("/key")),
+                Some("openai"),
+            );
+            assert_eq!(source, ApiKeySource::Command("pass show openai"));
+        }
+
+        #[test]
+        fn none_configured() {
+            let source = ApiKeySource::select(None, None, None, None);
+            assert_eq!(source, ApiKeySource::None);
+            assert_eq!(source.resolve("openai").unwrap(), None);
+        }
+    }
+
+    mod resolve {
+        use super::*;
+
+        #[cfg(unix)]
+        #[test]
+        fn command_output_is_trimmed() {
+            let key = ApiKeySource::Command("printf 'sk-from-cmd\\n'")
+                .resolve("openai")
+                .unwrap();
+            assert_eq!(key.as_deref(), Some("sk-from-cmd"));
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn failing_command_is_invalid_value() {
+            let err = ApiKeySource::Command("echo nope >&2; exit 3")
+                .resolve("anthropic")
+                .unwrap_err();
+            match err {
+                ConfigError::InvalidValue { field, reason } => {
+                    assert_eq!(field, "anthropic.api_key_cmd");
+                    assert!(reason.contains("nope"));
+                }
+                other => panic!("unexpected error: {other}"),
+            }
+        }
+
+        #[test]
+        fn reads_key_file() {
+            let temp = TempDir::new().unwrap();
+            let path = temp.path().join("key");
+            fs::write(&path, "sk-from-file\n").unwrap();
+
+            let key = ApiKeySource::File(&path).resolve("openai").unwrap();
+            assert_eq!(key.as_deref(), Some("sk-from-file"));
+        }
+
+        #[test]
+        fn missing_or_empty_file_is_invalid_value() {
+            let temp = TempDir::new().unwrap();
+            let missing = temp.path().join("missing");
+            assert!(matches!(
+                ApiKeySource::File(&missing).resolve("openai"),
+                Err(ConfigError::InvalidValue { .. })
+            ));
+
+            let empty = temp.path().join("empty");
+            fs::write(&empty, "  \n").unwrap();
+            assert!(matches!(
+                ApiKeySource::File(&empty).resolve("openai"),
+                Err(ConfigError::InvalidValue { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn display_never_shows_plaintext_key() {
+        let shown = ApiKeySource::Plain("sk-secret").to_string();
+        assert!(!shown.contains("sk-secret"));
+        assert_eq!(
+            ApiKeySource::Command("pass show openai").to_string(),
+            "from command `pass show openai`"
+        );
+    }
+}
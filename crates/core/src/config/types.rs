@@ -2,11 +2,20 @@
 //!
 //! All configuration types use serde for deserialization and provide sensible defaults.
 
-use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::project::ProjectConfigFile;
+use super::secret::ApiKeySource;
+use crate::error::ConfigError;
 
 /// Root configuration structure
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, Default)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
     /// General settings
     pub general: GeneralConfig,
@@ -18,16 +27,32 @@ pub struct Config {
     pub ollama: Option<OllamaConfig>,
     /// Safety settings
     pub safety: SafetyConfig,
+    /// Session retention settings
+    pub retention: RetentionConfig,
+    /// Context management settings
+    pub context: ContextConfig,
+    /// Named profiles, each overriding any of the sections above
+    #[schemars(with = "BTreeMap<String, Config>")]
+    pub profiles: BTreeMap<String, toml::Table>,
+    /// The profile applied to this config (not read from TOML)
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    /// Project config files merged into this config (not read from TOML)
+    #[serde(skip)]
+    pub project_files: Vec<ProjectConfigFile>,
 }
 
 /// General application settings
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct GeneralConfig {
     /// Default provider to use (openai, anthropic, ollama)
     pub default_provider: String,
     /// Log level (trace, debug, info, warn, error)
     pub log_level: String,
+    /// Persona (system prompt file) to use when none is given with `--persona`
+    pub persona: Option<String>,
 }
 
 impl Default for GeneralConfig {
@@ -35,60 +60,137 @@ impl Default for GeneralConfig {
         Self {
             default_provider: "openai".to_string(),
             log_level: "info".to_string(),
+            persona: None,
         }
     }
 }
 
 /// OpenAI provider configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct OpenAiConfig {
     /// API key (prefer env var OPENAI_API_KEY)
     pub api_key: Option<String>,
+    /// Shell command printing the API key (e.g. `pass show openai`)
+    pub api_key_cmd: Option<String>,
+    /// File containing the API key
+    pub api_key_file: Option<PathBuf>,
+    /// System keyring account holding the API key (service `cherry2k`)
+    pub api_key_keyring: Option<String>,
     /// Base URL for API (default: <https://api.openai.com/v1>)
     /// Allows using OpenAI-compatible APIs
     pub base_url: String,
     /// Model to use (default: gpt-4o)
     pub model: String,
+    /// Context window in tokens (default: looked up from the model name)
+    pub context_window: Option<usize>,
+}
+
+impl OpenAiConfig {
+    /// Where the API key comes from, by precedence.
+    #[must_use]
+    pub fn api_key_source(&self) -> ApiKeySource<'_> {
+        ApiKeySource::select(
+            self.api_key.as_deref(),
+            self.api_key_cmd.as_deref(),
+            self.api_key_file.as_deref(),
+            self.api_key_keyring.as_deref(),
+        )
+    }
+
+    /// Reads the API key from its source into `api_key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidValue`] if the source can't be read.
+    pub fn with_resolved_api_key(mut self) -> Result<Self, ConfigError> {
+        self.api_key = self.api_key_source().resolve("openai")?;
+        Ok(self)
+    }
 }
 
 impl Default for OpenAiConfig {
     fn default() -> Self {
         Self {
             api_key: None,
+            api_key_cmd: None,
+            api_key_file: None,
+            api_key_keyring: None,
             base_url: "https://api.openai.com/v1".to_string(),
             model: "gpt-4o".to_string(),
+            context_window: None,
         }
     }
 }
 
 /// Anthropic provider configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct AnthropicConfig {
     /// API key (prefer env var ANTHROPIC_API_KEY)
     pub api_key: Option<String>,
+    /// Shell command printing the API key (e.g. `pass show anthropic`)
+    pub api_key_cmd: Option<String>,
+    /// File containing the API key
+    pub api_key_file: Option<PathBuf>,
+    /// System keyring account holding the API key (service `cherry2k`)
+    pub api_key_keyring: Option<String>,
     /// Model to use (default: claude-sonnet-4-20250514)
     pub model: String,
+    /// Context window in tokens (default: looked up from the model name)
+    pub context_window: Option<usize>,
+}
+
+impl AnthropicConfig {
+    /// Where the API key comes from, by precedence.
+    #[must_use]
+    pub fn api_key_source(&self) -> ApiKeySource<'_> {
+        ApiKeySource::select(
+            self.api_key.as_deref(),
+            self.api_key_cmd.as_deref(),
+            self.api_key_file.as_deref(),
+            self.api_key_keyring.as_deref(),
+        )
+    }
+
+    /// Reads the API key from its source into `api_key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidValue`] if the source can't be read.
+    pub fn with_resolved_api_key(mut self) -> Result<Self, ConfigError> {
+        self.api_key = self.api_key_source().resolve("anthropic")?;
+        Ok(self)
+    }
 }
 
 impl Default for AnthropicConfig {
     fn default() -> Self {
         Self {
             api_key: None,
+            api_key_cmd: None,
+            api_key_file: None,
+            api_key_keyring: None,
             model: "claude-sonnet-4-20250514".to_string(),
+            context_window: None,
         }
     }
 }
 
 /// Ollama provider configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct OllamaConfig {
     /// Ollama host URL (default: <http://localhost:11434>)
     pub host: String,
     /// Model to use (default: llama3.2)
     pub model: String,
+    /// Context window in tokens, sent to Ollama as `num_ctx`
+    /// (default: Ollama's own default of 4096)
+    pub context_window: Option<usize>,
 }
 
 impl Default for OllamaConfig {
@@ -96,13 +198,15 @@ impl Default for OllamaConfig {
         Self {
             host: "http://localhost:11434".to_string(),
             model: "llama3.2".to_string(),
+            context_window: None,
         }
     }
 }
 
 /// Safety configuration for command execution
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct SafetyConfig {
     /// Require confirmation before executing commands (default: true)
     pub confirm_commands: bool,
@@ -127,3 +231,43 @@ impl Default for SafetyConfig {
         }
     }
 }
+
+/// Session retention configuration
+///
+/// Limits set to 0 are disabled. Pinned sessions are never removed.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Remove sessions inactive for longer than this many days (default: 30)
+    pub max_age_days: u32,
+    /// Keep at most this many sessions per directory (default: 0, unlimited)
+    pub max_sessions_per_dir: u32,
+    /// Remove the oldest sessions while the database exceeds this size in
+    /// megabytes (default: 0, unlimited)
+    pub max_db_size_mb: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 30,
+            max_sessions_per_dir: 0,
+            max_db_size_mb: 0,
+        }
+    }
+}
+
+/// Context management configuration
+///
+/// Summaries and session titles are internal calls that don't need the
+/// model answering questions; they can go to a cheaper or local one.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct ContextConfig {
+    /// Provider for summaries and session titles (default: the active provider)
+    pub summarizer_provider: Option<String>,
+    /// Model for summaries and session titles (default: the provider's model)
+    pub summarizer_model: Option<String>,
+}
@@ -0,0 +1,291 @@
+//! Config validation
+//!
+//! Every section uses `#[serde(default)]`, so serde alone accepts a config
+//! with a misspelled key (`confirm_comands = false`) and silently keeps the
+//! default. Unknown keys are collected here instead, so they can be reported
+//! as warnings, or as errors by `cherry2k config validate --strict`.
+//!
+//! Values are checked too: provider names, log levels, URLs and limits that
+//! serde's types can't express.
+//!
+//! [`config_schema`] describes the same structure as a JSON Schema, for
+//! editors that complete and check TOML files against one.
+
+use reqwest::Url;
+
+use super::types::Config;
+use crate::error::ConfigError;
+
+/// Providers that can be named in config
+const PROVIDERS: &[&str] = &["openai", "anthropic", "ollama"];
+
+/// Accepted log levels
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// Deserializes a config, collecting the dotted paths of unknown keys.
+///
+/// # Errors
+///
+/// Returns the deserializer's error if the input isn't a valid config.
+pub(crate) fn deserialize_tracking_unknown<'de, D>(
+    deserializer: D,
+) -> Result<(Config, Vec<String>), D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut unknown = Vec::new();
+    let config = serde_ignored::deserialize(deserializer, |path| {
+        unknown.push(dotted_path(&path));
+    })?;
+    Ok((config, unknown))
+}
+
+/// Formats an ignored path as a dotted key (`openai.api_kee`).
+fn dotted_path(path: &serde_ignored::Path<'_>) -> String {
+    fn collect(path: &serde_ignored::Path<'_>, segments: &mut Vec<String>) {
+        use serde_ignored::Path;
+        match path {
+            Path::Root => {}
+            Path::Seq { parent, index } => {
+                collect(parent, segments);
+                segments.push(index.to_string());
+            }
+            Path::Map { parent, key } => {
+                collect(parent, segments);
+                segments.push(key.clone());
+            }
+            Path::Some { parent }
+            | Path::NewtypeStruct { parent }
+            | Path::NewtypeVariant { parent } => collect(parent, segments),
+        }
+    }
+
+    let mut segments = Vec::new();
+    collect(path, &mut segments);
+    segments.join(".")
+}
+
+impl Config {
+    /// Checks values that deserialize fine but can't work.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidValue`] for the first invalid value.
+    pub fn check(&self) -> Result<(), ConfigError> {
+        one_of(
+            "general.default_provider",
+            &self.general.default_provider,
+            PROVIDERS,
+        )?;
+        one_of(
+            "general.log_level",
+            &self.general.log_level.to_ascii_lowercase(),
+            LOG_LEVELS,
+        )?;
+        if let Some(provider) = &self.context.summarizer_provider {
+            one_of("context.summarizer_provider", provider, PROVIDERS)?;
+        }
+
+        if let Some(openai) = &self.openai {
+            http_url("openai.base_url", &openai.base_url)?;
+            positive("openai.context_window", openai.context_window)?;
+            not_empty("openai.api_key_cmd", openai.api_key_cmd.as_deref())?;
+        }
+        if let Some(anthropic) = &self.anthropic {
+            positive("anthropic.context_window", anthropic.context_window)?;
+            not_empty("anthropic.api_key_cmd", anthropic.api_key_cmd.as_deref())?;
+        }
+        if let Some(ollama) = &self.ollama {
+            http_url("ollama.host", &ollama.host)?;
+            positive("ollama.context_window", ollama.context_window)?;
+        }
+
+        // An empty pattern is contained in every command and would block them all
+        if self
+            .safety
+            .blocked_patterns
+            .iter()
+            .any(|pattern| pattern.trim().is_empty())
+        {
+            return Err(invalid(
+                "safety.blocked_patterns",
+                "patterns must not be empty",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Generates the JSON Schema of the config file.
+///
+/// Every section rejects unknown keys, matching `config validate --strict`.
+#[must_use]
+pub fn config_schema() -> schemars::Schema {
+    schemars::schema_for!(Config)
+}
+
+/// Deserializes `value` as a whole config and checks it.
+///
+/// Used for profiles, which are stored as raw tables.
+pub(crate) fn check_table(value: toml::Value) -> Result<Vec<String>, ConfigError> {
+    let (config, unknown) = deserialize_tracking_unknown(value)
+        .map_err(|e: toml::de::Error| ConfigError::ParseError(e.to_string()))?;
+    config.check()?;
+    Ok(unknown)
+}
+
+fn invalid(field: &str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::InvalidValue {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn one_of(field: &str, value: &str, allowed: &[&str]) -> Result<(), ConfigError> {
+    if allowed.contains(&value) {
+        Ok(())
+    } else {
+        Err(invalid(
+            field,
+            format!("'{}' is not one of {}", value, allowed.join(", ")),
+        ))
+    }
+}
+
+fn http_url(field: &str, value: &str) -> Result<(), ConfigError> {
+    match Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => Ok(()),
+        Ok(_) => Err(invalid(field, format!("'{}' is not an http(s) URL", value))),
+        Err(e) => Err(invalid(field, format!("'{}' is not a URL: {}", value, e))),
+    }
+}
+
+fn positive(field: &str, value: Option<usize>) -> Result<(), ConfigError> {
+    match value {
+        Some(0) => Err(invalid(field, "must be greater than 0")),
+        _ => Ok(()),
+    }
+}
+
+fn not_empty(field: &str, value: Option<&str>) -> Result<(), ConfigError> {
+    match value {
+        Some(value) if value.trim().is_empty() => Err(invalid(field, "must not be empty")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{OllamaConfig, OpenAiConfig};
+
+    fn parse(content: &str) -> (Config, Vec<String>) {
+        deserialize_tracking_unknown(toml::Deserializer::parse(content).unwrap()).unwrap()
+    }
+
+    mod unknown_keys {
+        use super::*;
+
+        #[test]
+        fn reports_misspelled_keys_and_sections() {
+            let (config, unknown) = parse(
+                "[safety]\nconfirm_comands = false\n\n[antropic]\nmodel = \"claude\"\n\n[openai]\napi_kee = \"sk\"\n",
+            );
+            assert_eq!(
+                unknown,
+                vec!["antropic", "openai.api_kee", "safety.confirm_comands"]
+            );
+            // The misspelled setting kept its default
+            assert!(config.safety.confirm_commands);
+        }
+
+        #[test]
+        fn known_keys_are_not_reported() {
+            let (_, unknown) = parse(
+                "[general]\nlog_level = \"debug\"\n\n[profiles.home.general]\nlog_level = \"warn\"\n",
+            );
+            assert!(unknown.is_empty());
+        }
+    }
+
+    mod check {
+        use super::*;
+
+        #[test]
+        fn defaults_are_valid() {
+            assert!(Config::default().check().is_ok());
+        }
+
+        #[test]
+        fn rejects_unknown_provider() {
+            let mut config = Config::default();
+            config.general.default_provider = "antropic".to_string();
+            let err = config.check().unwrap_err();
+            assert!(err.to_string().contains("general.default_provider"));
+        }
+
+        #[test]
+        fn log_level_is_case_insensitive() {
+            let mut config = Config::default();
+            config.general.log_level = "WARN".to_string();
+            assert!(config.check().is_ok());
+            config.general.log_level = "loud".to_string();
+            assert!(config.check().is_err());
+        }
+
+        #[test]
+        fn rejects_non_http_urls() {
+            let config = Config {
+                ollama: Some(OllamaConfig {
+                    host: "localhost:11434".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            assert!(config.check().is_err());
+
+            let config = Config {
+                openai: Some(OpenAiConfig {
+                    base_url: "ftp://example.com".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            assert!(config.check().is_err());
+        }
+
+        #[test]
+        fn rejects_zero_context_window() {
+            let config = Config {
+                openai: Some(OpenAiConfig {
+                    context_window: Some(0),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let err = config.check().unwrap_err();
+            assert!(err.to_string().contains("openai.context_window"));
+        }
+
+        #[test]
+        fn rejects_empty_blocked_pattern() {
+            let mut config = Config::default();
+            config.safety.blocked_patterns.push(" ".to_string());
+            assert!(config.check().is_err());
+        }
+    }
+
+    #[test]
+    fn schema_describes_sections_and_rejects_unknown_keys() {
+        let schema = serde_json::to_value(config_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for section in ["general", "openai", "safety", "profiles"] {
+            assert!(properties.contains_key(section), "missing {section}");
+        }
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(
+            schema["$defs"]["SafetyConfig"]["additionalProperties"],
+            false
+        );
+    }
+}
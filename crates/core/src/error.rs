@@ -87,6 +87,15 @@ pub enum ConfigError {
         reason: String,
     },
 
+    /// The selected profile is not defined in any config file
+    #[error("Unknown profile '{name}' (available: {available})")]
+    UnknownProfile {
+        /// The requested profile
+        name: String,
+        /// Comma-separated names of the defined profiles
+        available: String,
+    },
+
     /// No providers are configured or available
     #[error("No providers available: {message}")]
     NoProviderAvailable {
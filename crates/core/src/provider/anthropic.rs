@@ -6,8 +6,10 @@
 //! # Configuration
 //!
 //! The provider is configured via [`AnthropicConfig`]:
-//! - `api_key`: API key (required, from env var or config file)
+//! - `api_key`: API key (required, from env var, config file, or the command,
+//!   file or keyring named by `api_key_cmd`, `api_key_file`, `api_key_keyring`)
 //! - `model`: Model to use (default: `claude-sonnet-4-20250514`)
+//! - `context_window`: Context window override (default: looked up from the model)
 //!
 //! # Example
 //!
@@ -36,6 +38,8 @@ use reqwest_eventsource::{Event, EventSource, RequestBuilderExt};
 use serde::{Deserialize, Serialize};
 
 use super::AiProvider;
+use super::context_window::{DEFAULT_CONTEXT_WINDOW, known_context_window};
+use super::tokenizer::Tokenizer;
 use super::types::{CompletionRequest, Message, Role};
 use crate::config::AnthropicConfig;
 use crate::error::{ConfigError, ProviderError};
@@ -208,6 +212,18 @@ impl AiProvider for AnthropicProvider {
             }
         })
     }
+
+    fn context_window(&self, model: Option<&str>) -> usize {
+        self.config.context_window.unwrap_or_else(|| {
+            known_context_window(model.unwrap_or(&self.config.model))
+                .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+        })
+    }
+
+    /// Claude's tokenizer isn't public, so counts are approximated.
+    fn tokenizer(&self, _model: Option<&str>) -> Tokenizer {
+        Tokenizer::Claude
+    }
 }
 
 /// Convert our messages to Anthropic format.
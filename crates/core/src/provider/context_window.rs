@@ -0,0 +1,142 @@
+//! Context window sizes and token budgets.
+//!
+//! Each model accepts a fixed number of tokens per request (its context
+//! window). Part of that window must stay free for the response, so the
+//! budget available for conversation history is the window minus the tokens
+//! reserved for output.
+//!
+//! Windows for well-known models are looked up by model name prefix. Each
+//! provider config can override the window with `context_window`.
+
+/// Context window used when the model is unknown.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 16_000;
+
+/// Maximum tokens reserved for the model's response.
+const MAX_RESERVED_OUTPUT: usize = 4_096;
+
+/// Known context windows, matched by model name prefix.
+///
+/// More specific prefixes must come before shorter ones they start with
+/// (e.g. `gpt-4o` before `gpt-4`).
+const KNOWN_WINDOWS: &[(&str, usize)] = &[
+    // OpenAI
+    ("gpt-5", 400_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1-mini", 128_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    // Anthropic
+    ("claude-", 200_000),
+];
+
+/// Looks up the context window of a well-known model.
+///
+/// Matching is by prefix, so dated snapshots such as
+/// `claude-sonnet-4-20250514` or `gpt-4o-2024-08-06` are covered.
+///
+/// # Returns
+///
+/// The window in tokens, or `None` if the model is not in the table.
+#[must_use]
+pub fn known_context_window(model: &str) -> Option<usize> {
+    let model = model.trim().to_ascii_lowercase();
+    KNOWN_WINDOWS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|&(_, window)| window)
+}
+
+/// Token budget for a single request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextBudget {
+    /// Total context window of the model, in tokens.
+    pub window: usize,
+    /// Tokens kept free for the response.
+    pub reserved_output: usize,
+}
+
+impl ContextBudget {
+    /// Creates a budget for a model with the given context window.
+    ///
+    /// Reserves up to 4,096 tokens for output, but never more than a quarter
+    /// of the window so small local models keep room for history.
+    #[must_use]
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            reserved_output: MAX_RESERVED_OUTPUT.min(window / 4),
+        }
+    }
+
+    /// Tokens available for conversation history.
+    #[must_use]
+    pub fn history_tokens(&self) -> usize {
+        self.window.saturating_sub(self.reserved_output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod known_context_window {
+        use super::*;
+
+        #[test]
+        fn matches_dated_snapshots() {
+            assert_eq!(
+                known_context_window("claude-sonnet-4-20250514"),
+                Some(200_000)
+            );
+            assert_eq!(known_context_window("gpt-4o-2024-08-06"), Some(128_000));
+        }
+
+        #[test]
+        fn prefers_more_specific_prefix() {
+            assert_eq!(known_context_window("gpt-4o-mini"), Some(128_000));
+            assert_eq!(known_context_window("gpt-4-turbo"), Some(128_000));
+            assert_eq!(known_context_window("gpt-4"), Some(8_192));
+            assert_eq!(known_context_window("o1-mini"), Some(128_000));
+        }
+
+        #[test]
+        fn ignores_case() {
+            assert_eq!(known_context_window("GPT-4o"), Some(128_000));
+        }
+
+        #[test]
+        fn unknown_model_is_none() {
+            assert_eq!(known_context_window("llama3.2"), None);
+            assert_eq!(known_context_window(""), None);
+        }
+    }
+
+    mod context_budget {
+        use super::*;
+
+        #[test]
+        fn reserves_output_tokens() {
+            let budget = ContextBudget::new(200_000);
+            assert_eq!(budget.reserved_output, 4_096);
+            assert_eq!(budget.history_tokens(), 195_904);
+        }
+
+        #[test]
+        fn small_window_reserves_a_quarter() {
+            let budget = ContextBudget::new(4_096);
+            assert_eq!(budget.reserved_output, 1_024);
+            assert_eq!(budget.history_tokens(), 3_072);
+        }
+
+        #[test]
+        fn zero_window_has_no_history() {
+            assert_eq!(ContextBudget::new(0).history_tokens(), 0);
+        }
+    }
+}
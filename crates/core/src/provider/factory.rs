@@ -11,6 +11,11 @@
 //! Invalid configurations are logged as warnings but don't block other providers.
 //! At least one provider must be successfully registered for the factory to be usable.
 //!
+//! An API key kept in a command, file or keyring is read when its provider is
+//! first looked up with [`ProviderFactory::get`], not when the factory is
+//! built, and a key that can't be read is returned as an error rather than
+//! skipped.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -20,10 +25,10 @@
 //! let factory = ProviderFactory::from_config(&config)?;
 //!
 //! // Get default provider
-//! let provider = factory.get_default();
+//! let provider = factory.get_default()?;
 //!
 //! // Get specific provider
-//! if let Some(anthropic) = factory.get("anthropic") {
+//! if let Some(anthropic) = factory.get("anthropic")? {
 //!     // Use Anthropic provider
 //! }
 //!
@@ -34,18 +39,127 @@
 //! ```
 
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use futures::future::BoxFuture;
 
-use super::{AiProvider, AnthropicProvider, OllamaProvider, OpenAiProvider};
-use crate::config::Config;
-use crate::error::ConfigError;
+use super::{
+    AiProvider, AnthropicProvider, CompletionRequest, CompletionStream, OllamaProvider,
+    OpenAiProvider,
+};
+use crate::config::{AnthropicConfig, ApiKeySource, Config, ContextConfig, OpenAiConfig};
+use crate::error::{ConfigError, ProviderError};
 
 /// Factory for creating and managing AI providers.
 ///
 /// Registers providers based on configuration and provides lookup by name.
 /// The factory ensures at least one provider is available after construction.
 pub struct ProviderFactory {
-    providers: HashMap<String, Box<dyn AiProvider>>,
+    providers: HashMap<String, Registered>,
     default_provider: String,
+    context: ContextConfig,
+}
+
+/// A registered provider.
+struct Registered {
+    /// The provider as configured, without a key still to be read
+    provider: Box<dyn AiProvider>,
+    /// Where the API key is read from on first use, if not configured inline
+    pending_key: Option<PendingKey>,
+    /// The provider with the key read from `pending_key`
+    keyed: OnceLock<Box<dyn AiProvider>>,
+}
+
+impl Registered {
+    fn ready(provider: Box<dyn AiProvider>) -> Self {
+        Self {
+            provider,
+            pending_key: None,
+            keyed: OnceLock::new(),
+        }
+    }
+
+    fn pending(provider: Box<dyn AiProvider>, key: PendingKey) -> Self {
+        Self {
+            provider,
+            pending_key: Some(key),
+            keyed: OnceLock::new(),
+        }
+    }
+
+    /// The provider ready for requests, reading its API key if needed.
+    fn keyed(&self) -> Result<&dyn AiProvider, ConfigError> {
+        let Some(key) = &self.pending_key else {
+            return Ok(self.provider.as_ref());
+        };
+        if let Some(provider) = self.keyed.get() {
+            return Ok(provider.as_ref());
+        }
+        let provider = key.build()?;
+        Ok(self.keyed.get_or_init(|| provider).as_ref())
+    }
+}
+
+/// Configuration of a provider whose API key hasn't been read yet.
+enum PendingKey {
+    OpenAi(OpenAiConfig),
+    Anthropic(AnthropicConfig),
+}
+
+impl PendingKey {
+    /// Reads the API key and builds the provider with it.
+    fn build(&self) -> Result<Box<dyn AiProvider>, ConfigError> {
+        let provider: Box<dyn AiProvider> = match self {
+            Self::OpenAi(cfg) => {
+                Box::new(OpenAiProvider::new(cfg.clone().with_resolved_api_key()?))
+            }
+            Self::Anthropic(cfg) => {
+                Box::new(AnthropicProvider::new(cfg.clone().with_resolved_api_key()?))
+            }
+        };
+        provider.validate_config()?;
+        Ok(provider)
+    }
+}
+
+/// Whether a key source has to be read (runs a command, opens a file or the keyring).
+fn is_deferred(source: ApiKeySource<'_>) -> bool {
+    !matches!(source, ApiKeySource::None | ApiKeySource::Plain(_))
+}
+
+/// Provider and model for internal calls: summaries and session titles.
+///
+/// Obtained from [`ProviderFactory::summarizer`], which honors the
+/// `[context]` config and falls back to the active provider.
+#[derive(Clone, Copy)]
+pub struct Summarizer<'a> {
+    /// Provider that handles the calls
+    pub provider: &'a dyn AiProvider,
+    /// Model override, or `None` for the provider's configured model
+    pub model: Option<&'a str>,
+}
+
+impl<'a> Summarizer<'a> {
+    /// Uses a provider with its configured model.
+    #[must_use]
+    pub fn new(provider: &'a dyn AiProvider) -> Self {
+        Self {
+            provider,
+            model: None,
+        }
+    }
+
+    /// Sends a completion request with the summarizer's model.
+    pub fn complete(
+        &self,
+        request: CompletionRequest,
+    ) -> BoxFuture<'a, Result<CompletionStream, ProviderError>> {
+        let request = match self.model {
+            Some(model) => request.with_model(model),
+            None => request,
+        };
+        self.provider.complete(request)
+    }
 }
 
 impl ProviderFactory {
@@ -57,6 +171,9 @@ impl ProviderFactory {
     /// 3. Registers providers that pass validation (invalid ones are skipped with a warning)
     /// 4. Validates the default_provider setting
     ///
+    /// API keys from a command, file or keyring are not read here; those
+    /// providers are registered and checked when first looked up.
+    ///
     /// # Errors
     ///
     /// Returns [`ConfigError::MissingField`] if no providers could be registered.
@@ -68,25 +185,33 @@ impl ProviderFactory {
     /// let factory = ProviderFactory::from_config(&config)?;
     /// ```
     pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
-        let mut providers: HashMap<String, Box<dyn AiProvider>> = HashMap::new();
+        let mut providers: HashMap<String, Registered> = HashMap::new();
 
         // Register OpenAI if configured
+        // A key from a command, file or keyring is read on first use
         if let Some(ref cfg) = config.openai {
-            let provider = OpenAiProvider::new(cfg.clone());
-            if let Err(e) = provider.validate_config() {
+            let provider = Box::new(OpenAiProvider::new(cfg.clone()));
+            if is_deferred(cfg.api_key_source()) {
+                let key = PendingKey::OpenAi(cfg.clone());
+                providers.insert("openai".to_string(), Registered::pending(provider, key));
+            } else if let Err(e) = provider.validate_config() {
                 tracing::warn!("OpenAI config invalid, skipping: {e}");
             } else {
-                providers.insert("openai".to_string(), Box::new(provider));
+                providers.insert("openai".to_string(), Registered::ready(provider));
             }
         }
 
         // Register Anthropic if configured
+        // A key from a command, file or keyring is read on first use
         if let Some(ref cfg) = config.anthropic {
-            let provider = AnthropicProvider::new(cfg.clone());
-            if let Err(e) = provider.validate_config() {
+            let provider = Box::new(AnthropicProvider::new(cfg.clone()));
+            if is_deferred(cfg.api_key_source()) {
+                let key = PendingKey::Anthropic(cfg.clone());
+                providers.insert("anthropic".to_string(), Registered::pending(provider, key));
+            } else if let Err(e) = provider.validate_config() {
                 tracing::warn!("Anthropic config invalid, skipping: {e}");
             } else {
-                providers.insert("anthropic".to_string(), Box::new(provider));
+                providers.insert("anthropic".to_string(), Registered::ready(provider));
             }
         }
 
@@ -96,7 +221,7 @@ impl ProviderFactory {
             if let Err(e) = provider.validate_config() {
                 tracing::warn!("Ollama config invalid, skipping: {e}");
             } else {
-                providers.insert("ollama".to_string(), Box::new(provider));
+                providers.insert("ollama".to_string(), Registered::ready(Box::new(provider)));
             }
         }
 
@@ -128,43 +253,63 @@ impl ProviderFactory {
             return Ok(Self {
                 providers,
                 default_provider: fallback,
+                context: config.context.clone(),
             });
         }
 
         Ok(Self {
             providers,
             default_provider,
+            context: config.context.clone(),
         })
     }
 
-    /// Get a provider by name.
+    /// Get a provider by name, reading its API key on first use.
     ///
     /// Returns `None` if the provider is not registered.
     ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidValue`] if the provider's API key command,
+    /// file or keyring entry can't be read.
+    ///
     /// # Example
     ///
     /// ```ignore
-    /// if let Some(provider) = factory.get("anthropic") {
+    /// if let Some(provider) = factory.get("anthropic")? {
     ///     let stream = provider.complete(request).await?;
     /// }
     /// ```
+    pub fn get(&self, name: &str) -> Result<Option<&dyn AiProvider>, ConfigError> {
+        self.providers.get(name).map(Registered::keyed).transpose()
+    }
+
+    /// Get a provider by name without reading its API key.
+    ///
+    /// Enough for the model's context window and tokenizer. Don't send
+    /// requests through it: a key from a command, file or keyring is missing.
+    /// Returns `None` if the provider is not registered.
     #[must_use]
-    pub fn get(&self, name: &str) -> Option<&dyn AiProvider> {
-        self.providers.get(name).map(|p| p.as_ref())
+    pub fn get_unkeyed(&self, name: &str) -> Option<&dyn AiProvider> {
+        self.providers.get(name).map(|p| p.provider.as_ref())
     }
 
-    /// Get the default provider.
+    /// Get the default provider, reading its API key on first use.
+    ///
+    /// The provider is guaranteed to exist after successful factory construction.
+    ///
+    /// # Errors
     ///
-    /// This is guaranteed to return a valid provider after successful factory construction.
+    /// Returns [`ConfigError::InvalidValue`] if the provider's API key command,
+    /// file or keyring entry can't be read.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// let provider = factory.get_default();
+    /// let provider = factory.get_default()?;
     /// let stream = provider.complete(request).await?;
     /// ```
-    #[must_use]
-    pub fn get_default(&self) -> &dyn AiProvider {
+    pub fn get_default(&self) -> Result<&dyn AiProvider, ConfigError> {
         // SAFETY: default_provider is guaranteed to exist after from_config succeeds.
         // The invariant is maintained by from_config which either:
         // - Sets default_provider to config value (after validating it exists), or
@@ -172,7 +317,7 @@ impl ProviderFactory {
         self.providers
             .get(&self.default_provider)
             .unwrap_or_else(|| unreachable!("default_provider invariant violated"))
-            .as_ref()
+            .keyed()
     }
 
     /// Get the name of the default provider.
@@ -218,6 +363,52 @@ impl ProviderFactory {
     pub fn contains(&self, name: &str) -> bool {
         self.providers.contains_key(name)
     }
+
+    /// Get the provider and model for summaries and session titles.
+    ///
+    /// Uses `[context] summarizer_provider` and `summarizer_model` when set.
+    /// Falls back to `active` (with its own model) if no summarizer provider
+    /// is configured or it isn't available (including when its API key can't
+    /// be read); `summarizer_model` alone applies to the active provider.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let summarizer = factory.summarizer(factory.get_default().unwrap());
+    /// let stream = summarizer.complete(request).await?;
+    /// ```
+    #[must_use]
+    pub fn summarizer<'a>(&'a self, active: &'a dyn AiProvider) -> Summarizer<'a> {
+        let model = self.context.summarizer_model.as_deref();
+
+        let Some(name) = self.context.summarizer_provider.as_deref() else {
+            return Summarizer {
+                provider: active,
+                model,
+            };
+        };
+
+        match self.get(name) {
+            Ok(Some(provider)) => Summarizer { provider, model },
+            Ok(None) => {
+                tracing::warn!(
+                    "Summarizer provider '{}' not available, using '{}'",
+                    name,
+                    active.provider_id()
+                );
+                Summarizer::new(active)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Summarizer provider '{}' unavailable ({}), using '{}'",
+                    name,
+                    e,
+                    active.provider_id()
+                );
+                Summarizer::new(active)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -378,7 +569,7 @@ mod tests {
             let config = fixtures::config_openai_only();
             let factory = ProviderFactory::from_config(&config).unwrap();
 
-            let provider = factory.get("openai");
+            let provider = factory.get("openai").unwrap();
             assert!(provider.is_some());
             assert_eq!(provider.unwrap().provider_id(), "openai");
         }
@@ -388,7 +579,50 @@ mod tests {
             let config = fixtures::config_openai_only();
             let factory = ProviderFactory::from_config(&config).unwrap();
 
-            assert!(factory.get("nonexistent").is_none());
+            assert!(factory.get("nonexistent").unwrap().is_none());
+        }
+
+        #[test]
+        fn reads_key_command_on_first_use() {
+            let temp = tempfile::TempDir::new().unwrap();
+            let marker = temp.path().join("ran");
+            let mut config = fixtures::config_multiple_providers();
+            config.openai = Some(OpenAiConfig {
+                api_key_cmd: Some(format!("touch '{}' && echo sk-cmd", marker.display())),
+                ..Default::default()
+            });
+
+            let factory = ProviderFactory::from_config(&config).unwrap();
+            assert!(factory.contains("openai"));
+            assert!(factory.get_unkeyed("openai").is_some());
+            factory.get("anthropic").unwrap();
+            assert!(!marker.exists());
+
+            let provider = factory.get("openai").unwrap().unwrap();
+            assert_eq!(provider.provider_id(), "openai");
+            assert!(marker.exists());
+        }
+
+        #[test]
+        fn unreadable_key_is_an_error() {
+            let mut config = fixtures::config_multiple_providers();
+            config.anthropic = Some(AnthropicConfig {
+                api_key_cmd: Some("exit 1".to_string()),
+                ..Default::default()
+            });
+
+            let factory = ProviderFactory::from_config(&config).unwrap();
+
+            // Still the default: no silent fallback to another provider
+            assert_eq!(factory.default_provider_name(), "anthropic");
+            assert!(matches!(
+                factory.get("anthropic"),
+                Err(ConfigError::InvalidValue { field, .. }) if field == "anthropic.api_key_cmd"
+            ));
+            assert!(matches!(
+                factory.get_default(),
+                Err(ConfigError::InvalidValue { .. })
+            ));
         }
     }
 
@@ -400,11 +634,76 @@ mod tests {
             let config = fixtures::config_multiple_providers();
             let factory = ProviderFactory::from_config(&config).unwrap();
 
-            let provider = factory.get_default();
+            let provider = factory.get_default().unwrap();
             assert_eq!(provider.provider_id(), "anthropic");
         }
     }
 
+    mod summarizer {
+        use super::*;
+        use crate::config::ContextConfig;
+
+        fn config_with_context(context: ContextConfig) -> Config {
+            Config {
+                context,
+                ..fixtures::config_multiple_providers()
+            }
+        }
+
+        #[test]
+        fn defaults_to_active_provider() {
+            let factory =
+                ProviderFactory::from_config(&fixtures::config_multiple_providers()).unwrap();
+
+            let summarizer = factory.summarizer(factory.get_default().unwrap());
+            assert_eq!(summarizer.provider.provider_id(), "anthropic");
+            assert!(summarizer.model.is_none());
+        }
+
+        #[test]
+        fn uses_configured_provider_and_model() {
+            let config = config_with_context(ContextConfig {
+                summarizer_provider: Some("ollama".to_string()),
+                summarizer_model: Some("llama3.2:1b".to_string()),
+            });
+            let factory = ProviderFactory::from_config(&config).unwrap();
+
+            let summarizer = factory.summarizer(factory.get_default().unwrap());
+            assert_eq!(summarizer.provider.provider_id(), "ollama");
+            assert_eq!(summarizer.model, Some("llama3.2:1b"));
+        }
+
+        #[test]
+        fn model_alone_applies_to_active_provider() {
+            let config = config_with_context(ContextConfig {
+                summarizer_provider: None,
+                summarizer_model: Some("claude-3-5-haiku-latest".to_string()),
+            });
+            let factory = ProviderFactory::from_config(&config).unwrap();
+
+            let summarizer = factory.summarizer(factory.get_default().unwrap());
+            assert_eq!(summarizer.provider.provider_id(), "anthropic");
+            assert_eq!(summarizer.model, Some("claude-3-5-haiku-latest"));
+        }
+
+        #[test]
+        fn unavailable_provider_falls_back_to_active() {
+            let config = Config {
+                context: ContextConfig {
+                    summarizer_provider: Some("ollama".to_string()),
+                    summarizer_model: Some("llama3.2:1b".to_string()),
+                },
+                ..fixtures::config_openai_only()
+            };
+            let factory = ProviderFactory::from_config(&config).unwrap();
+
+            let summarizer = factory.summarizer(factory.get_default().unwrap());
+            assert_eq!(summarizer.provider.provider_id(), "openai");
+            // The model belongs to the missing provider
+            assert!(summarizer.model.is_none());
+        }
+    }
+
     mod list {
         use super::*;
 
@@ -26,6 +26,8 @@
 //! - [`CompletionRequest`]: Request configuration
 //! - [`Message`]: A single conversation message
 //! - [`Role`]: Message sender role (System, User, Assistant)
+//! - [`ContextBudget`]: Tokens available for history in a model's context window
+//! - [`Tokenizer`]: Token counting for a model family
 //!
 //! # Example
 //!
@@ -49,18 +51,24 @@
 //! ```
 
 mod anthropic;
+mod context_window;
 mod factory;
 mod ollama;
 mod openai;
 pub mod sse;
 mod system_prompts;
+mod tokenizer;
 mod r#trait;
 mod types;
 
 pub use anthropic::AnthropicProvider;
-pub use factory::ProviderFactory;
+pub use context_window::{ContextBudget, DEFAULT_CONTEXT_WINDOW, known_context_window};
+pub use factory::{ProviderFactory, Summarizer};
 pub use ollama::OllamaProvider;
 pub use openai::OpenAiProvider;
-pub use system_prompts::{COMMAND_MODE_PROMPT, command_mode_system_prompt};
+pub use system_prompts::{
+    COMMAND_MODE_PROMPT, PromptVars, command_mode_system_prompt, render_prompt,
+};
+pub use tokenizer::Tokenizer;
 pub use r#trait::{AiProvider, CompletionStream};
-pub use types::{CompletionRequest, Message, Role};
+pub use types::{CompletionRequest, Message, Role, Usage};
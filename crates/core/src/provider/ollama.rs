@@ -8,6 +8,7 @@
 //! The provider is configured via [`OllamaConfig`]:
 //! - `host`: Ollama server URL (default: `http://localhost:11434`)
 //! - `model`: Model to use (default: `llama3.2`)
+//! - `context_window`: Context size sent as `num_ctx` (default: Ollama's 4096)
 //!
 //! # No Authentication
 //!
@@ -32,6 +33,8 @@
 //! let stream = provider.complete(request).await?;
 //! ```
 
+use std::sync::{Arc, Mutex};
+
 use async_stream::try_stream;
 use futures::future::BoxFuture;
 use futures::{Stream, StreamExt};
@@ -39,10 +42,13 @@ use reqwest::Client;
 use serde::Serialize;
 
 use super::AiProvider;
-use super::types::{CompletionRequest, Message};
+use super::types::{CompletionRequest, Message, Usage};
 use crate::config::OllamaConfig;
 use crate::error::{ConfigError, ProviderError};
 
+/// Context size Ollama loads models with when `num_ctx` is not set.
+const OLLAMA_DEFAULT_NUM_CTX: usize = 4096;
+
 /// Ollama local inference provider.
 ///
 /// Implements streaming completions using Ollama's chat API with NDJSON streaming.
@@ -50,6 +56,8 @@ use crate::error::{ConfigError, ProviderError};
 pub struct OllamaProvider {
     client: Client,
     config: OllamaConfig,
+    /// Token counts from the final chunk of the last completion
+    last_usage: Arc<Mutex<Option<Usage>>>,
 }
 
 impl OllamaProvider {
@@ -63,6 +71,7 @@ impl OllamaProvider {
         Self {
             client: Client::new(),
             config,
+            last_usage: Arc::new(Mutex::new(None)),
         }
     }
 }
@@ -73,6 +82,14 @@ struct OllamaChatRequest {
     model: String,
     messages: Vec<Message>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+}
+
+/// Model parameters for Ollama chat API.
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    num_ctx: usize,
 }
 
 impl AiProvider for OllamaProvider {
@@ -84,6 +101,9 @@ impl AiProvider for OllamaProvider {
         let client = self.client.clone();
         let host = self.config.host.clone();
         let model = request.model.unwrap_or_else(|| self.config.model.clone());
+        let context_window = self.config.context_window;
+        let last_usage = Arc::clone(&self.last_usage);
+        *lock_usage(&last_usage) = None;
 
         Box::pin(async move {
             let url = format!("{}/api/chat", host);
@@ -92,6 +112,7 @@ impl AiProvider for OllamaProvider {
                 model,
                 messages: request.messages,
                 stream: true,
+                options: context_window.map(|num_ctx| OllamaOptions { num_ctx }),
             };
 
             // Make the request
@@ -127,7 +148,7 @@ impl AiProvider for OllamaProvider {
             }
 
             // Return a stream that parses NDJSON
-            let stream = parse_ollama_ndjson_stream(response);
+            let stream = parse_ollama_ndjson_stream(response, last_usage);
             Ok(Box::pin(stream) as super::CompletionStream)
         })
     }
@@ -178,6 +199,33 @@ impl AiProvider for OllamaProvider {
             }
         })
     }
+
+    /// Ollama loads every model with the same `num_ctx`, whatever the model
+    /// itself supports, so the window comes from config rather than the model.
+    fn context_window(&self, _model: Option<&str>) -> usize {
+        self.config.context_window.unwrap_or(OLLAMA_DEFAULT_NUM_CTX)
+    }
+
+    /// Ollama reports `prompt_eval_count` and `eval_count` in the final chunk.
+    fn last_usage(&self) -> Option<Usage> {
+        *lock_usage(&self.last_usage)
+    }
+}
+
+/// Locks the shared usage slot, recovering it if a stream panicked while holding it.
+fn lock_usage(usage: &Mutex<Option<Usage>>) -> std::sync::MutexGuard<'_, Option<Usage>> {
+    usage
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Extracts token counts from the final (`"done": true`) chunk.
+fn parse_usage(json: &serde_json::Value) -> Usage {
+    let count = |key: &str| json[key].as_u64().and_then(|n| u32::try_from(n).ok());
+    Usage {
+        prompt_tokens: count("prompt_eval_count"),
+        completion_tokens: count("eval_count"),
+    }
 }
 
 /// Parse Ollama's NDJSON streaming response.
@@ -190,9 +238,11 @@ impl AiProvider for OllamaProvider {
 /// ```
 ///
 /// Network chunks don't align with JSON line boundaries, so we buffer bytes
-/// and parse complete lines as they arrive.
+/// and parse complete lines as they arrive. Token counts from the final
+/// chunk are stored in `last_usage`.
 fn parse_ollama_ndjson_stream(
     response: reqwest::Response,
+    last_usage: Arc<Mutex<Option<Usage>>>,
 ) -> impl Stream<Item = Result<String, ProviderError>> {
     try_stream! {
         let mut buffer = Vec::new();
@@ -230,6 +280,7 @@ fn parse_ollama_ndjson_stream(
 
                 // Check if stream is done
                 if json["done"].as_bool() == Some(true) {
+                    *lock_usage(&last_usage) = Some(parse_usage(&json));
                     return;
                 }
             }
@@ -249,6 +300,10 @@ fn parse_ollama_ndjson_stream(
                 {
                     yield content.to_string();
                 }
+
+                if json["done"].as_bool() == Some(true) {
+                    *lock_usage(&last_usage) = Some(parse_usage(&json));
+                }
             }
         }
     }
@@ -273,6 +328,7 @@ mod tests {
             let config = OllamaConfig {
                 host: "".to_string(),
                 model: "llama3.2".to_string(),
+                context_window: None,
             };
             let provider = OllamaProvider::new(config);
             let result = provider.validate_config();
@@ -290,6 +346,77 @@ mod tests {
         }
     }
 
+    mod context_window {
+        use super::*;
+
+        #[test]
+        fn defaults_to_ollama_num_ctx() {
+            let provider = OllamaProvider::new(OllamaConfig::default());
+            assert_eq!(provider.context_window(None), OLLAMA_DEFAULT_NUM_CTX);
+        }
+
+        #[test]
+        fn uses_configured_window() {
+            let config = OllamaConfig {
+                context_window: Some(32_768),
+                ..Default::default()
+            };
+            let provider = OllamaProvider::new(config);
+            assert_eq!(provider.context_window(Some("qwen2.5")), 32_768);
+        }
+
+        #[test]
+        fn sends_num_ctx_only_when_configured() {
+            let body = OllamaChatRequest {
+                model: "llama3.2".to_string(),
+                messages: vec![],
+                stream: true,
+                options: None,
+            };
+            let json = serde_json::to_value(&body).unwrap();
+            assert!(json.get("options").is_none());
+
+            let body = OllamaChatRequest {
+                options: Some(OllamaOptions { num_ctx: 8192 }),
+                ..body
+            };
+            let json = serde_json::to_value(&body).unwrap();
+            assert_eq!(json["options"]["num_ctx"], 8192);
+        }
+    }
+
+    mod parse_usage {
+        use super::*;
+
+        #[test]
+        fn reads_counts_from_done_chunk() {
+            let json = serde_json::json!({
+                "done": true,
+                "prompt_eval_count": 26,
+                "eval_count": 298
+            });
+            assert_eq!(
+                parse_usage(&json),
+                Usage {
+                    prompt_tokens: Some(26),
+                    completion_tokens: Some(298),
+                }
+            );
+        }
+
+        #[test]
+        fn missing_counts_are_none() {
+            let json = serde_json::json!({ "done": true });
+            assert_eq!(parse_usage(&json), Usage::default());
+        }
+
+        #[test]
+        fn no_usage_before_first_completion() {
+            let provider = OllamaProvider::new(OllamaConfig::default());
+            assert_eq!(provider.last_usage(), None);
+        }
+    }
+
     mod provider_traits {
         use super::*;
 
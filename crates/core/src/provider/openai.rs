@@ -6,9 +6,11 @@
 //! # Configuration
 //!
 //! The provider is configured via [`OpenAiConfig`]:
-//! - `api_key`: API key (required, from env var or config file)
+//! - `api_key`: API key (required, from env var, config file, or the command,
+//!   file or keyring named by `api_key_cmd`, `api_key_file`, `api_key_keyring`)
 //! - `base_url`: API base URL (default: `https://api.openai.com/v1`)
 //! - `model`: Model to use (default: `gpt-4o`)
+//! - `context_window`: Context window override (default: looked up from the model)
 //!
 //! # Example
 //!
@@ -37,7 +39,9 @@ use reqwest_eventsource::{Event, EventSource, RequestBuilderExt};
 use serde::Serialize;
 
 use super::AiProvider;
+use super::context_window::{DEFAULT_CONTEXT_WINDOW, known_context_window};
 use super::sse::parse_sse_chunk;
+use super::tokenizer::Tokenizer;
 use super::types::{CompletionRequest, Message};
 use crate::config::OpenAiConfig;
 use crate::error::{ConfigError, ProviderError};
@@ -177,6 +181,17 @@ impl AiProvider for OpenAiProvider {
             }
         })
     }
+
+    fn context_window(&self, model: Option<&str>) -> usize {
+        self.config.context_window.unwrap_or_else(|| {
+            known_context_window(model.unwrap_or(&self.config.model))
+                .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+        })
+    }
+
+    fn tokenizer(&self, model: Option<&str>) -> Tokenizer {
+        Tokenizer::for_openai_model(model.unwrap_or(&self.config.model))
+    }
 }
 
 /// Create a stream that processes SSE events and yields text chunks.
@@ -298,6 +313,53 @@ mod tests {
         }
     }
 
+    mod context_window {
+        use super::*;
+
+        #[test]
+        fn looks_up_configured_model() {
+            let provider = OpenAiProvider::new(OpenAiConfig::default());
+            assert_eq!(provider.context_window(None), 128_000);
+        }
+
+        #[test]
+        fn looks_up_request_model() {
+            let provider = OpenAiProvider::new(OpenAiConfig::default());
+            assert_eq!(provider.context_window(Some("gpt-4")), 8_192);
+        }
+
+        #[test]
+        fn unknown_model_uses_default() {
+            let config = OpenAiConfig {
+                model: "my-local-model".to_string(),
+                ..Default::default()
+            };
+            let provider = OpenAiProvider::new(config);
+            assert_eq!(provider.context_window(None), DEFAULT_CONTEXT_WINDOW);
+        }
+
+        #[test]
+        fn config_override_wins() {
+            let config = OpenAiConfig {
+                context_window: Some(32_000),
+                ..Default::default()
+            };
+            let provider = OpenAiProvider::new(config);
+            assert_eq!(provider.context_window(Some("gpt-4o")), 32_000);
+        }
+    }
+
+    mod tokenizer {
+        use super::*;
+
+        #[test]
+        fn follows_request_model() {
+            let provider = OpenAiProvider::new(OpenAiConfig::default());
+            assert_eq!(provider.tokenizer(None), Tokenizer::O200k);
+            assert_eq!(provider.tokenizer(Some("gpt-4")), Tokenizer::Cl100k);
+        }
+    }
+
     mod provider_traits {
         use super::*;
 
@@ -1,7 +1,13 @@
 //! System prompts for AI behavior configuration.
 //!
 //! Provides system prompt snippets that configure AI behavior for different
-//! modes of operation.
+//! modes of operation, and renders user-defined prompt templates.
+//!
+//! # Templates
+//!
+//! Prompt templates may reference the environment with `{os}`, `{shell}`,
+//! `{cwd}`, `{project_root}` and `{git_branch}`. Any other text in braces is
+//! left untouched, so templates can contain code.
 
 /// System prompt snippet for command suggestion mode.
 ///
@@ -24,6 +30,16 @@ When the user wants an explanation or information:
 - Provide a clear, concise answer without code blocks
 - Only include code blocks if demonstrating syntax
 
+When changing part of an existing file, send only the change as a
+search/replace block instead of the whole file:
+path/to/file
+<<<<<<< SEARCH
+exact lines from the current file
+=======
+replacement lines
+>>>>>>> REPLACE
+Unified diffs in a ```diff block are also accepted.
+
 Explicit mode markers (user can force a mode):
 - `!` at start or `/run` at start = always suggest a command
 - `?` at end = always provide explanation, never suggest command
@@ -35,10 +51,110 @@ pub fn command_mode_system_prompt() -> &'static str {
     COMMAND_MODE_PROMPT
 }
 
+/// Values substituted into prompt templates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptVars {
+    /// Operating system (e.g. `linux`, `macos`)
+    pub os: String,
+    /// The user's shell (e.g. `zsh`)
+    pub shell: String,
+    /// Current working directory
+    pub cwd: String,
+    /// Project root (git working directory, or the cwd outside git)
+    pub project_root: String,
+    /// Current git branch, empty outside git
+    pub git_branch: String,
+}
+
+impl PromptVars {
+    /// Looks up the value of a template variable.
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "os" => Some(&self.os),
+            "shell" => Some(&self.shell),
+            "cwd" => Some(&self.cwd),
+            "project_root" => Some(&self.project_root),
+            "git_branch" => Some(&self.git_branch),
+            _ => None,
+        }
+    }
+}
+
+/// Renders a prompt template, replacing known `{variable}`s.
+///
+/// # Arguments
+///
+/// * `template` - The prompt template
+/// * `vars` - Values for the template variables
+///
+/// # Returns
+///
+/// The prompt with every known variable replaced. Unknown variables and other
+/// braces are kept as written.
+#[must_use]
+pub fn render_prompt(template: &str, vars: &PromptVars) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| vars.get(&after[..end]).map(|value| (value, end)));
+
+        match value {
+            Some((value, end)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn vars() -> PromptVars {
+        PromptVars {
+            os: "linux".to_string(),
+            shell: "zsh".to_string(),
+            cwd: "/repo/src".to_string(),
+            project_root: "/repo".to_string(),
+            git_branch: "main".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_replaces_known_variables() {
+        let rendered = render_prompt(
+            "On {os} with {shell} in {cwd} ({project_root}@{git_branch})",
+            &vars(),
+        );
+        assert_eq!(rendered, "On linux with zsh in /repo/src (/repo@main)");
+    }
+
+    #[test]
+    fn render_keeps_unknown_braces() {
+        let template = "fn main() { println!(\"{}\", {user}); } {os";
+        assert_eq!(render_prompt(template, &vars()), template);
+    }
+
+    #[test]
+    fn render_command_mode_prompt_unchanged() {
+        assert_eq!(
+            render_prompt(COMMAND_MODE_PROMPT, &vars()),
+            COMMAND_MODE_PROMPT
+        );
+    }
+
     #[test]
     fn command_mode_prompt_not_empty() {
         assert!(!COMMAND_MODE_PROMPT.is_empty());
@@ -50,6 +166,12 @@ mod tests {
         assert!(COMMAND_MODE_PROMPT.contains("```bash"));
     }
 
+    #[test]
+    fn command_mode_prompt_documents_edit_blocks() {
+        assert!(COMMAND_MODE_PROMPT.contains("<<<<<<< SEARCH"));
+        assert!(COMMAND_MODE_PROMPT.contains(">>>>>>> REPLACE"));
+    }
+
     #[test]
     fn command_mode_prompt_documents_markers() {
         assert!(COMMAND_MODE_PROMPT.contains('!'));
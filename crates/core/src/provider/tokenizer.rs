@@ -0,0 +1,160 @@
+//! Token counting.
+//!
+//! Context budgets are measured in tokens, and how many tokens a text takes
+//! depends on the model's tokenizer. OpenAI models use BPE encodings that
+//! ship with this crate (`cl100k_base`, `o200k_base`), so they are counted
+//! exactly and offline. Anthropic doesn't publish Claude's tokenizer, so it
+//! is approximated. Anything else falls back to a character heuristic.
+//!
+//! Providers pick the tokenizer for a model via
+//! [`AiProvider::tokenizer`](super::AiProvider::tokenizer).
+
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton};
+
+/// Characters per token assumed by [`Tokenizer::Heuristic`].
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Claude token counts relative to `cl100k_base`, in percent.
+///
+/// Claude's tokenizer produces noticeably more tokens than `cl100k_base` for
+/// the same text, so counts are scaled up to stay on the safe side.
+const CLAUDE_CL100K_RATIO_PERCENT: usize = 115;
+
+/// OpenAI model name prefixes using `o200k_base`; other models use `cl100k_base`.
+const O200K_PREFIXES: &[&str] = &[
+    "gpt-5",
+    "gpt-4.1",
+    "gpt-4o",
+    "chatgpt-4o",
+    "o1",
+    "o3",
+    "o4",
+    "ft:gpt-4o",
+];
+
+/// Counts the tokens a text takes for a family of models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// OpenAI `cl100k_base` (GPT-4, GPT-3.5)
+    Cl100k,
+    /// OpenAI `o200k_base` (GPT-4o and newer, o-series)
+    O200k,
+    /// Approximation of Claude's tokenizer
+    Claude,
+    /// Four characters per token, for models with an unknown tokenizer
+    Heuristic,
+}
+
+impl Tokenizer {
+    /// Picks the encoding of an OpenAI model.
+    ///
+    /// Unknown models (e.g. behind an OpenAI-compatible API) are counted
+    /// with `cl100k_base`, which is still far closer than the heuristic for
+    /// code and non-English text.
+    #[must_use]
+    pub fn for_openai_model(model: &str) -> Self {
+        let model = model.trim().to_ascii_lowercase();
+        if O200K_PREFIXES
+            .iter()
+            .any(|prefix| model.starts_with(prefix))
+        {
+            Self::O200k
+        } else {
+            Self::Cl100k
+        }
+    }
+
+    /// Counts the tokens in `text`.
+    #[must_use]
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Cl100k => bpe_count(cl100k_base_singleton(), text),
+            Self::O200k => bpe_count(o200k_base_singleton(), text),
+            Self::Claude => {
+                let tokens = bpe_count(cl100k_base_singleton(), text);
+                (tokens * CLAUDE_CL100K_RATIO_PERCENT).div_ceil(100)
+            }
+            Self::Heuristic => text.len() / CHARS_PER_TOKEN,
+        }
+    }
+}
+
+/// Counts tokens with a BPE encoding, treating special tokens as plain text.
+fn bpe_count(bpe: &CoreBPE, text: &str) -> usize {
+    bpe.encode_ordinary(text).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod for_openai_model {
+        use super::*;
+
+        #[test]
+        fn recent_models_use_o200k() {
+            assert_eq!(Tokenizer::for_openai_model("gpt-4o"), Tokenizer::O200k);
+            assert_eq!(
+                Tokenizer::for_openai_model("gpt-4o-mini-2024-07-18"),
+                Tokenizer::O200k
+            );
+            assert_eq!(Tokenizer::for_openai_model("o3-mini"), Tokenizer::O200k);
+            assert_eq!(Tokenizer::for_openai_model("gpt-5"), Tokenizer::O200k);
+        }
+
+        #[test]
+        fn older_and_unknown_models_use_cl100k() {
+            assert_eq!(Tokenizer::for_openai_model("gpt-4"), Tokenizer::Cl100k);
+            assert_eq!(
+                Tokenizer::for_openai_model("gpt-3.5-turbo"),
+                Tokenizer::Cl100k
+            );
+            assert_eq!(
+                Tokenizer::for_openai_model("mistral-large"),
+                Tokenizer::Cl100k
+            );
+        }
+    }
+
+    mod count {
+        use super::*;
+
+        #[test]
+        fn counts_bpe_tokens() {
+            assert_eq!(Tokenizer::Cl100k.count("hello world"), 2);
+            assert_eq!(Tokenizer::O200k.count("hello world"), 2);
+        }
+
+        #[test]
+        fn empty_text_has_no_tokens() {
+            for tokenizer in [
+                Tokenizer::Cl100k,
+                Tokenizer::O200k,
+                Tokenizer::Claude,
+                Tokenizer::Heuristic,
+            ] {
+                assert_eq!(tokenizer.count(""), 0);
+            }
+        }
+
+        #[test]
+        fn claude_counts_above_cl100k() {
+            let text = "fn main() { println!(\"{}\", 40 + 2); }";
+            assert!(Tokenizer::Claude.count(text) > Tokenizer::Cl100k.count(text));
+        }
+
+        #[test]
+        fn heuristic_uses_4_chars_per_token() {
+            assert_eq!(Tokenizer::Heuristic.count(&"a".repeat(100)), 25);
+        }
+
+        #[test]
+        fn bpe_differs_from_heuristic_for_non_english() {
+            // Each CJK character is several bytes but about one token or more
+            let text = "上下文窗口管理";
+            assert!(Tokenizer::Cl100k.count(text) >= text.chars().count());
+            assert!(Tokenizer::Heuristic.count(text) < text.chars().count() * 2);
+        }
+    }
+}
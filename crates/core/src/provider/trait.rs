@@ -19,7 +19,9 @@ use std::pin::Pin;
 use futures::Stream;
 use futures::future::BoxFuture;
 
-use super::types::CompletionRequest;
+use super::context_window::DEFAULT_CONTEXT_WINDOW;
+use super::tokenizer::Tokenizer;
+use super::types::{CompletionRequest, Usage};
 use crate::error::{ConfigError, ProviderError};
 
 /// A stream of completion chunks from an AI provider.
@@ -170,6 +172,41 @@ pub trait AiProvider: Send + Sync {
     /// - [`ProviderError::Unavailable`]: Provider is down
     /// - [`ProviderError::RequestFailed`]: Network error
     fn health_check(&self) -> BoxFuture<'_, Result<(), ProviderError>>;
+
+    /// Returns the context window, in tokens, of the model that will answer.
+    ///
+    /// Used to size the conversation history sent with each request.
+    /// Providers should honor a configured override first, then the
+    /// known window of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Model override for the request, or `None` for the configured model
+    fn context_window(&self, model: Option<&str>) -> usize {
+        let _ = model;
+        DEFAULT_CONTEXT_WINDOW
+    }
+
+    /// Returns the tokenizer used to count tokens for the model that will answer.
+    ///
+    /// Used to measure conversation history against the context budget.
+    /// Defaults to a character heuristic for models with an unknown tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Model override for the request, or `None` for the configured model
+    fn tokenizer(&self, model: Option<&str>) -> Tokenizer {
+        let _ = model;
+        Tokenizer::Heuristic
+    }
+
+    /// Returns the token usage reported for the most recent completion.
+    ///
+    /// Only available once the response stream has been fully consumed, and
+    /// only for providers whose API reports counts while streaming.
+    fn last_usage(&self) -> Option<Usage> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -164,6 +164,19 @@ impl CompletionRequest {
     }
 }
 
+/// Token counts reported by a provider for one completion.
+///
+/// Providers that report usage return it from
+/// [`AiProvider::last_usage`](super::AiProvider::last_usage) once the
+/// response stream has finished.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Tokens in the prompt, including the conversation history.
+    pub prompt_tokens: Option<u32>,
+    /// Tokens in the generated response.
+    pub completion_tokens: Option<u32>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3,14 +3,16 @@
 //! This module provides an async interface to SQLite using tokio-rusqlite,
 //! handling database initialization, XDG paths, and proper file permissions.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use directories::ProjectDirs;
 use tokio_rusqlite::Connection;
 
 use crate::StorageError;
-use crate::schema::ensure_schema;
+use crate::schema::{
+    Migration, SCHEMA_VERSION, current_version, ensure_schema, pending_migrations,
+};
 
 /// Async SQLite database wrapper
 ///
@@ -41,7 +43,8 @@ impl Database {
     /// 3. Opens/creates the SQLite database file
     /// 4. Sets file permissions to 0600 (owner read/write only)
     /// 5. Configures SQLite for robustness (busy timeout, foreign keys)
-    /// 6. Runs schema migrations if needed
+    /// 6. Backs up the database file if it needs migrating
+    /// 7. Runs schema migrations if needed
     ///
     /// # Database Location
     ///
@@ -97,13 +100,23 @@ impl Database {
         }
 
         // Configure SQLite and run migrations
-        conn.call(|conn| {
+        conn.call(move |conn| {
             // Set busy timeout to 5 seconds for concurrent access
             conn.busy_timeout(Duration::from_secs(5))?;
 
             // Enable foreign key constraints
             conn.execute_batch("PRAGMA foreign_keys = ON;")?;
 
+            // Keep a copy of the pre-migration database in case a migration misbehaves
+            let version = current_version(conn)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            if version > 0 && version < SCHEMA_VERSION {
+                let backup = backup_path(&path, version);
+                tracing::info!("Backing up database to {:?} before migrating", backup);
+                backup_database(conn, &backup)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            }
+
             // Run schema migrations
             ensure_schema(conn)
                 .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
@@ -116,6 +129,72 @@ impl Database {
         Ok(Self { conn })
     }
 
+    /// Lists the migrations that opening the database at `path` would apply
+    ///
+    /// The database is opened read-only and left untouched. A missing file
+    /// reports every migration as pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError` if the database cannot be read or was created by
+    /// a newer version of cherry2k.
+    pub async fn pending_migrations_at(
+        path: &Path,
+    ) -> Result<Vec<&'static Migration>, StorageError> {
+        if !path.exists() {
+            return Ok(crate::schema::MIGRATIONS.iter().collect());
+        }
+
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .await
+            .map_err(|e| StorageError::Database(format!("Failed to open database: {e}")))?;
+
+        conn.call(|conn| {
+            pending_migrations(conn)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))
+    }
+
+    /// Runs SQLite's `PRAGMA integrity_check` on the database at `path`
+    ///
+    /// The database is opened read-only and left untouched.
+    ///
+    /// # Returns
+    ///
+    /// The problems SQLite reports, empty if the database is intact.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::Database` if the database cannot be opened or
+    /// is not a database at all.
+    pub async fn integrity_check_at(path: &Path) -> Result<Vec<String>, StorageError> {
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .await
+            .map_err(|e| StorageError::Database(format!("Failed to open database: {e}")))?;
+
+        let messages = conn
+            .call(|conn| {
+                let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+                let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                rows.collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .map_err(|e| StorageError::Database(format!("Integrity check failed: {e}")))?;
+
+        Ok(messages.into_iter().filter(|m| m != "ok").collect())
+    }
+
+    /// Returns the schema version of the open database
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::Database` if the version cannot be read.
+    pub async fn schema_version(&self) -> Result<i32, StorageError> {
+        self.call_storage(|conn| current_version(conn)).await
+    }
+
     /// Returns the default database path based on XDG directories
     ///
     /// # Errors
@@ -191,6 +270,37 @@ impl Database {
     }
 }
 
+/// Returns the path of the pre-migration backup for a database at `version`
+///
+/// For example `sessions.db` at version 1 is backed up to `sessions.db.v1.bak`.
+pub fn backup_path(db_path: &Path, version: i32) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{version}.bak"));
+    db_path.with_file_name(name)
+}
+
+/// Writes a consistent copy of the database to `dest` with 0600 permissions
+fn backup_database(conn: &rusqlite::Connection, dest: &Path) -> Result<(), StorageError> {
+    // VACUUM INTO refuses to overwrite, so replace any stale backup
+    if dest.exists() {
+        std::fs::remove_file(dest).map_err(|e| {
+            StorageError::IoError(format!("Failed to replace database backup: {e}"))
+        })?;
+    }
+
+    conn.execute("VACUUM INTO ?1", [dest.to_string_lossy()])
+        .map_err(|e| StorageError::Migration(format!("Failed to back up database: {e}")))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dest, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| StorageError::IoError(format!("Failed to set backup permissions: {e}")))?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,4 +411,104 @@ mod tests {
         let err = result.unwrap_err();
         assert!(matches!(err, StorageError::Database(_)));
     }
+
+    #[tokio::test]
+    async fn integrity_check_passes_for_new_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("intact.db");
+        drop(Database::open_at(db_path.clone()).await.unwrap());
+
+        assert!(
+            Database::integrity_check_at(&db_path)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn integrity_check_rejects_non_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("garbage.db");
+        std::fs::write(&db_path, "definitely not sqlite, just some text").unwrap();
+
+        assert!(Database::integrity_check_at(&db_path).await.is_err());
+    }
+
+    /// Creates a database file at the given (older) schema version.
+    fn create_db_at_version(path: &Path, version: i32) {
+        let conn = rusqlite::Connection::open(path).unwrap();
+        crate::schema::migrate_to(&conn, version).unwrap();
+    }
+
+    #[tokio::test]
+    async fn migration_backs_up_old_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("old.db");
+        create_db_at_version(&db_path, 1);
+
+        let db = Database::open_at(db_path.clone()).await.unwrap();
+
+        assert_eq!(db.schema_version().await.unwrap(), SCHEMA_VERSION);
+        let backup = backup_path(&db_path, 1);
+        assert!(backup.exists(), "backup should be written before migrating");
+        let backup_conn = rusqlite::Connection::open(&backup).unwrap();
+        assert_eq!(current_version(&backup_conn).unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn fresh_database_is_not_backed_up() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("fresh.db");
+
+        let _db = Database::open_at(db_path.clone()).await.unwrap();
+
+        let entries = std::fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(entries, 1, "only the database file should exist");
+    }
+
+    #[test]
+    fn backup_path_appends_version() {
+        let path = backup_path(Path::new("/data/sessions.db"), 3);
+        assert_eq!(path, Path::new("/data/sessions.db.v3.bak"));
+    }
+
+    mod pending_migrations_at {
+        use super::*;
+
+        #[tokio::test]
+        async fn missing_database_has_all_pending() {
+            let temp_dir = TempDir::new().unwrap();
+            let db_path = temp_dir.path().join("missing.db");
+
+            let pending = Database::pending_migrations_at(&db_path).await.unwrap();
+
+            assert_eq!(pending.len(), SCHEMA_VERSION as usize);
+            assert!(!db_path.exists(), "dry run should not create the database");
+        }
+
+        #[tokio::test]
+        async fn old_database_lists_remaining_without_migrating() {
+            let temp_dir = TempDir::new().unwrap();
+            let db_path = temp_dir.path().join("old.db");
+            create_db_at_version(&db_path, 1);
+
+            let pending = Database::pending_migrations_at(&db_path).await.unwrap();
+
+            assert_eq!(pending.first().unwrap().version, 2);
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            assert_eq!(current_version(&conn).unwrap(), 1);
+        }
+
+        #[tokio::test]
+        async fn current_database_has_none_pending() {
+            let temp_dir = TempDir::new().unwrap();
+            let db_path = temp_dir.path().join("current.db");
+            drop(Database::open_at(db_path.clone()).await.unwrap());
+
+            let pending = Database::pending_migrations_at(&db_path).await.unwrap();
+
+            assert!(pending.is_empty());
+        }
+    }
 }
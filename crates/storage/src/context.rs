@@ -4,28 +4,57 @@
 //! including token estimation and automatic summarization when approaching
 //! context limits.
 //!
+//! # Token Counting
+//!
+//! Messages are measured with the tokenizer of the model that will answer
+//! (see [`AiProvider::tokenizer`]). A message that already has a
+//! `token_count`, such as the count a provider reported for an answer, uses
+//! that instead, but only if it was stored by the provider that will answer:
+//! each provider counts with its own tokenizer, so a count from another one
+//! can be far off.
+//!
 //! # Token Budget
 //!
-//! The context window is managed with a 16,000 token budget. When the
-//! conversation exceeds 75% of this budget, older messages are summarized
-//! using the AI provider to preserve context while staying within limits.
+//! The budget comes from the context window of the model that will answer
+//! (see [`AiProvider::context_window`]), minus the tokens reserved for its
+//! response. When the conversation exceeds 75% of this budget, older
+//! messages are summarized using the AI provider to preserve context while
+//! staying within limits.
+//!
+//! # Summaries
+//!
+//! Summarization never deletes anything. The summary is stored as a new
+//! system message and the messages it covers are linked to it through
+//! `summarized_by`, so the full transcript stays available to `resume`,
+//! export and search. Only messages that have not been summarized are sent
+//! to the provider, with the current summary first.
+//!
+//! # Pinned Messages
+//!
+//! Pinned messages are never summarized or dropped to fit the budget. They
+//! are sent verbatim and keep their place in the history, so the
+//! conversation stays in time order.
+//!
+//! Summarizing takes an extra round-trip to the provider, so it is deferred
+//! until after the answer is delivered (see [`summarize_session`]). A lock
+//! row in `summarization_locks` keeps two processes from summarizing the
+//! same session at once.
 
 use futures::StreamExt;
 
-use cherry2k_core::provider::{AiProvider, CompletionRequest, Message, Role};
+use cherry2k_core::provider::{
+    AiProvider, CompletionRequest, ContextBudget, Message, Role, Summarizer, Tokenizer,
+};
 
 use crate::Database;
 use crate::StorageError;
 use crate::message::{StoredMessage, get_messages};
 
-/// Maximum tokens for conversation history.
-const TOKEN_BUDGET: usize = 16_000;
-
 /// Trigger summarization at 75% of token budget.
 const SUMMARIZE_THRESHOLD: f32 = 0.75;
 
-/// Conservative estimate: 4 characters per token.
-const CHARS_PER_TOKEN: usize = 4;
+/// Summarization locks older than this are considered abandoned.
+const LOCK_TIMEOUT_SECS: u64 = 600;
 
 /// Prompt template for summarizing conversation history.
 const SUMMARIZATION_PROMPT: &str = r#"Summarize the following conversation history, preserving:
@@ -44,32 +73,54 @@ Summary:"#;
 /// Result of context preparation.
 ///
 /// Contains the messages ready to send to the provider and indicates
-/// whether summarization occurred.
+/// whether summarization occurred or should run after this turn.
 #[derive(Debug, Clone)]
-#[must_use = "ContextResult contains was_summarized flag that should be checked"]
+#[must_use = "ContextResult contains was_summarized and needs_summary flags that should be checked"]
 pub struct ContextResult {
     /// Messages to send to provider (converted from StoredMessage).
     pub messages: Vec<Message>,
     /// True if summarization occurred during preparation.
     pub was_summarized: bool,
+    /// True if the history is over the summarization threshold and
+    /// [`summarize_session`] should run once the answer is delivered.
+    pub needs_summary: bool,
 }
 
 /// Estimates token count for a list of messages.
 ///
-/// Uses a conservative heuristic of 4 characters per token.
-/// This is a simple but sufficient approximation for Phase 03.
+/// Uses each message's stored `token_count` when it was stored by
+/// `provider_id`, and counts the content with `tokenizer` otherwise.
 ///
 /// # Arguments
 ///
 /// * `messages` - The messages to estimate tokens for
+/// * `provider_id` - Provider the messages will be sent to, or `None` to
+///   count every message with `tokenizer`
+/// * `tokenizer` - Tokenizer of the model the messages will be sent to
 ///
 /// # Returns
 ///
 /// Estimated token count.
 #[must_use]
-pub fn estimate_tokens(messages: &[StoredMessage]) -> usize {
-    let total_chars: usize = messages.iter().map(|m| m.content.len()).sum();
-    total_chars / CHARS_PER_TOKEN
+pub fn estimate_tokens(
+    messages: &[StoredMessage],
+    provider_id: Option<&str>,
+    tokenizer: Tokenizer,
+) -> usize {
+    messages
+        .iter()
+        .map(|m| match m.token_count {
+            Some(count) if provider_id.is_some() && m.provider.as_deref() == provider_id => {
+                usize::try_from(count).unwrap_or(0)
+            }
+            _ => tokenizer.count(&m.content),
+        })
+        .sum()
+}
+
+/// Token count at which a conversation gets summarized.
+fn summarize_threshold(budget: ContextBudget) -> usize {
+    ((budget.history_tokens() as f32) * SUMMARIZE_THRESHOLD) as usize
 }
 
 /// Formats messages for summarization.
@@ -99,73 +150,309 @@ fn stored_to_message(stored: &StoredMessage) -> Message {
 
 /// Prepares conversation context for the AI provider.
 ///
-/// Loads messages for the session and checks if summarization is needed.
-/// If the estimated token count exceeds 75% of the budget, older messages
-/// are summarized using the provider.
+/// Loads the messages of the session that have not been summarized yet.
+/// Summarization is deferred whenever possible: if the estimated token count
+/// exceeds 75% of the history budget of the answering model, the messages
+/// are returned as they are with `needs_summary` set, and the caller runs
+/// [`summarize_session`] once the answer is delivered.
+///
+/// Only when the history no longer fits the budget at all are older messages
+/// summarized right away. If another process is already summarizing the
+/// session, there is too little to summarize or the summarizer fails, the
+/// oldest messages are left out instead. Either way the result is trimmed
+/// to the budget.
 ///
 /// # Arguments
 ///
 /// * `db` - The database connection
 /// * `session_id` - The session to load context for
-/// * `provider` - The AI provider to use for summarization
+/// * `provider` - The AI provider that will answer (sets the context window)
+/// * `model` - Model override for the request, or `None` for the provider's model
+/// * `summarizer` - Provider and model that write the summary
 ///
 /// # Returns
 ///
 /// A `ContextResult` containing messages ready for the provider and
-/// a flag indicating if summarization occurred.
+/// flags indicating if summarization occurred or is due.
 ///
 /// # Errors
 ///
-/// Returns `StorageError` if database operations fail or summarization fails.
+/// Returns `StorageError` if database operations fail.
 pub async fn prepare_context(
     db: &Database,
     session_id: &str,
     provider: &dyn AiProvider,
+    model: Option<&str>,
+    summarizer: Summarizer<'_>,
+) -> Result<ContextResult, StorageError> {
+    prepare(db, session_id, None, provider, model, summarizer).await
+}
+
+/// Prepares the context for answering a stored message again.
+///
+/// Like [`prepare_context`], but only messages older than `message_id` are
+/// included (the current summary always is), so the history ends where the
+/// message was asked whatever came after it.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to load context for
+/// * `message_id` - The message being answered again
+/// * `provider` - The AI provider that will answer (sets the context window)
+/// * `model` - Model override for the request, or `None` for the provider's model
+/// * `summarizer` - Provider and model that write the summary
+///
+/// # Errors
+///
+/// Returns `StorageError` if database operations fail.
+pub async fn prepare_context_before(
+    db: &Database,
+    session_id: &str,
+    message_id: i64,
+    provider: &dyn AiProvider,
+    model: Option<&str>,
+    summarizer: Summarizer<'_>,
 ) -> Result<ContextResult, StorageError> {
-    // Load all messages for the session
-    let messages = get_messages(db, session_id).await?;
+    prepare(
+        db,
+        session_id,
+        Some(message_id),
+        provider,
+        model,
+        summarizer,
+    )
+    .await
+}
 
-    // Check if we're under the threshold
-    let estimated_tokens = estimate_tokens(&messages);
-    let threshold_tokens = ((TOKEN_BUDGET as f32) * SUMMARIZE_THRESHOLD) as usize;
+/// Shared implementation of [`prepare_context`] and [`prepare_context_before`].
+async fn prepare(
+    db: &Database,
+    session_id: &str,
+    before: Option<i64>,
+    provider: &dyn AiProvider,
+    model: Option<&str>,
+    summarizer: Summarizer<'_>,
+) -> Result<ContextResult, StorageError> {
+    let messages = load_active_messages(db, session_id, before).await?;
+
+    let provider_id = Some(provider.provider_id());
+    let tokenizer = provider.tokenizer(model);
+    let estimated_tokens = estimate_tokens(&messages, provider_id, tokenizer);
+    let budget = ContextBudget::new(provider.context_window(model));
+    let threshold_tokens = summarize_threshold(budget);
 
-    if estimated_tokens < threshold_tokens {
-        // Under threshold - convert and return without summarization
-        let provider_messages: Vec<Message> = messages.iter().map(stored_to_message).collect();
+    if estimated_tokens <= budget.history_tokens() {
+        // Fits the budget - send as-is, summarizing later if over the threshold
         return Ok(ContextResult {
-            messages: provider_messages,
+            messages: messages.iter().map(stored_to_message).collect(),
             was_summarized: false,
+            needs_summary: estimated_tokens >= threshold_tokens,
         });
     }
 
-    // Over threshold - need to summarize
-    tracing::info!(
-        "Context exceeds threshold ({} tokens > {}), summarizing...",
-        estimated_tokens,
-        threshold_tokens
-    );
-
-    // Split messages at 50% point
-    let split_point = messages.len() / 2;
-    let (old_messages, recent_messages) = messages.split_at(split_point);
+    // Over budget - the history can't be sent as it is
+    let was_summarized = if !try_lock_summarization(db, session_id).await? {
+        tracing::info!(
+            "Context exceeds budget ({} tokens > {}) while session is being summarized, dropping oldest messages",
+            estimated_tokens,
+            budget.history_tokens()
+        );
+        false
+    } else {
+        let summarizable = messages.iter().filter(|m| !m.pinned).count();
+        let result = if summarizable < 2 {
+            tracing::info!(
+                "Context exceeds budget ({} tokens > {}) with too little to summarize, dropping oldest messages",
+                estimated_tokens,
+                budget.history_tokens()
+            );
+            Ok(false)
+        } else {
+            tracing::info!(
+                "Context exceeds budget ({} tokens > {}), summarizing...",
+                estimated_tokens,
+                budget.history_tokens()
+            );
+            summarize_messages(db, session_id, summarizer, &messages)
+                .await
+                .map(|()| true)
+        };
+        unlock_summarization(db, session_id).await?;
+        result.unwrap_or_else(|e| {
+            tracing::warn!("{}, dropping oldest messages instead", e);
+            false
+        })
+    };
 
-    // Get the ID of the first message to keep (for deletion)
-    let first_kept_id = if recent_messages.is_empty() {
-        i64::MAX
+    // The summary replaced older messages; reload so it leads the history
+    let messages = if was_summarized {
+        load_active_messages(db, session_id, before).await?
     } else {
-        recent_messages[0].id
+        messages
+    };
+    let kept = fit_to_budget(&messages, budget.history_tokens(), provider_id, tokenizer);
+
+    Ok(ContextResult {
+        messages: kept.iter().map(stored_to_message).collect(),
+        was_summarized,
+        needs_summary: false,
+    })
+}
+
+/// Summarizes older messages of a session if its context is over threshold.
+///
+/// Meant to run after an answer has been delivered (see
+/// [`ContextResult::needs_summary`]), so the next turn finds a ready summary.
+/// A lock in the database keeps concurrent summarizers off the same session.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to summarize
+/// * `provider` - The AI provider that answers in this session (sets the
+///   context window)
+/// * `model` - Model whose context window sets the threshold, or `None` for
+///   the provider's model
+/// * `summarizer` - Provider and model that write the summary
+///
+/// # Returns
+///
+/// `true` if a summary was written, `false` if none was needed or another
+/// summarizer holds the lock.
+///
+/// # Errors
+///
+/// Returns `StorageError` if database operations fail or summarization fails.
+pub async fn summarize_session(
+    db: &Database,
+    session_id: &str,
+    provider: &dyn AiProvider,
+    model: Option<&str>,
+    summarizer: Summarizer<'_>,
+) -> Result<bool, StorageError> {
+    if !try_lock_summarization(db, session_id).await? {
+        tracing::debug!("Session {} is already being summarized", session_id);
+        return Ok(false);
+    }
+
+    let result = async {
+        // Reload under the lock: another summarizer may have just finished
+        let messages = load_active_messages(db, session_id, None).await?;
+        let budget = ContextBudget::new(provider.context_window(model));
+        let tokens = estimate_tokens(
+            &messages,
+            Some(provider.provider_id()),
+            provider.tokenizer(model),
+        );
+        let summarizable = messages.iter().filter(|m| !m.pinned).count();
+        if summarizable < 2 || tokens < summarize_threshold(budget) {
+            return Ok(false);
+        }
+
+        summarize_messages(db, session_id, summarizer, &messages).await?;
+        Ok(true)
+    }
+    .await;
+
+    unlock_summarization(db, session_id).await?;
+    result
+}
+
+/// Loads the messages of a session not yet replaced by a summary.
+///
+/// The summary comes first, then the rest (pinned or not) in time order.
+/// With `before`, only messages older than that ID are loaded, besides the
+/// summary.
+async fn load_active_messages(
+    db: &Database,
+    session_id: &str,
+    before: Option<i64>,
+) -> Result<Vec<StoredMessage>, StorageError> {
+    let mut messages: Vec<StoredMessage> = get_messages(db, session_id)
+        .await?
+        .into_iter()
+        .filter(|m| m.summarized_by.is_none())
+        .filter(|m| m.is_summary || before.is_none_or(|id| m.id < id))
+        .collect();
+    messages.sort_by_key(|m| (!m.is_summary, m.id));
+    Ok(messages)
+}
+
+/// Drops the oldest messages until the rest fit in `max_tokens`.
+///
+/// Pinned messages are always kept, in place, and count against the budget
+/// first. A leading summary is kept if it fits, and the latest message is
+/// always kept.
+fn fit_to_budget(
+    messages: &[StoredMessage],
+    max_tokens: usize,
+    provider_id: Option<&str>,
+    tokenizer: Tokenizer,
+) -> Vec<StoredMessage> {
+    let (summary, rest) = match messages.split_first() {
+        Some((first, rest)) if first.is_summary => (Some(first), rest),
+        _ => (None, messages),
     };
+    let (pinned, unpinned): (Vec<StoredMessage>, Vec<StoredMessage>) =
+        rest.iter().cloned().partition(|m| m.pinned);
+    let max_tokens = max_tokens.saturating_sub(estimate_tokens(&pinned, provider_id, tokenizer));
+
+    let mut start = 0;
+    while start + 1 < unpinned.len()
+        && estimate_tokens(&unpinned[start..], provider_id, tokenizer) > max_tokens
+    {
+        start += 1;
+    }
+    let recent = &unpinned[start..];
+    let oldest_recent = recent.first().map(|m| m.id);
+
+    let mut kept = Vec::with_capacity(pinned.len() + recent.len() + 1);
+    if let Some(summary) = summary
+        && estimate_tokens(std::slice::from_ref(summary), provider_id, tokenizer)
+            + estimate_tokens(recent, provider_id, tokenizer)
+            <= max_tokens
+    {
+        kept.push(summary.clone());
+    }
+    kept.extend(
+        rest.iter()
+            .filter(|m| m.pinned || oldest_recent.is_some_and(|id| m.id >= id))
+            .cloned(),
+    );
+    kept
+}
+
+/// Summarizes the older half of `messages` and links them to the summary.
+///
+/// Pinned messages are left out of the summary. Callers make sure there are
+/// at least two other messages.
+async fn summarize_messages(
+    db: &Database,
+    session_id: &str,
+    summarizer: Summarizer<'_>,
+    messages: &[StoredMessage],
+) -> Result<(), StorageError> {
+    let candidates: Vec<&StoredMessage> = messages.iter().filter(|m| !m.pinned).collect();
+
+    // Split messages at 50% point
+    let split_point = candidates.len() / 2;
+    let old_messages: Vec<StoredMessage> = candidates[..split_point]
+        .iter()
+        .map(|&m| m.clone())
+        .collect();
+    let summarized_ids: Vec<i64> = old_messages.iter().map(|m| m.id).collect();
 
     // Format old messages for summarization
-    let conversation_text = format_for_summary(old_messages);
+    let conversation_text = format_for_summary(&old_messages);
     let prompt = SUMMARIZATION_PROMPT.replace("{conversation}", &conversation_text);
 
-    // Call provider to get summary
+    // Call the summarizer to get summary
     let request = CompletionRequest::new()
         .with_message(Message::user(&prompt))
         .with_max_tokens(1000);
 
-    let stream = provider
+    let stream = summarizer
         .complete(request)
         .await
         .map_err(|e| StorageError::Database(format!("Summarization failed: {e}")))?;
@@ -184,41 +471,81 @@ pub async fn prepare_context(
         }
     }
 
-    // Atomically delete old messages and save summary in a single transaction
-    // This prevents data loss if save_summary fails after deletion
+    // Atomically save the summary and link the old messages to it
     let session_id_owned = session_id.to_string();
-    let summary_clone = summary.clone();
     db.call(move |conn| {
         let tx = conn.transaction()?;
 
-        // Delete old messages
-        let deleted = tx.execute(
-            "DELETE FROM messages WHERE session_id = ?1 AND id < ?2",
-            rusqlite::params![session_id_owned, first_kept_id],
-        )?;
-        tracing::debug!("Deleted {} old messages during summarization", deleted);
-
         // Save summary as system message
         tx.execute(
             "INSERT INTO messages (session_id, role, content, is_summary) VALUES (?1, 'system', ?2, 1)",
-            rusqlite::params![session_id_owned, summary_clone],
+            rusqlite::params![session_id_owned, summary],
         )?;
+        let summary_id = tx.last_insert_rowid();
+
+        // Mark old messages as superseded (kept for resume, export and search)
+        let mut update =
+            tx.prepare("UPDATE messages SET summarized_by = ?1 WHERE id = ?2 AND session_id = ?3")?;
+        for id in &summarized_ids {
+            update.execute(rusqlite::params![summary_id, id, session_id_owned])?;
+        }
+        drop(update);
+        tracing::debug!(
+            "Summarized {} messages into message {}",
+            summarized_ids.len(),
+            summary_id
+        );
 
         tx.commit()?;
         Ok(())
     })
     .await
-    .map_err(|e| StorageError::Database(format!("Failed to save summary: {e}")))?;
+    .map_err(|e| StorageError::Database(format!("Failed to save summary: {e}")))
+}
 
-    // Build final message list: summary + recent messages
-    let mut result_messages = Vec::with_capacity(recent_messages.len() + 1);
-    result_messages.push(Message::system(&summary));
-    result_messages.extend(recent_messages.iter().map(stored_to_message));
+/// Takes the summarization lock for a session.
+///
+/// Locks older than [`LOCK_TIMEOUT_SECS`] are considered abandoned (e.g. the
+/// summarizer crashed) and are taken over.
+///
+/// Returns `false` if another summarizer holds the lock.
+async fn try_lock_summarization(db: &Database, session_id: &str) -> Result<bool, StorageError> {
+    let session_id = session_id.to_string();
 
-    Ok(ContextResult {
-        messages: result_messages,
-        was_summarized: true,
+    db.call(move |conn| {
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM summarization_locks
+             WHERE session_id = ?1 AND acquired_at < datetime('now', ?2)",
+            rusqlite::params![session_id, format!("-{LOCK_TIMEOUT_SECS} seconds")],
+        )?;
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO summarization_locks (session_id, pid) VALUES (?1, ?2)",
+            rusqlite::params![session_id, std::process::id()],
+        )?;
+        tx.commit()?;
+        Ok(inserted == 1)
     })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Releases the summarization lock for a session.
+///
+/// Only a lock held by this process is removed: if ours was taken over as
+/// abandoned, the new holder keeps it.
+async fn unlock_summarization(db: &Database, session_id: &str) -> Result<(), StorageError> {
+    let session_id = session_id.to_string();
+
+    db.call(move |conn| {
+        conn.execute(
+            "DELETE FROM summarization_locks WHERE session_id = ?1 AND pid = ?2",
+            rusqlite::params![session_id, std::process::id()],
+        )?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
 }
 
 #[cfg(test)]
@@ -249,7 +576,7 @@ mod tests {
         #[test]
         fn empty_messages_returns_zero() {
             let messages: Vec<StoredMessage> = vec![];
-            assert_eq!(estimate_tokens(&messages), 0);
+            assert_eq!(estimate_tokens(&messages, None, Tokenizer::Heuristic), 0);
         }
 
         #[test]
@@ -262,9 +589,12 @@ mod tests {
                 content: "a".repeat(100),
                 token_count: None,
                 is_summary: false,
+                provider: None,
+                summarized_by: None,
+                pinned: false,
                 created_at: chrono::Utc::now(),
             }];
-            assert_eq!(estimate_tokens(&messages), 25);
+            assert_eq!(estimate_tokens(&messages, None, Tokenizer::Heuristic), 25);
         }
 
         #[test]
@@ -277,6 +607,9 @@ mod tests {
                     content: "a".repeat(40), // 10 tokens
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
                 StoredMessage {
@@ -286,11 +619,72 @@ mod tests {
                     content: "b".repeat(80), // 20 tokens
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
             ];
             // Total: 120 chars / 4 = 30 tokens
-            assert_eq!(estimate_tokens(&messages), 30);
+            assert_eq!(estimate_tokens(&messages, None, Tokenizer::Heuristic), 30);
+        }
+
+        #[test]
+        fn prefers_stored_token_count() {
+            let messages = vec![StoredMessage {
+                id: 1,
+                session_id: "test".to_string(),
+                role: Role::Assistant,
+                content: "a".repeat(100),
+                token_count: Some(7),
+                is_summary: false,
+                provider: Some("ollama".to_string()),
+                summarized_by: None,
+                pinned: false,
+                created_at: chrono::Utc::now(),
+            }];
+            assert_eq!(
+                estimate_tokens(&messages, Some("ollama"), Tokenizer::Heuristic),
+                7
+            );
+        }
+
+        #[test]
+        fn recounts_tokens_stored_by_another_provider() {
+            let messages = vec![StoredMessage {
+                id: 1,
+                session_id: "test".to_string(),
+                role: Role::Assistant,
+                content: "a".repeat(100),
+                token_count: Some(7),
+                is_summary: false,
+                provider: Some("ollama".to_string()),
+                summarized_by: None,
+                pinned: false,
+                created_at: chrono::Utc::now(),
+            }];
+            assert_eq!(
+                estimate_tokens(&messages, Some("openai"), Tokenizer::Heuristic),
+                25
+            );
+            assert_eq!(estimate_tokens(&messages, None, Tokenizer::Heuristic), 25);
+        }
+
+        #[test]
+        fn counts_with_model_tokenizer() {
+            let messages = vec![StoredMessage {
+                id: 1,
+                session_id: "test".to_string(),
+                role: Role::User,
+                content: "hello world".to_string(),
+                token_count: None,
+                is_summary: false,
+                provider: None,
+                summarized_by: None,
+                pinned: false,
+                created_at: chrono::Utc::now(),
+            }];
+            assert_eq!(estimate_tokens(&messages, None, Tokenizer::Cl100k), 2);
         }
     }
 
@@ -306,6 +700,9 @@ mod tests {
                 content: "Hello".to_string(),
                 token_count: None,
                 is_summary: false,
+                provider: None,
+                summarized_by: None,
+                pinned: false,
                 created_at: chrono::Utc::now(),
             }];
             let formatted = format_for_summary(&messages);
@@ -322,6 +719,9 @@ mod tests {
                     content: "Hi".to_string(),
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
                 StoredMessage {
@@ -331,6 +731,9 @@ mod tests {
                     content: "Hello!".to_string(),
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
             ];
@@ -348,6 +751,9 @@ mod tests {
                     content: "System".to_string(),
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
                 StoredMessage {
@@ -357,6 +763,9 @@ mod tests {
                     content: "User".to_string(),
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
                 StoredMessage {
@@ -366,6 +775,9 @@ mod tests {
                     content: "Assistant".to_string(),
                     token_count: None,
                     is_summary: false,
+                    provider: None,
+                    summarized_by: None,
+                    pinned: false,
                     created_at: chrono::Utc::now(),
                 },
             ];
@@ -388,6 +800,9 @@ mod tests {
                 content: "Hello".to_string(),
                 token_count: Some(10),
                 is_summary: false,
+                provider: None,
+                summarized_by: None,
+                pinned: false,
                 created_at: chrono::Utc::now(),
             };
             let message = stored_to_message(&stored);
@@ -431,13 +846,54 @@ mod tests {
             }
         }
 
+        /// Provider whose model has a tiny context window and that always
+        /// replies with the same summary.
+        struct TinyWindowProvider;
+
+        impl AiProvider for TinyWindowProvider {
+            fn complete(
+                &self,
+                _request: CompletionRequest,
+            ) -> BoxFuture<
+                '_,
+                Result<cherry2k_core::provider::CompletionStream, cherry2k_core::ProviderError>,
+            > {
+                Box::pin(async {
+                    let stream = futures::stream::iter([Ok("Earlier talk".to_string())]);
+                    Ok(Box::pin(stream) as cherry2k_core::provider::CompletionStream)
+                })
+            }
+
+            fn provider_id(&self) -> &'static str {
+                "tiny"
+            }
+
+            fn validate_config(&self) -> Result<(), cherry2k_core::ConfigError> {
+                Ok(())
+            }
+
+            fn health_check(&self) -> BoxFuture<'_, Result<(), cherry2k_core::ProviderError>> {
+                Box::pin(async { Ok(()) })
+            }
+
+            fn context_window(&self, _model: Option<&str>) -> usize {
+                400
+            }
+        }
+
         #[tokio::test]
         async fn returns_empty_for_no_messages() {
             let (db, _temp, session_id) = setup_with_session().await;
 
-            let result = prepare_context(&db, &session_id, &DummyProvider)
-                .await
-                .unwrap();
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
 
             assert!(result.messages.is_empty());
             assert!(!result.was_summarized);
@@ -447,7 +903,7 @@ mod tests {
         async fn returns_messages_without_summarization_when_under_threshold() {
             let (db, _temp, session_id) = setup_with_session().await;
 
-            // Add a few short messages (well under 12K tokens)
+            // Add a few short messages (well under the threshold)
             save_message(&db, &session_id, Role::User, "Hello", None)
                 .await
                 .unwrap();
@@ -455,9 +911,15 @@ mod tests {
                 .await
                 .unwrap();
 
-            let result = prepare_context(&db, &session_id, &DummyProvider)
-                .await
-                .unwrap();
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
 
             assert_eq!(result.messages.len(), 2);
             assert_eq!(result.messages[0].role, Role::User);
@@ -481,31 +943,664 @@ mod tests {
                 .await
                 .unwrap();
 
-            let result = prepare_context(&db, &session_id, &DummyProvider)
-                .await
-                .unwrap();
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
 
             assert_eq!(result.messages.len(), 3);
             assert_eq!(result.messages[0].role, Role::System);
             assert_eq!(result.messages[1].role, Role::User);
             assert_eq!(result.messages[2].role, Role::Assistant);
         }
+
+        #[tokio::test]
+        async fn pinned_messages_stay_in_time_order() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let mut ids = Vec::new();
+            for (role, content) in [
+                (Role::User, "Question"),
+                (Role::Assistant, "Answer"),
+                (Role::User, "Follow-up"),
+            ] {
+                ids.push(
+                    save_message(&db, &session_id, role, content, None)
+                        .await
+                        .unwrap(),
+                );
+            }
+            crate::message::set_message_pinned(&db, ids[1], true)
+                .await
+                .unwrap();
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
+
+            let contents: Vec<&str> = result.messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, vec!["Question", "Answer", "Follow-up"]);
+        }
+
+        #[tokio::test]
+        async fn before_leaves_out_the_message_and_later_ones() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let mut ids = Vec::new();
+            for (role, content) in [
+                (Role::User, "First"),
+                (Role::Assistant, "First answer"),
+                (Role::User, "Second"),
+                (Role::Assistant, "Old second answer"),
+            ] {
+                ids.push(
+                    save_message(&db, &session_id, role, content, None)
+                        .await
+                        .unwrap(),
+                );
+            }
+            // Pinning the question must not change what counts as "before" it
+            crate::message::set_message_pinned(&db, ids[2], true)
+                .await
+                .unwrap();
+
+            let result = prepare_context_before(
+                &db,
+                &session_id,
+                ids[2],
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
+
+            let contents: Vec<&str> = result.messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, vec!["First", "First answer"]);
+        }
+
+        #[tokio::test]
+        async fn uses_model_context_window() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            // 1000 chars = 250 tokens: over 75% of the 300 history tokens
+            // left in a 400 token window, far under a 16K window.
+            save_message(&db, &session_id, Role::User, &"a".repeat(1000), None)
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::Assistant, "Ok", None)
+                .await
+                .unwrap();
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
+            assert!(!result.needs_summary);
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+            assert!(result.needs_summary);
+        }
+
+        #[tokio::test]
+        async fn defers_summary_while_history_fits() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, &"a".repeat(1000), None)
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::Assistant, "Ok", None)
+                .await
+                .unwrap();
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!result.was_summarized);
+            assert!(result.needs_summary);
+            assert_eq!(result.messages.len(), 2);
+            let stored = get_messages(&db, &session_id).await.unwrap();
+            assert!(stored.iter().all(|m| !m.is_summary));
+        }
+
+        #[tokio::test]
+        async fn drops_oldest_messages_while_locked() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            for content in ["a".repeat(1000), "b".repeat(1000), "c".repeat(100)] {
+                save_message(&db, &session_id, Role::User, &content, None)
+                    .await
+                    .unwrap();
+            }
+            assert!(try_lock_summarization(&db, &session_id).await.unwrap());
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!result.was_summarized);
+            let contents: Vec<&str> = result.messages.iter().map(|m| m.content.as_str()).collect();
+            // 250 + 25 tokens fit in the 300 token history budget
+            assert_eq!(contents, vec!["b".repeat(1000), "c".repeat(100)]);
+        }
+
+        #[tokio::test]
+        async fn summarization_keeps_original_messages() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            let mut ids = Vec::new();
+            for (role, content) in [
+                (Role::User, "a".repeat(1000)),
+                (Role::Assistant, "b".repeat(1000)),
+                (Role::User, "c".repeat(100)),
+                (Role::Assistant, "d".repeat(100)),
+            ] {
+                ids.push(
+                    save_message(&db, &session_id, role, &content, None)
+                        .await
+                        .unwrap(),
+                );
+            }
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(result.was_summarized);
+            assert_eq!(result.messages.len(), 3);
+            assert_eq!(result.messages[0].role, Role::System);
+            assert_eq!(result.messages[0].content, "Earlier talk");
+            assert_eq!(result.messages[1].content, "c".repeat(100));
+
+            // Every original message is still stored; the old half points at the summary
+            let stored = get_messages(&db, &session_id).await.unwrap();
+            assert_eq!(stored.len(), 5);
+            let summary = stored.iter().find(|m| m.is_summary).unwrap();
+            for message in &stored {
+                let expected = if message.id == ids[0] || message.id == ids[1] {
+                    Some(summary.id)
+                } else {
+                    None
+                };
+                assert_eq!(message.summarized_by, expected, "message {}", message.id);
+            }
+        }
+
+        #[tokio::test]
+        async fn failed_summary_drops_oldest_messages() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            for (role, content) in [
+                (Role::User, "a".repeat(1000)),
+                (Role::Assistant, "b".repeat(1000)),
+                (Role::User, "c".repeat(100)),
+                (Role::Assistant, "d".repeat(100)),
+            ] {
+                save_message(&db, &session_id, role, &content, None)
+                    .await
+                    .unwrap();
+            }
+
+            // DummyProvider fails every request
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!result.was_summarized);
+            let contents: Vec<&str> = result.messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(
+                contents,
+                vec!["b".repeat(1000), "c".repeat(100), "d".repeat(100)]
+            );
+            assert!(try_lock_summarization(&db, &session_id).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn does_not_summarize_a_single_message() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, &"a".repeat(2000), None)
+                .await
+                .unwrap();
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!result.was_summarized);
+            assert_eq!(result.messages.len(), 1);
+            let stored = get_messages(&db, &session_id).await.unwrap();
+            assert!(stored.iter().all(|m| !m.is_summary));
+        }
+
+        #[tokio::test]
+        async fn summarization_skips_pinned_messages() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            let mut ids = Vec::new();
+            for (role, content) in [
+                (Role::User, "a".repeat(400)),
+                (Role::Assistant, "b".repeat(1000)),
+                (Role::User, "c".repeat(100)),
+                (Role::Assistant, "d".repeat(100)),
+            ] {
+                ids.push(
+                    save_message(&db, &session_id, role, &content, None)
+                        .await
+                        .unwrap(),
+                );
+            }
+            crate::message::set_message_pinned(&db, ids[0], true)
+                .await
+                .unwrap();
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(result.was_summarized);
+            let contents: Vec<&str> = result.messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(
+                contents,
+                vec![
+                    "Earlier talk",
+                    &"a".repeat(400),
+                    &"c".repeat(100),
+                    &"d".repeat(100)
+                ]
+            );
+
+            let stored = get_messages(&db, &session_id).await.unwrap();
+            let pinned = stored.iter().find(|m| m.id == ids[0]).unwrap();
+            assert_eq!(pinned.summarized_by, None);
+        }
+
+        #[tokio::test]
+        async fn later_context_starts_with_summary() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            for (role, content) in [
+                (Role::User, "a".repeat(1000)),
+                (Role::Assistant, "b".repeat(1000)),
+                (Role::User, "c".repeat(100)),
+                (Role::Assistant, "d".repeat(100)),
+            ] {
+                save_message(&db, &session_id, role, &content, None)
+                    .await
+                    .unwrap();
+            }
+            let _ = prepare_context(
+                &db,
+                &session_id,
+                &TinyWindowProvider,
+                None,
+                Summarizer::new(&TinyWindowProvider),
+            )
+            .await
+            .unwrap();
+            save_message(&db, &session_id, Role::User, "Next", None)
+                .await
+                .unwrap();
+
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &DummyProvider,
+                None,
+                Summarizer::new(&DummyProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!result.was_summarized);
+            let contents: Vec<&str> = result.messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(
+                contents,
+                vec!["Earlier talk", &"c".repeat(100), &"d".repeat(100), "Next"]
+            );
+        }
+    }
+
+    mod summarize_session {
+        use super::*;
+        use futures::future::BoxFuture;
+
+        /// Provider with a 400 token window that replies with a fixed summary.
+        struct SummaryProvider;
+
+        impl AiProvider for SummaryProvider {
+            fn complete(
+                &self,
+                _request: CompletionRequest,
+            ) -> BoxFuture<
+                '_,
+                Result<cherry2k_core::provider::CompletionStream, cherry2k_core::ProviderError>,
+            > {
+                Box::pin(async {
+                    let stream = futures::stream::iter([Ok("Earlier talk".to_string())]);
+                    Ok(Box::pin(stream) as cherry2k_core::provider::CompletionStream)
+                })
+            }
+
+            fn provider_id(&self) -> &'static str {
+                "summary"
+            }
+
+            fn validate_config(&self) -> Result<(), cherry2k_core::ConfigError> {
+                Ok(())
+            }
+
+            fn health_check(&self) -> BoxFuture<'_, Result<(), cherry2k_core::ProviderError>> {
+                Box::pin(async { Ok(()) })
+            }
+
+            fn context_window(&self, _model: Option<&str>) -> usize {
+                400
+            }
+        }
+
+        async fn setup_long_session() -> (Database, TempDir, String) {
+            let (db, temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, &"a".repeat(1000), None)
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::Assistant, "Ok", None)
+                .await
+                .unwrap();
+            (db, temp, session_id)
+        }
+
+        #[tokio::test]
+        async fn summarizes_over_threshold() {
+            let (db, _temp, session_id) = setup_long_session().await;
+
+            let summarized = summarize_session(
+                &db,
+                &session_id,
+                &SummaryProvider,
+                None,
+                Summarizer::new(&SummaryProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(summarized);
+            let result = prepare_context(
+                &db,
+                &session_id,
+                &SummaryProvider,
+                None,
+                Summarizer::new(&SummaryProvider),
+            )
+            .await
+            .unwrap();
+            assert!(!result.needs_summary);
+            assert_eq!(result.messages[0].content, "Earlier talk");
+        }
+
+        #[tokio::test]
+        async fn skips_session_under_threshold() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, "Hello", None)
+                .await
+                .unwrap();
+
+            let summarized = summarize_session(
+                &db,
+                &session_id,
+                &SummaryProvider,
+                None,
+                Summarizer::new(&SummaryProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!summarized);
+        }
+
+        #[tokio::test]
+        async fn skips_locked_session() {
+            let (db, _temp, session_id) = setup_long_session().await;
+            assert!(try_lock_summarization(&db, &session_id).await.unwrap());
+
+            let summarized = summarize_session(
+                &db,
+                &session_id,
+                &SummaryProvider,
+                None,
+                Summarizer::new(&SummaryProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(!summarized);
+            let stored = get_messages(&db, &session_id).await.unwrap();
+            assert!(stored.iter().all(|m| !m.is_summary));
+        }
+
+        #[tokio::test]
+        async fn releases_lock() {
+            let (db, _temp, session_id) = setup_long_session().await;
+
+            summarize_session(
+                &db,
+                &session_id,
+                &SummaryProvider,
+                None,
+                Summarizer::new(&SummaryProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(try_lock_summarization(&db, &session_id).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn keeps_lock_taken_over_by_another_process() {
+            let (db, _temp, session_id) = setup_long_session().await;
+            let id = session_id.clone();
+            db.call(move |conn| {
+                conn.execute(
+                    "INSERT INTO summarization_locks (session_id, pid) VALUES (?1, ?2)",
+                    rusqlite::params![id, std::process::id() + 1],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+            unlock_summarization(&db, &session_id).await.unwrap();
+
+            assert!(!try_lock_summarization(&db, &session_id).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn takes_over_abandoned_lock() {
+            let (db, _temp, session_id) = setup_long_session().await;
+            let id = session_id.clone();
+            db.call(move |conn| {
+                conn.execute(
+                    "INSERT INTO summarization_locks (session_id, pid, acquired_at)
+                     VALUES (?1, 1, datetime('now', '-1 hour'))",
+                    rusqlite::params![id],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+            let summarized = summarize_session(
+                &db,
+                &session_id,
+                &SummaryProvider,
+                None,
+                Summarizer::new(&SummaryProvider),
+            )
+            .await
+            .unwrap();
+
+            assert!(summarized);
+        }
+    }
+
+    mod fit_to_budget {
+        use super::*;
+
+        fn stored(id: i64, content: &str, is_summary: bool) -> StoredMessage {
+            StoredMessage {
+                id,
+                session_id: "test".to_string(),
+                role: if is_summary { Role::System } else { Role::User },
+                content: content.to_string(),
+                token_count: None,
+                is_summary,
+                provider: None,
+                summarized_by: None,
+                pinned: false,
+                created_at: chrono::Utc::now(),
+            }
+        }
+
+        #[test]
+        fn drops_oldest_first() {
+            let messages = vec![
+                stored(1, &"a".repeat(400), false),
+                stored(2, &"b".repeat(400), false),
+                stored(3, &"c".repeat(400), false),
+            ];
+            let kept = fit_to_budget(&messages, 200, None, Tokenizer::Heuristic);
+            let ids: Vec<i64> = kept.iter().map(|m| m.id).collect();
+            assert_eq!(ids, vec![2, 3]);
+        }
+
+        #[test]
+        fn keeps_summary_when_it_fits() {
+            let messages = vec![
+                stored(9, &"s".repeat(40), true),
+                stored(1, &"a".repeat(400), false),
+                stored(2, &"b".repeat(400), false),
+            ];
+            let kept = fit_to_budget(&messages, 110, None, Tokenizer::Heuristic);
+            let ids: Vec<i64> = kept.iter().map(|m| m.id).collect();
+            assert_eq!(ids, vec![9, 2]);
+        }
+
+        #[test]
+        fn always_keeps_pinned_messages() {
+            let mut pinned = stored(1, &"a".repeat(400), false);
+            pinned.pinned = true;
+            let messages = vec![
+                pinned,
+                stored(2, &"b".repeat(400), false),
+                stored(3, &"c".repeat(400), false),
+            ];
+            let kept = fit_to_budget(&messages, 250, None, Tokenizer::Heuristic);
+            let ids: Vec<i64> = kept.iter().map(|m| m.id).collect();
+            assert_eq!(ids, vec![1, 3]);
+        }
+
+        #[test]
+        fn pinned_messages_keep_their_place() {
+            let mut pinned = stored(3, &"c".repeat(40), false);
+            pinned.pinned = true;
+            let messages = vec![
+                stored(1, &"a".repeat(4000), false),
+                stored(2, &"b".repeat(40), false),
+                pinned,
+                stored(4, &"d".repeat(40), false),
+            ];
+            let kept = fit_to_budget(&messages, 100, None, Tokenizer::Heuristic);
+            let ids: Vec<i64> = kept.iter().map(|m| m.id).collect();
+            assert_eq!(ids, vec![2, 3, 4]);
+        }
+
+        #[test]
+        fn always_keeps_latest_message() {
+            let messages = vec![stored(1, &"a".repeat(4000), false)];
+            assert_eq!(
+                fit_to_budget(&messages, 10, None, Tokenizer::Heuristic).len(),
+                1
+            );
+        }
     }
 
     mod threshold_calculation {
         use super::*;
 
         #[test]
-        fn threshold_is_75_percent_of_budget() {
-            let threshold = ((TOKEN_BUDGET as f32) * SUMMARIZE_THRESHOLD) as usize;
-            assert_eq!(threshold, 12_000); // 16000 * 0.75 = 12000
+        fn threshold_is_75_percent_of_history_budget() {
+            // 16000 window - 4000 reserved = 12000 history tokens
+            let threshold = summarize_threshold(ContextBudget::new(16_000));
+            assert_eq!(threshold, 9_000); // 12000 * 0.75 = 9000
+        }
+
+        #[test]
+        fn large_window_raises_threshold() {
+            // 200000 window - 4096 reserved = 195904 history tokens
+            let threshold = summarize_threshold(ContextBudget::new(200_000));
+            assert_eq!(threshold, 146_928);
         }
 
         #[test]
         fn chars_needed_for_threshold() {
-            // To hit 12K tokens at 4 chars/token, need 48K chars
-            let chars_for_threshold = 12_000 * CHARS_PER_TOKEN;
-            assert_eq!(chars_for_threshold, 48_000);
+            // To hit 9K tokens at 4 chars/token, need 36K chars
+            let tokens = Tokenizer::Heuristic.count(&"a".repeat(36_000));
+            assert_eq!(tokens, 9_000);
         }
     }
 }
@@ -0,0 +1,327 @@
+//! Execution repository for AI-suggested commands that were actually run.
+//!
+//! Every command the user approves (or that auto-runs when confirmation is
+//! disabled) is recorded with the text the AI suggested, the text that was
+//! actually run after any edits, where it ran, and how it ended. This makes
+//! it possible to audit and replay past suggestions.
+
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+
+use crate::StorageError;
+use crate::connection::Database;
+use crate::util::{escape_like, parse_datetime};
+
+/// A command execution to be recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewExecution {
+    /// The session whose response suggested the command (if any)
+    pub session_id: Option<String>,
+    /// The command as suggested by the AI
+    pub original_command: String,
+    /// The command as actually run (differs from original if the user edited it)
+    pub command: String,
+    /// The working directory the command ran in
+    pub working_dir: String,
+    /// Exit code, or `None` if the process was terminated by a signal
+    pub exit_code: Option<i32>,
+    /// Wall-clock run time in milliseconds
+    pub duration_ms: i64,
+    /// Whether the user cancelled the command with Ctrl+C
+    pub was_cancelled: bool,
+}
+
+/// A recorded command execution from the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredExecution {
+    /// Unique execution identifier (auto-incremented)
+    pub id: i64,
+    /// The session whose response suggested the command (if it still exists)
+    pub session_id: Option<String>,
+    /// The command as suggested by the AI
+    pub original_command: String,
+    /// The command as actually run
+    pub command: String,
+    /// The working directory the command ran in
+    pub working_dir: String,
+    /// Exit code, or `None` if the process was terminated by a signal
+    pub exit_code: Option<i32>,
+    /// Wall-clock run time in milliseconds
+    pub duration_ms: i64,
+    /// Whether the user cancelled the command with Ctrl+C
+    pub was_cancelled: bool,
+    /// When the command was run
+    pub executed_at: DateTime<Utc>,
+}
+
+impl StoredExecution {
+    /// Returns true if the user edited the command before running it.
+    #[must_use]
+    pub fn was_edited(&self) -> bool {
+        self.original_command != self.command
+    }
+}
+
+/// Records a command execution.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `execution` - The execution details to record
+///
+/// # Returns
+///
+/// The newly created execution ID.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the insert fails.
+pub async fn record_execution(db: &Database, execution: NewExecution) -> Result<i64, StorageError> {
+    db.call(move |conn| {
+        conn.execute(
+            "INSERT INTO executions (session_id, original_command, command, working_dir,
+                                     exit_code, duration_ms, was_cancelled)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                execution.session_id,
+                execution.original_command,
+                execution.command,
+                execution.working_dir,
+                execution.exit_code,
+                execution.duration_ms,
+                execution.was_cancelled,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Retrieves an execution by ID.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn get_execution(
+    db: &Database,
+    id: i64,
+) -> Result<Option<StoredExecution>, StorageError> {
+    db.call(move |conn| {
+        conn.query_row(
+            "SELECT id, session_id, original_command, command, working_dir,
+                    exit_code, duration_ms, was_cancelled, executed_at
+             FROM executions WHERE id = ?1",
+            params![id],
+            row_to_execution,
+        )
+        .optional()
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Lists executions, most recent first.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `search` - Optional substring to match against the command (original or
+///   edited) or the working directory
+/// * `limit` - Maximum number of executions to return
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn list_executions(
+    db: &Database,
+    search: Option<&str>,
+    limit: usize,
+) -> Result<Vec<StoredExecution>, StorageError> {
+    let pattern = search.map(|s| format!("%{}%", escape_like(s)));
+
+    db.call(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, original_command, command, working_dir,
+                    exit_code, duration_ms, was_cancelled, executed_at
+             FROM executions
+             WHERE ?1 IS NULL
+                OR command LIKE ?1 ESCAPE '\\'
+                OR original_command LIKE ?1 ESCAPE '\\'
+                OR working_dir LIKE ?1 ESCAPE '\\'
+             ORDER BY executed_at DESC, id DESC
+             LIMIT ?2",
+        )?;
+
+        let rows = stmt.query_map(params![pattern, limit as i64], row_to_execution)?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Maps a row from the executions table to a StoredExecution.
+fn row_to_execution(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoredExecution> {
+    let executed_at_str: String = row.get(8)?;
+
+    Ok(StoredExecution {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        original_command: row.get(2)?,
+        command: row.get(3)?,
+        working_dir: row.get(4)?,
+        exit_code: row.get(5)?,
+        duration_ms: row.get(6)?,
+        was_cancelled: row.get(7)?,
+        executed_at: parse_datetime(&executed_at_str),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{create_session, delete_session};
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    async fn setup_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    fn execution(command: &str) -> NewExecution {
+        NewExecution {
+            session_id: None,
+            original_command: command.to_string(),
+            command: command.to_string(),
+            working_dir: "/test/exec".to_string(),
+            exit_code: Some(0),
+            duration_ms: 12,
+            was_cancelled: false,
+        }
+    }
+
+    mod record_execution {
+        use super::*;
+
+        #[tokio::test]
+        async fn records_all_fields() {
+            let (db, _temp) = setup_db().await;
+            let session_id = create_session(&db, Path::new("/test/exec")).await.unwrap();
+
+            let id = record_execution(
+                &db,
+                NewExecution {
+                    session_id: Some(session_id.clone()),
+                    original_command: "ls".to_string(),
+                    command: "ls -la".to_string(),
+                    working_dir: "/test/exec".to_string(),
+                    exit_code: Some(2),
+                    duration_ms: 1500,
+                    was_cancelled: true,
+                },
+            )
+            .await
+            .unwrap();
+
+            let stored = get_execution(&db, id).await.unwrap().unwrap();
+            assert_eq!(stored.session_id, Some(session_id));
+            assert_eq!(stored.original_command, "ls");
+            assert_eq!(stored.command, "ls -la");
+            assert_eq!(stored.working_dir, "/test/exec");
+            assert_eq!(stored.exit_code, Some(2));
+            assert_eq!(stored.duration_ms, 1500);
+            assert!(stored.was_cancelled);
+            assert!(stored.was_edited());
+        }
+
+        #[tokio::test]
+        async fn keeps_execution_when_session_deleted() {
+            let (db, _temp) = setup_db().await;
+            let session_id = create_session(&db, Path::new("/test/exec")).await.unwrap();
+
+            let mut new = execution("echo hi");
+            new.session_id = Some(session_id.clone());
+            let id = record_execution(&db, new).await.unwrap();
+
+            delete_session(&db, &session_id).await.unwrap();
+
+            let stored = get_execution(&db, id).await.unwrap().unwrap();
+            assert_eq!(stored.session_id, None);
+        }
+    }
+
+    mod get_execution {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_none_for_nonexistent() {
+            let (db, _temp) = setup_db().await;
+
+            assert!(get_execution(&db, 42).await.unwrap().is_none());
+        }
+    }
+
+    mod list_executions {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_most_recent_first() {
+            let (db, _temp) = setup_db().await;
+            record_execution(&db, execution("first")).await.unwrap();
+            record_execution(&db, execution("second")).await.unwrap();
+
+            let executions = list_executions(&db, None, 10).await.unwrap();
+
+            assert_eq!(executions.len(), 2);
+            assert_eq!(executions[0].command, "second");
+            assert_eq!(executions[1].command, "first");
+        }
+
+        #[tokio::test]
+        async fn filters_by_search() {
+            let (db, _temp) = setup_db().await;
+            record_execution(&db, execution("cargo build"))
+                .await
+                .unwrap();
+            record_execution(&db, execution("git status"))
+                .await
+                .unwrap();
+
+            let executions = list_executions(&db, Some("cargo"), 10).await.unwrap();
+
+            assert_eq!(executions.len(), 1);
+            assert_eq!(executions[0].command, "cargo build");
+        }
+
+        #[tokio::test]
+        async fn search_treats_wildcards_literally() {
+            let (db, _temp) = setup_db().await;
+            record_execution(&db, execution("echo 100%")).await.unwrap();
+            record_execution(&db, execution("echo 1000")).await.unwrap();
+
+            let executions = list_executions(&db, Some("0%"), 10).await.unwrap();
+
+            assert_eq!(executions.len(), 1);
+            assert_eq!(executions[0].command, "echo 100%");
+        }
+
+        #[tokio::test]
+        async fn respects_limit() {
+            let (db, _temp) = setup_db().await;
+            for i in 0..5 {
+                record_execution(&db, execution(&format!("echo {i}")))
+                    .await
+                    .unwrap();
+            }
+
+            let executions = list_executions(&db, None, 3).await.unwrap();
+
+            assert_eq!(executions.len(), 3);
+        }
+    }
+}
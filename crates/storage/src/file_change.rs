@@ -0,0 +1,477 @@
+//! File change repository for AI-proposed file writes.
+//!
+//! Every set of files written together from an AI response is recorded as a
+//! batch, with each file's content before and after the write and the
+//! session and message that proposed it. This makes it possible to review
+//! past edits and undo them.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+
+use crate::StorageError;
+use crate::connection::Database;
+use crate::util::parse_datetime;
+
+/// A file write to be recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewFileChange {
+    /// Absolute path of the written file
+    pub path: String,
+    /// Content before the write, `None` if the file was created
+    pub previous_content: Option<String>,
+    /// Content after the write
+    pub new_content: String,
+}
+
+/// A set of file writes to be recorded together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewChangeBatch {
+    /// The session whose response proposed the changes (if any)
+    pub session_id: Option<String>,
+    /// The assistant message that proposed the changes (if any)
+    pub message_id: Option<i64>,
+    /// The working directory the changes were made from
+    pub working_dir: String,
+    /// The files written
+    pub changes: Vec<NewFileChange>,
+}
+
+/// A recorded file write from the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFileChange {
+    /// Unique change identifier (auto-incremented)
+    pub id: i64,
+    /// Absolute path of the written file
+    pub path: String,
+    /// Content before the write, `None` if the file was created
+    pub previous_content: Option<String>,
+    /// Content after the write
+    pub new_content: String,
+}
+
+impl StoredFileChange {
+    /// Returns true if the write created the file.
+    #[must_use]
+    pub fn created_file(&self) -> bool {
+        self.previous_content.is_none()
+    }
+}
+
+/// A recorded batch of file writes from the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredChangeBatch {
+    /// Unique batch identifier (auto-incremented)
+    pub id: i64,
+    /// The session whose response proposed the changes (if it still exists)
+    pub session_id: Option<String>,
+    /// The assistant message that proposed the changes (if it still exists)
+    pub message_id: Option<i64>,
+    /// The working directory the changes were made from
+    pub working_dir: String,
+    /// When the files were written
+    pub created_at: DateTime<Utc>,
+    /// When the batch was undone, if it was
+    pub undone_at: Option<DateTime<Utc>>,
+    /// The files written, in the order they were recorded
+    pub changes: Vec<StoredFileChange>,
+}
+
+/// Records a batch of file writes.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `batch` - The batch details and its files
+///
+/// # Returns
+///
+/// The newly created batch ID.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the insert fails. Nothing is recorded
+/// in that case.
+pub async fn record_change_batch(
+    db: &Database,
+    batch: NewChangeBatch,
+) -> Result<i64, StorageError> {
+    db.call(move |conn| {
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO file_change_batches (session_id, message_id, working_dir)
+             VALUES (?1, ?2, ?3)",
+            params![batch.session_id, batch.message_id, batch.working_dir],
+        )?;
+        let batch_id = tx.last_insert_rowid();
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO file_changes (batch_id, path, previous_content, new_content)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for change in &batch.changes {
+                stmt.execute(params![
+                    batch_id,
+                    change.path,
+                    change.previous_content,
+                    change.new_content
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(batch_id)
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Retrieves a batch and its files by ID.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn get_change_batch(
+    db: &Database,
+    id: i64,
+) -> Result<Option<StoredChangeBatch>, StorageError> {
+    db.call(move |conn| {
+        let batch = conn
+            .query_row(
+                "SELECT id, session_id, message_id, working_dir, created_at, undone_at
+                 FROM file_change_batches WHERE id = ?1",
+                params![id],
+                row_to_batch,
+            )
+            .optional()?;
+        match batch {
+            Some(mut batch) => {
+                batch.changes = load_changes(conn, batch.id)?;
+                Ok(Some(batch))
+            }
+            None => Ok(None),
+        }
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Retrieves the most recent batch that hasn't been undone, made from
+/// `working_dir` or one of its parent directories.
+///
+/// Batches made from other projects are never picked, so the files they
+/// wrote aren't restored by accident.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn last_change_batch(
+    db: &Database,
+    working_dir: &Path,
+) -> Result<Option<StoredChangeBatch>, StorageError> {
+    let working_dir = working_dir.to_path_buf();
+
+    let id: Option<i64> = db
+        .call(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, working_dir FROM file_change_batches
+                 WHERE undone_at IS NULL
+                 ORDER BY created_at DESC, id DESC",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let batch_dir: String = row.get(1)?;
+                if working_dir.starts_with(&batch_dir) {
+                    return Ok(Some(row.get(0)?));
+                }
+            }
+            Ok(None)
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    match id {
+        Some(id) => get_change_batch(db, id).await,
+        None => Ok(None),
+    }
+}
+
+/// Lists batches with their files, most recent first.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `limit` - Maximum number of batches to return
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn list_change_batches(
+    db: &Database,
+    limit: usize,
+) -> Result<Vec<StoredChangeBatch>, StorageError> {
+    db.call(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, message_id, working_dir, created_at, undone_at
+             FROM file_change_batches
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?1",
+        )?;
+        let mut batches = stmt
+            .query_map(params![limit as i64], row_to_batch)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for batch in &mut batches {
+            batch.changes = load_changes(conn, batch.id)?;
+        }
+        Ok(batches)
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Marks a batch as undone.
+///
+/// # Returns
+///
+/// `true` if the batch existed and wasn't already undone.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the update fails.
+pub async fn mark_batch_undone(db: &Database, id: i64) -> Result<bool, StorageError> {
+    db.call(move |conn| {
+        let updated = conn.execute(
+            "UPDATE file_change_batches SET undone_at = datetime('now')
+             WHERE id = ?1 AND undone_at IS NULL",
+            params![id],
+        )?;
+        Ok(updated > 0)
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Loads a batch's files in recording order.
+fn load_changes(
+    conn: &rusqlite::Connection,
+    batch_id: i64,
+) -> rusqlite::Result<Vec<StoredFileChange>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, path, previous_content, new_content
+         FROM file_changes WHERE batch_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![batch_id], |row| {
+        Ok(StoredFileChange {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            previous_content: row.get(2)?,
+            new_content: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Maps a row from the file_change_batches table to a StoredChangeBatch
+/// (without its files).
+fn row_to_batch(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoredChangeBatch> {
+    let created_at_str: String = row.get(4)?;
+    let undone_at_str: Option<String> = row.get(5)?;
+
+    Ok(StoredChangeBatch {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        message_id: row.get(2)?,
+        working_dir: row.get(3)?,
+        created_at: parse_datetime(&created_at_str),
+        undone_at: undone_at_str.as_deref().map(parse_datetime),
+        changes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{create_session, delete_session};
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    async fn setup_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    fn batch(paths: &[&str]) -> NewChangeBatch {
+        NewChangeBatch {
+            session_id: None,
+            message_id: None,
+            working_dir: "/test/project".to_string(),
+            changes: paths
+                .iter()
+                .map(|path| NewFileChange {
+                    path: path.to_string(),
+                    previous_content: Some(format!("old {path}")),
+                    new_content: format!("new {path}"),
+                })
+                .collect(),
+        }
+    }
+
+    mod record_change_batch {
+        use super::*;
+
+        #[tokio::test]
+        async fn records_batch_with_files_in_order() {
+            let (db, _temp) = setup_db().await;
+            let session_id = create_session(&db, Path::new("/test/project"))
+                .await
+                .unwrap();
+
+            let mut new = batch(&["/test/project/b.rs", "/test/project/a.rs"]);
+            new.session_id = Some(session_id.clone());
+            new.changes.push(NewFileChange {
+                path: "/test/project/new.rs".to_string(),
+                previous_content: None,
+                new_content: "fn main() {}".to_string(),
+            });
+            let id = record_change_batch(&db, new).await.unwrap();
+
+            let stored = get_change_batch(&db, id).await.unwrap().unwrap();
+            assert_eq!(stored.session_id, Some(session_id));
+            assert_eq!(stored.working_dir, "/test/project");
+            assert_eq!(stored.undone_at, None);
+            let paths: Vec<&str> = stored.changes.iter().map(|c| c.path.as_str()).collect();
+            assert_eq!(
+                paths,
+                [
+                    "/test/project/b.rs",
+                    "/test/project/a.rs",
+                    "/test/project/new.rs"
+                ]
+            );
+            assert_eq!(
+                stored.changes[0].previous_content.as_deref(),
+                Some("old /test/project/b.rs")
+            );
+            assert!(!stored.changes[0].created_file());
+            assert!(stored.changes[2].created_file());
+        }
+
+        #[tokio::test]
+        async fn keeps_batch_when_session_deleted() {
+            let (db, _temp) = setup_db().await;
+            let session_id = create_session(&db, Path::new("/test/project"))
+                .await
+                .unwrap();
+
+            let mut new = batch(&["/test/project/a.rs"]);
+            new.session_id = Some(session_id.clone());
+            let id = record_change_batch(&db, new).await.unwrap();
+
+            delete_session(&db, &session_id).await.unwrap();
+
+            let stored = get_change_batch(&db, id).await.unwrap().unwrap();
+            assert_eq!(stored.session_id, None);
+            assert_eq!(stored.changes.len(), 1);
+        }
+    }
+
+    mod last_change_batch {
+        use super::*;
+
+        #[tokio::test]
+        async fn skips_undone_batches() {
+            let (db, _temp) = setup_db().await;
+            let first = record_change_batch(&db, batch(&["/a"])).await.unwrap();
+            let second = record_change_batch(&db, batch(&["/b"])).await.unwrap();
+            let dir = Path::new("/test/project");
+
+            assert_eq!(
+                last_change_batch(&db, dir).await.unwrap().unwrap().id,
+                second
+            );
+
+            assert!(mark_batch_undone(&db, second).await.unwrap());
+            assert_eq!(
+                last_change_batch(&db, dir).await.unwrap().unwrap().id,
+                first
+            );
+
+            assert!(mark_batch_undone(&db, first).await.unwrap());
+            assert!(last_change_batch(&db, dir).await.unwrap().is_none());
+        }
+
+        #[tokio::test]
+        async fn only_picks_batches_from_this_directory_or_above() {
+            let (db, _temp) = setup_db().await;
+            let here = record_change_batch(&db, batch(&["/test/project/a"]))
+                .await
+                .unwrap();
+            let mut elsewhere = batch(&["/other/b"]);
+            elsewhere.working_dir = "/other".to_string();
+            record_change_batch(&db, elsewhere).await.unwrap();
+
+            let from_subdir = last_change_batch(&db, Path::new("/test/project/src"))
+                .await
+                .unwrap();
+            assert_eq!(from_subdir.unwrap().id, here);
+
+            // A sibling directory sharing a name prefix doesn't count
+            let sibling = last_change_batch(&db, Path::new("/test/project-b"))
+                .await
+                .unwrap();
+            assert!(sibling.is_none());
+            let above = last_change_batch(&db, Path::new("/test")).await.unwrap();
+            assert!(above.is_none());
+        }
+
+        #[tokio::test]
+        async fn undo_is_recorded_once() {
+            let (db, _temp) = setup_db().await;
+            let id = record_change_batch(&db, batch(&["/a"])).await.unwrap();
+
+            assert!(mark_batch_undone(&db, id).await.unwrap());
+            assert!(!mark_batch_undone(&db, id).await.unwrap());
+            assert!(!mark_batch_undone(&db, id + 1).await.unwrap());
+
+            let stored = get_change_batch(&db, id).await.unwrap().unwrap();
+            assert!(stored.undone_at.is_some());
+        }
+    }
+
+    mod list_change_batches {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_most_recent_first_with_files() {
+            let (db, _temp) = setup_db().await;
+            record_change_batch(&db, batch(&["/first"])).await.unwrap();
+            record_change_batch(&db, batch(&["/second/a", "/second/b"]))
+                .await
+                .unwrap();
+
+            let batches = list_change_batches(&db, 10).await.unwrap();
+
+            assert_eq!(batches.len(), 2);
+            assert_eq!(batches[0].changes.len(), 2);
+            assert_eq!(batches[1].changes[0].path, "/first");
+        }
+
+        #[tokio::test]
+        async fn respects_limit() {
+            let (db, _temp) = setup_db().await;
+            for i in 0..5 {
+                record_change_batch(&db, batch(&[&format!("/file{i}")]))
+                    .await
+                    .unwrap();
+            }
+
+            assert_eq!(list_change_batches(&db, 3).await.unwrap().len(), 3);
+        }
+    }
+}
@@ -2,8 +2,13 @@
 //!
 //! This crate provides SQLite-based persistence for Cherry2K, including:
 //! - Conversation history storage
-//! - Session management
+//! - Session management, including titles, tags and pinning
+//! - Configurable session retention
 //! - Context window management with summarization
+//! - Command execution history
+//! - Snapshots of AI file changes for review and undo
+//! - Full-text search across conversations
+//! - Session export and import
 //!
 //! # Usage
 //!
@@ -35,16 +40,35 @@
 
 mod connection;
 pub mod context;
+pub mod execution;
+pub mod file_change;
 pub mod message;
+pub mod retention;
 mod schema;
+pub mod search;
 pub mod session;
+pub mod title;
+pub mod transfer;
 mod util;
 
 // Re-export the main types
-pub use connection::Database;
+pub use connection::{Database, backup_path};
+pub use schema::{MIGRATIONS, Migration, SCHEMA_VERSION};
 
 // Re-export context types
-pub use context::{ContextResult, prepare_context};
+pub use context::{ContextResult, prepare_context, prepare_context_before, summarize_session};
+
+// Re-export execution types
+pub use execution::{NewExecution, StoredExecution};
+
+// Re-export file change types
+pub use file_change::{NewChangeBatch, NewFileChange, StoredChangeBatch, StoredFileChange};
+
+// Re-export retention types
+pub use retention::{PruneCandidate, PruneReason, PruneReport, RetentionPolicy};
+
+// Re-export search types
+pub use search::{SearchFilter, SearchHit};
 
 // Re-export session types
 pub use session::{Session, SessionInfo, is_valid_session_id};
@@ -52,5 +76,8 @@ pub use session::{Session, SessionInfo, is_valid_session_id};
 // Re-export message types
 pub use message::StoredMessage;
 
+// Re-export transfer types
+pub use transfer::{ImportedSession, SessionTranscript, TranscriptMessage};
+
 // Re-export core error types for convenience
 pub use cherry2k_core::StorageError;
@@ -5,7 +5,7 @@
 //! and optional token count for context window management.
 
 use chrono::{DateTime, Utc};
-use rusqlite::params;
+use rusqlite::{OptionalExtension, params};
 
 use cherry2k_core::provider::Role;
 
@@ -28,6 +28,12 @@ pub struct StoredMessage {
     pub token_count: Option<i64>,
     /// Whether this message is a summary of previous messages
     pub is_summary: bool,
+    /// The provider that handled the exchange (if recorded)
+    pub provider: Option<String>,
+    /// The summary that replaced this message in the provider context, if any
+    pub summarized_by: Option<i64>,
+    /// Whether this message is always sent verbatim and never summarized
+    pub pinned: bool,
     /// When the message was created
     pub created_at: DateTime<Utc>,
 }
@@ -58,37 +64,173 @@ pub async fn save_message(
     role: Role,
     content: &str,
     token_count: Option<i64>,
+) -> Result<i64, StorageError> {
+    insert_message(db, session_id, role, content, token_count, None).await
+}
+
+/// Saves a message along with the provider that handled it.
+///
+/// Behaves like [`save_message`], additionally recording the provider so
+/// conversations can later be searched by provider.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to add the message to
+/// * `role` - The message role (user, assistant, system)
+/// * `content` - The message content
+/// * `token_count` - Optional token count for context tracking
+/// * `provider` - The provider the message was sent to or received from
+///
+/// # Returns
+///
+/// The newly created message ID.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the insert fails (e.g., invalid session_id).
+pub async fn save_message_from(
+    db: &Database,
+    session_id: &str,
+    role: Role,
+    content: &str,
+    token_count: Option<i64>,
+    provider: &str,
+) -> Result<i64, StorageError> {
+    insert_message(
+        db,
+        session_id,
+        role,
+        content,
+        token_count,
+        Some(provider.to_string()),
+    )
+    .await
+}
+
+/// Saves a new answer to an earlier question, replacing what followed it.
+///
+/// Used to redo the last turn. Everything after the question is deleted,
+/// and the question's content can be replaced, in the same transaction that
+/// saves the answer, so the previous answer survives until the new one is
+/// stored.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session of the question
+/// * `question_id` - The question being answered
+/// * `question` - New content for the question, or `None` to keep it; its
+///   token count is kept when the content is unchanged
+/// * `content` - The answer
+/// * `token_count` - Optional token count of the answer
+/// * `provider` - The provider that answered
+///
+/// # Returns
+///
+/// The ID of the saved answer.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the question is not part of the
+/// session or the update fails.
+pub async fn replace_answer(
+    db: &Database,
+    session_id: &str,
+    question_id: i64,
+    question: Option<&str>,
+    content: &str,
+    token_count: Option<i64>,
+    provider: &str,
+) -> Result<i64, StorageError> {
+    let id = session_id.to_string();
+    let question = question.map(str::to_string);
+    let content = content.to_string();
+    let provider = provider.to_string();
+
+    let saved = db
+        .call(move |conn| {
+            let tx = conn.transaction()?;
+            if rewind_in(&tx, &id, question_id, question.as_deref())?.is_none() {
+                return Ok(None);
+            }
+            let answer_id = insert_in(
+                &tx,
+                &id,
+                Role::Assistant,
+                &content,
+                token_count,
+                Some(&provider),
+            )?;
+            tx.commit()?;
+            Ok(Some(answer_id))
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    saved.ok_or_else(|| {
+        StorageError::Database(format!(
+            "Message {question_id} is not part of session {session_id}"
+        ))
+    })
+}
+
+/// Inserts a message and bumps the session timestamp in one transaction.
+async fn insert_message(
+    db: &Database,
+    session_id: &str,
+    role: Role,
+    content: &str,
+    token_count: Option<i64>,
+    provider: Option<String>,
 ) -> Result<i64, StorageError> {
     let session_id = session_id.to_string();
-    let role_str = role.to_string();
     let content = content.to_string();
 
     db.call(move |conn| {
         let tx = conn.transaction()?;
-
-        // Insert the message
-        tx.execute(
-            "INSERT INTO messages (session_id, role, content, token_count, is_summary)
-             VALUES (?1, ?2, ?3, ?4, 0)",
-            params![session_id, role_str, content, token_count],
+        let message_id = insert_in(
+            &tx,
+            &session_id,
+            role,
+            &content,
+            token_count,
+            provider.as_deref(),
         )?;
-
-        let message_id = tx.last_insert_rowid();
-
-        // Update the session timestamp
-        tx.execute(
-            "UPDATE sessions SET last_message_at = datetime('now') WHERE id = ?1",
-            params![session_id],
-        )?;
-
         tx.commit()?;
-
         Ok(message_id)
     })
     .await
     .map_err(|e| StorageError::Database(e.to_string()))
 }
 
+/// Inserts a message and bumps the session timestamp within a transaction.
+fn insert_in(
+    tx: &rusqlite::Transaction<'_>,
+    session_id: &str,
+    role: Role,
+    content: &str,
+    token_count: Option<i64>,
+    provider: Option<&str>,
+) -> rusqlite::Result<i64> {
+    // Insert the message
+    tx.execute(
+        "INSERT INTO messages (session_id, role, content, token_count, is_summary, provider)
+         VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+        params![session_id, role.to_string(), content, token_count, provider],
+    )?;
+
+    let message_id = tx.last_insert_rowid();
+
+    // Update the session timestamp
+    tx.execute(
+        "UPDATE sessions SET last_message_at = datetime('now') WHERE id = ?1",
+        params![session_id],
+    )?;
+
+    Ok(message_id)
+}
+
 /// Saves a summary message to the database.
 ///
 /// Summary messages are marked with `is_summary=true` and have the System role.
@@ -152,10 +294,11 @@ pub async fn get_messages(
 
     db.call(move |conn| {
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, token_count, is_summary, created_at
+            "SELECT id, session_id, role, content, token_count, is_summary, created_at, provider,
+                    summarized_by, pinned
              FROM messages
              WHERE session_id = ?1
-             ORDER BY created_at ASC",
+             ORDER BY created_at ASC, id ASC",
         )?;
 
         let rows = stmt.query_map(params![session_id], |row| {
@@ -170,6 +313,9 @@ pub async fn get_messages(
                 content: row.get(3)?,
                 token_count: row.get(4)?,
                 is_summary: is_summary_int != 0,
+                provider: row.get(7)?,
+                summarized_by: row.get(8)?,
+                pinned: row.get(9)?,
                 created_at: parse_datetime(&created_at_str),
             })
         })?;
@@ -207,10 +353,11 @@ pub async fn get_messages_since(
 
     db.call(move |conn| {
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, token_count, is_summary, created_at
+            "SELECT id, session_id, role, content, token_count, is_summary, created_at, provider,
+                    summarized_by, pinned
              FROM messages
              WHERE session_id = ?1 AND created_at > ?2
-             ORDER BY created_at ASC",
+             ORDER BY created_at ASC, id ASC",
         )?;
 
         let rows = stmt.query_map(params![session_id, since_str], |row| {
@@ -225,6 +372,9 @@ pub async fn get_messages_since(
                 content: row.get(3)?,
                 token_count: row.get(4)?,
                 is_summary: is_summary_int != 0,
+                provider: row.get(7)?,
+                summarized_by: row.get(8)?,
+                pinned: row.get(9)?,
                 created_at: parse_datetime(&created_at_str),
             })
         })?;
@@ -298,10 +448,211 @@ pub async fn delete_messages_before(
     .map_err(|e| StorageError::Database(e.to_string()))
 }
 
+/// Retrieves the most recent user message in a session.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to look in
+///
+/// # Returns
+///
+/// The last user message, or `None` if the session has none.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn last_user_message(
+    db: &Database,
+    session_id: &str,
+) -> Result<Option<StoredMessage>, StorageError> {
+    let session_id = session_id.to_string();
+
+    db.call(move |conn| {
+        conn.query_row(
+            "SELECT id, session_id, role, content, token_count, is_summary, created_at, provider,
+                    summarized_by, pinned
+             FROM messages
+             WHERE session_id = ?1 AND role = 'user' AND is_summary = 0
+             ORDER BY id DESC
+             LIMIT 1",
+            params![session_id],
+            |row| {
+                let role_str: String = row.get(2)?;
+                let is_summary_int: i64 = row.get(5)?;
+                let created_at_str: String = row.get(6)?;
+
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    role: parse_role(&role_str),
+                    content: row.get(3)?,
+                    token_count: row.get(4)?,
+                    is_summary: is_summary_int != 0,
+                    provider: row.get(7)?,
+                    summarized_by: row.get(8)?,
+                    pinned: row.get(9)?,
+                    created_at: parse_datetime(&created_at_str),
+                })
+            },
+        )
+        .optional()
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Rewinds a session to a message, deleting every message after it.
+///
+/// Used to replace the last turn rather than append to it. The message's
+/// content can be replaced in the same transaction.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to rewind
+/// * `message_id` - The message to keep as the last one
+/// * `content` - New content for that message, or `None` to keep it
+///
+/// # Returns
+///
+/// The number of messages deleted.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the message is not part of the session
+/// or the update fails.
+pub async fn rewind_session(
+    db: &Database,
+    session_id: &str,
+    message_id: i64,
+    content: Option<&str>,
+) -> Result<usize, StorageError> {
+    let id = session_id.to_string();
+    let content = content.map(str::to_string);
+
+    let deleted = db
+        .call(move |conn| {
+            let tx = conn.transaction()?;
+            let deleted = rewind_in(&tx, &id, message_id, content.as_deref())?;
+            tx.commit()?;
+            Ok(deleted)
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    deleted.ok_or_else(|| {
+        StorageError::Database(format!(
+            "Message {message_id} is not part of session {session_id}"
+        ))
+    })
+}
+
+/// Rewinds a session within a transaction.
+///
+/// Returns the number of messages deleted, or `None` if the message is not
+/// part of the session.
+fn rewind_in(
+    tx: &rusqlite::Transaction<'_>,
+    session_id: &str,
+    message_id: i64,
+    content: Option<&str>,
+) -> rusqlite::Result<Option<usize>> {
+    let exists = tx
+        .query_row(
+            "SELECT 1 FROM messages WHERE id = ?1 AND session_id = ?2",
+            params![message_id, session_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if !exists {
+        return Ok(None);
+    }
+
+    if let Some(content) = content {
+        tx.execute(
+            "UPDATE messages SET content = ?2, token_count = NULL WHERE id = ?1 AND content != ?2",
+            params![message_id, content],
+        )?;
+    }
+
+    let deleted = tx.execute(
+        "DELETE FROM messages WHERE session_id = ?1 AND id > ?2",
+        params![session_id, message_id],
+    )?;
+    Ok(Some(deleted))
+}
+
+/// Pins or unpins a message.
+///
+/// Pinned messages are always sent to the provider verbatim and are never
+/// summarized. Pinning a message that was already summarized brings it back
+/// into the context.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `message_id` - The message to update
+/// * `pinned` - Whether the message should be pinned
+///
+/// # Returns
+///
+/// The updated message, or `None` if no message has that ID.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the update fails.
+pub async fn set_message_pinned(
+    db: &Database,
+    message_id: i64,
+    pinned: bool,
+) -> Result<Option<StoredMessage>, StorageError> {
+    db.call(move |conn| {
+        let sql = if pinned {
+            "UPDATE messages SET pinned = 1, summarized_by = NULL WHERE id = ?1"
+        } else {
+            "UPDATE messages SET pinned = 0 WHERE id = ?1"
+        };
+        if conn.execute(sql, params![message_id])? == 0 {
+            return Ok(None);
+        }
+
+        conn.query_row(
+            "SELECT id, session_id, role, content, token_count, is_summary, created_at, provider,
+                    summarized_by, pinned
+             FROM messages
+             WHERE id = ?1",
+            params![message_id],
+            |row| {
+                let role_str: String = row.get(2)?;
+                let is_summary_int: i64 = row.get(5)?;
+                let created_at_str: String = row.get(6)?;
+
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    role: parse_role(&role_str),
+                    content: row.get(3)?,
+                    token_count: row.get(4)?,
+                    is_summary: is_summary_int != 0,
+                    provider: row.get(7)?,
+                    summarized_by: row.get(8)?,
+                    pinned: row.get(9)?,
+                    created_at: parse_datetime(&created_at_str),
+                })
+            },
+        )
+        .optional()
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
 /// Parses a role string into a Role enum.
 ///
 /// Falls back to `Role::User` for unknown role strings.
-fn parse_role(s: &str) -> Role {
+pub(crate) fn parse_role(s: &str) -> Role {
     match s {
         "user" => Role::User,
         "assistant" => Role::Assistant,
@@ -409,6 +760,26 @@ mod tests {
         }
     }
 
+    mod save_message_from {
+        use super::*;
+
+        #[tokio::test]
+        async fn records_provider() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            save_message_from(&db, &session_id, Role::User, "Hello", None, "ollama")
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::User, "Untracked", None)
+                .await
+                .unwrap();
+
+            let messages = get_messages(&db, &session_id).await.unwrap();
+            assert_eq!(messages[0].provider.as_deref(), Some("ollama"));
+            assert_eq!(messages[1].provider, None);
+        }
+    }
+
     mod save_summary {
         use super::*;
 
@@ -608,6 +979,233 @@ mod tests {
         }
     }
 
+    mod last_user_message {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_latest_user_message() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, "First", None)
+                .await
+                .unwrap();
+            let id = save_message(&db, &session_id, Role::User, "Second", None)
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::Assistant, "Answer", None)
+                .await
+                .unwrap();
+
+            let message = last_user_message(&db, &session_id).await.unwrap().unwrap();
+
+            assert_eq!(message.id, id);
+            assert_eq!(message.content, "Second");
+        }
+
+        #[tokio::test]
+        async fn returns_none_for_empty_session() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            assert!(last_user_message(&db, &session_id).await.unwrap().is_none());
+        }
+    }
+
+    mod rewind_session {
+        use super::*;
+
+        #[tokio::test]
+        async fn deletes_messages_after() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let question = save_message(&db, &session_id, Role::User, "Question", None)
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::Assistant, "Bad answer", None)
+                .await
+                .unwrap();
+
+            let deleted = rewind_session(&db, &session_id, question, None)
+                .await
+                .unwrap();
+
+            assert_eq!(deleted, 1);
+            let messages = get_messages(&db, &session_id).await.unwrap();
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].content, "Question");
+        }
+
+        #[tokio::test]
+        async fn replaces_content() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let question = save_message(&db, &session_id, Role::User, "Typo", Some(3))
+                .await
+                .unwrap();
+
+            rewind_session(&db, &session_id, question, Some("Fixed"))
+                .await
+                .unwrap();
+
+            let messages = get_messages(&db, &session_id).await.unwrap();
+            assert_eq!(messages[0].content, "Fixed");
+            assert!(messages[0].token_count.is_none());
+        }
+
+        #[tokio::test]
+        async fn errors_for_message_in_other_session() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let other = create_session(&db, Path::new("/test/other")).await.unwrap();
+            let foreign = save_message(&db, &other, Role::User, "Elsewhere", None)
+                .await
+                .unwrap();
+
+            let result = rewind_session(&db, &session_id, foreign, None).await;
+
+            assert!(result.is_err());
+            assert_eq!(get_messages(&db, &other).await.unwrap().len(), 1);
+        }
+    }
+
+    mod replace_answer {
+        use super::*;
+
+        #[tokio::test]
+        async fn replaces_previous_answer() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let question = save_message(&db, &session_id, Role::User, "Question", None)
+                .await
+                .unwrap();
+            save_message(&db, &session_id, Role::Assistant, "Bad answer", None)
+                .await
+                .unwrap();
+
+            let answer = replace_answer(
+                &db,
+                &session_id,
+                question,
+                None,
+                "Good answer",
+                Some(2),
+                "ollama",
+            )
+            .await
+            .unwrap();
+
+            let messages = get_messages(&db, &session_id).await.unwrap();
+            let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, vec!["Question", "Good answer"]);
+            assert_eq!(messages[1].id, answer);
+            assert_eq!(messages[1].provider.as_deref(), Some("ollama"));
+        }
+
+        #[tokio::test]
+        async fn replaces_question_content() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let question = save_message(&db, &session_id, Role::User, "Typo", None)
+                .await
+                .unwrap();
+
+            replace_answer(
+                &db,
+                &session_id,
+                question,
+                Some("Fixed"),
+                "Answer",
+                None,
+                "ollama",
+            )
+            .await
+            .unwrap();
+
+            let messages = get_messages(&db, &session_id).await.unwrap();
+            assert_eq!(messages[0].content, "Fixed");
+            assert_eq!(messages[1].content, "Answer");
+        }
+
+        #[tokio::test]
+        async fn keeps_token_count_of_unchanged_question() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let question = save_message(&db, &session_id, Role::User, "Same", Some(7))
+                .await
+                .unwrap();
+
+            replace_answer(
+                &db,
+                &session_id,
+                question,
+                Some("Same"),
+                "Answer",
+                None,
+                "ollama",
+            )
+            .await
+            .unwrap();
+
+            let messages = get_messages(&db, &session_id).await.unwrap();
+            assert_eq!(messages[0].token_count, Some(7));
+        }
+
+        #[tokio::test]
+        async fn saves_nothing_for_message_in_other_session() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            let other = create_session(&db, Path::new("/test/other")).await.unwrap();
+            let foreign = save_message(&db, &other, Role::User, "Elsewhere", None)
+                .await
+                .unwrap();
+
+            let result =
+                replace_answer(&db, &session_id, foreign, None, "Answer", None, "ollama").await;
+
+            assert!(result.is_err());
+            assert!(get_messages(&db, &session_id).await.unwrap().is_empty());
+        }
+    }
+
+    mod set_message_pinned {
+        use super::*;
+
+        #[tokio::test]
+        async fn pins_and_unpins() {
+            let (db, _dir, session_id) = setup_with_session().await;
+            let id = save_message(&db, &session_id, Role::User, "Use nightly Rust", None)
+                .await
+                .unwrap();
+
+            let message = set_message_pinned(&db, id, true).await.unwrap().unwrap();
+            assert!(message.pinned);
+            assert_eq!(message.session_id, session_id);
+
+            let message = set_message_pinned(&db, id, false).await.unwrap().unwrap();
+            assert!(!message.pinned);
+        }
+
+        #[tokio::test]
+        async fn pinning_restores_summarized_message() {
+            let (db, _dir, session_id) = setup_with_session().await;
+            let id = save_message(&db, &session_id, Role::User, "Never suggest sudo", None)
+                .await
+                .unwrap();
+            let summary_id = save_message(&db, &session_id, Role::System, "Summary", None)
+                .await
+                .unwrap();
+            db.call(move |conn| {
+                conn.execute(
+                    "UPDATE messages SET summarized_by = ?1 WHERE id = ?2",
+                    params![summary_id, id],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+            let message = set_message_pinned(&db, id, true).await.unwrap().unwrap();
+            assert_eq!(message.summarized_by, None);
+        }
+
+        #[tokio::test]
+        async fn unknown_message_is_none() {
+            let (db, _dir) = setup_db().await;
+            assert!(set_message_pinned(&db, 999, true).await.unwrap().is_none());
+        }
+    }
+
     mod parse_role {
         use super::*;
 
@@ -0,0 +1,561 @@
+//! Session retention policy.
+//!
+//! Decides which sessions to delete based on age, the number of sessions per
+//! working directory and the overall database size. Planning is deterministic
+//! so a dry run shows exactly what a real prune would remove. Pinned sessions
+//! are never removed.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, params};
+
+use cherry2k_core::config::RetentionConfig;
+
+use crate::StorageError;
+use crate::connection::Database;
+use crate::util::{format_datetime, parse_datetime};
+
+/// Compact the database when at least this fraction of its pages are free
+/// after a prune.
+const VACUUM_FREE_RATIO: f64 = 0.25;
+
+/// Estimated bytes a message occupies per byte of content (the row itself
+/// plus its full-text index entry).
+const BYTES_PER_CONTENT_BYTE: u64 = 2;
+
+/// Limits deciding which sessions are removed. `None` disables a limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Remove sessions inactive for longer than this
+    pub max_age: Option<Duration>,
+    /// Keep at most this many unpinned sessions per working directory
+    pub max_sessions_per_dir: Option<usize>,
+    /// Remove the oldest sessions while the database is larger than this
+    pub max_db_bytes: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self::from(&RetentionConfig::default())
+    }
+}
+
+impl From<&RetentionConfig> for RetentionPolicy {
+    fn from(config: &RetentionConfig) -> Self {
+        Self {
+            max_age: (config.max_age_days > 0)
+                .then(|| Duration::days(i64::from(config.max_age_days))),
+            max_sessions_per_dir: (config.max_sessions_per_dir > 0)
+                .then_some(config.max_sessions_per_dir as usize),
+            max_db_bytes: (config.max_db_size_mb > 0)
+                .then(|| config.max_db_size_mb.saturating_mul(1024 * 1024)),
+        }
+    }
+}
+
+/// Why a session is selected for removal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    /// Inactive for longer than the maximum age
+    Age,
+    /// Beyond the per-directory session limit
+    DirectoryLimit,
+    /// Removed to bring the database under its size limit
+    SizeLimit,
+}
+
+impl fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Age => write!(f, "too old"),
+            Self::DirectoryLimit => write!(f, "directory limit"),
+            Self::SizeLimit => write!(f, "size limit"),
+        }
+    }
+}
+
+/// A session selected for removal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneCandidate {
+    /// The session ID
+    pub session_id: String,
+    /// The session's working directory
+    pub working_dir: String,
+    /// The session's title (if any)
+    pub title: Option<String>,
+    /// When the session was last active
+    pub last_message_at: DateTime<Utc>,
+    /// Why the session is being removed
+    pub reason: PruneReason,
+}
+
+/// The outcome of a prune.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneReport {
+    /// The sessions that were deleted, oldest first
+    pub removed: Vec<PruneCandidate>,
+    /// Whether the database was compacted afterwards
+    pub vacuumed: bool,
+    /// Database size in bytes before pruning
+    pub size_before: u64,
+    /// Database size in bytes after pruning (and compacting)
+    pub size_after: u64,
+}
+
+/// Lists the sessions a prune would remove, without deleting anything.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `policy` - The retention limits to apply
+///
+/// # Returns
+///
+/// The sessions that would be removed, oldest first.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn plan_prune(
+    db: &Database,
+    policy: &RetentionPolicy,
+) -> Result<Vec<PruneCandidate>, StorageError> {
+    let policy = policy.clone();
+
+    db.call(move |conn| plan(conn, &policy, Utc::now()))
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Deletes the sessions selected by the retention policy.
+///
+/// Messages are removed with their sessions. If the deletes leave a large
+/// part of the database unused, it is compacted with `VACUUM`.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `policy` - The retention limits to apply
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the delete or compaction fails.
+pub async fn prune_sessions(
+    db: &Database,
+    policy: &RetentionPolicy,
+) -> Result<PruneReport, StorageError> {
+    let policy = policy.clone();
+
+    let report = db
+        .call(move |conn| {
+            let size_before = used_bytes(conn)?.0;
+            let removed = plan(conn, &policy, Utc::now())?;
+
+            if removed.is_empty() {
+                return Ok(PruneReport {
+                    removed,
+                    vacuumed: false,
+                    size_before,
+                    size_after: size_before,
+                });
+            }
+
+            let tx = conn.transaction()?;
+            for candidate in &removed {
+                tx.execute(
+                    "DELETE FROM sessions WHERE id = ?1",
+                    params![candidate.session_id],
+                )?;
+            }
+            tx.commit()?;
+
+            let (_, page_count, free_pages) = used_bytes(conn)?;
+            let shrink_for_size = removed.iter().any(|c| c.reason == PruneReason::SizeLimit);
+            let vacuumed = page_count > 0
+                && (shrink_for_size || free_pages as f64 / page_count as f64 >= VACUUM_FREE_RATIO);
+            if vacuumed {
+                conn.execute_batch("VACUUM")?;
+            }
+
+            Ok(PruneReport {
+                removed,
+                vacuumed,
+                size_before,
+                size_after: file_bytes(conn)?,
+            })
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    if !report.removed.is_empty() {
+        tracing::debug!(
+            "Pruned {} session(s){}",
+            report.removed.len(),
+            if report.vacuumed {
+                " and compacted the database"
+            } else {
+                ""
+            }
+        );
+    }
+
+    Ok(report)
+}
+
+/// A session considered by the planner.
+struct Row {
+    id: String,
+    working_dir: String,
+    title: Option<String>,
+    last_message_at: String,
+    content_bytes: u64,
+}
+
+/// Selects the sessions to remove, oldest first.
+///
+/// Limits are applied in order: age, then per-directory count, then size.
+/// Ties on activity time are broken by session ID so the result is stable.
+fn plan(
+    conn: &Connection,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> rusqlite::Result<Vec<PruneCandidate>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.working_dir, s.title, s.last_message_at,
+                (SELECT COALESCE(SUM(LENGTH(CAST(m.content AS BLOB))), 0)
+                 FROM messages m WHERE m.session_id = s.id)
+         FROM sessions s
+         WHERE s.pinned = 0
+         ORDER BY s.last_message_at DESC, s.id DESC",
+    )?;
+    // Newest first
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Row {
+                id: row.get(0)?,
+                working_dir: row.get(1)?,
+                title: row.get(2)?,
+                last_message_at: row.get(3)?,
+                content_bytes: row.get::<_, i64>(4)?.max(0) as u64,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut reasons: Vec<Option<PruneReason>> = vec![None; rows.len()];
+
+    if let Some(max_age) = policy.max_age {
+        let threshold = format_datetime(now - max_age);
+        for (row, reason) in rows.iter().zip(reasons.iter_mut()) {
+            if row.last_message_at < threshold {
+                *reason = Some(PruneReason::Age);
+            }
+        }
+    }
+
+    if let Some(max) = policy.max_sessions_per_dir {
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        for (row, reason) in rows.iter().zip(reasons.iter_mut()) {
+            if reason.is_some() {
+                continue;
+            }
+            let count = kept.entry(row.working_dir.as_str()).or_default();
+            if *count >= max {
+                *reason = Some(PruneReason::DirectoryLimit);
+            } else {
+                *count += 1;
+            }
+        }
+    }
+
+    if let Some(max_bytes) = policy.max_db_bytes {
+        let freed: u64 = rows
+            .iter()
+            .zip(&reasons)
+            .filter(|(_, reason)| reason.is_some())
+            .map(|(row, _)| row.content_bytes * BYTES_PER_CONTENT_BYTE)
+            .sum();
+        let mut size = used_bytes(conn)?.0.saturating_sub(freed);
+
+        // Oldest first
+        for (row, reason) in rows.iter().zip(reasons.iter_mut()).rev() {
+            if size <= max_bytes {
+                break;
+            }
+            if reason.is_none() {
+                *reason = Some(PruneReason::SizeLimit);
+                size = size.saturating_sub(row.content_bytes * BYTES_PER_CONTENT_BYTE);
+            }
+        }
+    }
+
+    Ok(rows
+        .into_iter()
+        .zip(reasons)
+        .rev()
+        .filter_map(|(row, reason)| {
+            reason.map(|reason| PruneCandidate {
+                session_id: row.id,
+                working_dir: row.working_dir,
+                title: row.title,
+                last_message_at: parse_datetime(&row.last_message_at),
+                reason,
+            })
+        })
+        .collect())
+}
+
+/// Returns the bytes in use (excluding free pages), the page count and the
+/// free page count.
+fn used_bytes(conn: &Connection) -> rusqlite::Result<(u64, u64, u64)> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let free_pages: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+
+    let used = (page_count - free_pages).max(0) as u64 * page_size.max(0) as u64;
+    Ok((used, page_count.max(0) as u64, free_pages.max(0) as u64))
+}
+
+/// Returns the size of the database in bytes, including free pages.
+fn file_bytes(conn: &Connection) -> rusqlite::Result<u64> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    Ok(page_count.max(0) as u64 * page_size.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::save_message;
+    use crate::session::{create_session, get_session, set_session_pinned};
+    use cherry2k_core::provider::Role;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    async fn setup_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    /// Inserts a session last active `days_ago` days ago.
+    async fn insert_session(db: &Database, id: &str, dir: &str, days_ago: i64) {
+        let id = id.to_string();
+        let dir = dir.to_string();
+        let at = format_datetime(Utc::now() - Duration::days(days_ago));
+        db.call(move |conn| {
+            conn.execute(
+                "INSERT INTO sessions (id, working_dir, created_at, last_message_at)
+                 VALUES (?1, ?2, ?3, ?3)",
+                params![id, dir, at],
+            )
+        })
+        .await
+        .unwrap();
+    }
+
+    /// Moves a session's last activity to `days_ago` days ago.
+    async fn set_last_active(db: &Database, id: &str, days_ago: i64) {
+        let id = id.to_string();
+        let at = format_datetime(Utc::now() - Duration::days(days_ago));
+        db.call(move |conn| {
+            conn.execute(
+                "UPDATE sessions SET last_message_at = ?2 WHERE id = ?1",
+                params![id, at],
+            )
+        })
+        .await
+        .unwrap();
+    }
+
+    fn ids(candidates: &[PruneCandidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.session_id.as_str()).collect()
+    }
+
+    fn policy() -> RetentionPolicy {
+        RetentionPolicy {
+            max_age: None,
+            max_sessions_per_dir: None,
+            max_db_bytes: None,
+        }
+    }
+
+    mod retention_policy {
+        use super::*;
+
+        #[test]
+        fn zero_disables_limits() {
+            let config = RetentionConfig {
+                max_age_days: 0,
+                max_sessions_per_dir: 0,
+                max_db_size_mb: 0,
+            };
+
+            assert_eq!(RetentionPolicy::from(&config), policy());
+        }
+
+        #[test]
+        fn default_keeps_thirty_days() {
+            let policy = RetentionPolicy::default();
+
+            assert_eq!(policy.max_age, Some(Duration::days(30)));
+            assert!(policy.max_sessions_per_dir.is_none());
+            assert!(policy.max_db_bytes.is_none());
+        }
+
+        #[test]
+        fn converts_megabytes() {
+            let config = RetentionConfig {
+                max_db_size_mb: 2,
+                ..Default::default()
+            };
+
+            assert_eq!(
+                RetentionPolicy::from(&config).max_db_bytes,
+                Some(2 * 1024 * 1024)
+            );
+        }
+    }
+
+    mod plan_prune {
+        use super::*;
+
+        #[tokio::test]
+        async fn selects_sessions_past_max_age() {
+            let (db, _temp) = setup_db().await;
+            insert_session(&db, "old", "/a", 40).await;
+            insert_session(&db, "new", "/a", 1).await;
+
+            let policy = RetentionPolicy {
+                max_age: Some(Duration::days(30)),
+                ..policy()
+            };
+            let plan = plan_prune(&db, &policy).await.unwrap();
+
+            assert_eq!(ids(&plan), vec!["old"]);
+            assert_eq!(plan[0].reason, PruneReason::Age);
+        }
+
+        #[tokio::test]
+        async fn keeps_newest_sessions_per_directory() {
+            let (db, _temp) = setup_db().await;
+            insert_session(&db, "a1", "/a", 3).await;
+            insert_session(&db, "a2", "/a", 2).await;
+            insert_session(&db, "a3", "/a", 1).await;
+            insert_session(&db, "b1", "/b", 5).await;
+
+            let policy = RetentionPolicy {
+                max_sessions_per_dir: Some(2),
+                ..policy()
+            };
+            let plan = plan_prune(&db, &policy).await.unwrap();
+
+            assert_eq!(ids(&plan), vec!["a1"]);
+            assert_eq!(plan[0].reason, PruneReason::DirectoryLimit);
+        }
+
+        #[tokio::test]
+        async fn removes_oldest_until_under_size_limit() {
+            let (db, _temp) = setup_db().await;
+            let content = "x".repeat(64 * 1024);
+            for (id, days) in [("s1", 3), ("s2", 2), ("s3", 1)] {
+                insert_session(&db, id, "/a", days).await;
+                save_message(&db, id, Role::User, &content, None)
+                    .await
+                    .unwrap();
+                // Saving a message marks the session active now
+                set_last_active(&db, id, days).await;
+            }
+
+            let used = db.call(|conn| used_bytes(conn)).await.unwrap().0;
+            // Room for roughly one session less than is stored
+            let policy = RetentionPolicy {
+                max_db_bytes: Some(used - 64 * 1024),
+                ..policy()
+            };
+            let plan = plan_prune(&db, &policy).await.unwrap();
+
+            assert_eq!(ids(&plan), vec!["s1"]);
+            assert_eq!(plan[0].reason, PruneReason::SizeLimit);
+        }
+
+        #[tokio::test]
+        async fn never_selects_pinned_sessions() {
+            let (db, _temp) = setup_db().await;
+            insert_session(&db, "old", "/a", 400).await;
+            set_session_pinned(&db, "old", true).await.unwrap();
+
+            let policy = RetentionPolicy {
+                max_age: Some(Duration::days(1)),
+                max_sessions_per_dir: Some(1),
+                max_db_bytes: Some(1),
+            };
+            let plan = plan_prune(&db, &policy).await.unwrap();
+
+            assert!(plan.is_empty());
+        }
+
+        #[tokio::test]
+        async fn does_not_delete() {
+            let (db, _temp) = setup_db().await;
+            insert_session(&db, "old", "/a", 40).await;
+
+            let plan = plan_prune(&db, &RetentionPolicy::default()).await.unwrap();
+
+            assert_eq!(plan.len(), 1);
+            assert!(get_session(&db, "old").await.unwrap().is_some());
+        }
+    }
+
+    mod prune_sessions {
+        use super::*;
+
+        #[tokio::test]
+        async fn deletes_planned_sessions() {
+            let (db, _temp) = setup_db().await;
+            insert_session(&db, "old", "/a", 40).await;
+            let recent = create_session(&db, Path::new("/a")).await.unwrap();
+
+            let report = prune_sessions(&db, &RetentionPolicy::default())
+                .await
+                .unwrap();
+
+            assert_eq!(ids(&report.removed), vec!["old"]);
+            assert!(get_session(&db, "old").await.unwrap().is_none());
+            assert!(get_session(&db, &recent).await.unwrap().is_some());
+        }
+
+        #[tokio::test]
+        async fn vacuums_after_large_delete() {
+            let (db, _temp) = setup_db().await;
+            let content = "x".repeat(256 * 1024);
+            insert_session(&db, "old", "/a", 40).await;
+            save_message(&db, "old", Role::User, &content, None)
+                .await
+                .unwrap();
+            set_last_active(&db, "old", 40).await;
+
+            let report = prune_sessions(&db, &RetentionPolicy::default())
+                .await
+                .unwrap();
+
+            assert!(report.vacuumed);
+            assert!(report.size_after < report.size_before);
+        }
+
+        #[tokio::test]
+        async fn nothing_to_prune() {
+            let (db, _temp) = setup_db().await;
+            create_session(&db, Path::new("/a")).await.unwrap();
+
+            let report = prune_sessions(&db, &RetentionPolicy::default())
+                .await
+                .unwrap();
+
+            assert!(report.removed.is_empty());
+            assert!(!report.vacuumed);
+        }
+    }
+}
@@ -1,14 +1,79 @@
 //! Database schema definitions and migrations
 //!
 //! This module contains the SQL schema for Cherry2K's SQLite database,
-//! including tables for sessions and messages.
+//! including tables for sessions, messages, command executions and file
+//! change snapshots.
 
 use rusqlite::Connection;
 
 use crate::StorageError;
 
-/// Current schema version for migration tracking
-pub const SCHEMA_VERSION: i32 = 1;
+/// Current schema version (the version of the last entry in [`MIGRATIONS`])
+pub const SCHEMA_VERSION: i32 = 9;
+
+/// A single versioned schema migration.
+#[derive(Debug)]
+pub struct Migration {
+    /// Version the database is at after this migration is applied
+    pub version: i32,
+    /// Short human-readable description
+    pub description: &'static str,
+    /// SQL executed to apply the migration
+    sql: &'static str,
+}
+
+/// All migrations, in the order they must be applied.
+///
+/// Never edit or reorder a released migration: existing databases have
+/// already applied it. Add a new entry with the next version instead and
+/// bump [`SCHEMA_VERSION`].
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "sessions and messages",
+        sql: INIT_SCHEMA,
+    },
+    Migration {
+        version: 2,
+        description: "command execution history",
+        sql: EXECUTIONS_SCHEMA,
+    },
+    Migration {
+        version: 3,
+        description: "message providers and full-text search",
+        sql: MESSAGE_SEARCH_SCHEMA,
+    },
+    Migration {
+        version: 4,
+        description: "session titles, tags and pinning",
+        sql: SESSION_LABELS_SCHEMA,
+    },
+    Migration {
+        version: 5,
+        description: "session fork lineage",
+        sql: SESSION_FORKS_SCHEMA,
+    },
+    Migration {
+        version: 6,
+        description: "non-destructive summaries",
+        sql: SUMMARIZED_BY_SCHEMA,
+    },
+    Migration {
+        version: 7,
+        description: "summarization locks",
+        sql: SUMMARIZATION_LOCKS_SCHEMA,
+    },
+    Migration {
+        version: 8,
+        description: "pinned messages",
+        sql: PINNED_MESSAGES_SCHEMA,
+    },
+    Migration {
+        version: 9,
+        description: "file change snapshots",
+        sql: FILE_CHANGES_SCHEMA,
+    },
+];
 
 /// Initial database schema SQL
 ///
@@ -54,23 +119,207 @@ CREATE INDEX IF NOT EXISTS idx_messages_session
 -- Partial index for summary messages (used in context management)
 CREATE INDEX IF NOT EXISTS idx_messages_summary
     ON messages(session_id, id DESC) WHERE is_summary = 1;
+"#;
+
+/// Version 2: execution history
+///
+/// Creates:
+/// - `executions` table recording AI-suggested commands that were actually run
+/// - Index for listing executions newest first
+const EXECUTIONS_SCHEMA: &str = r#"
+-- Executions table: commands run from AI suggestions
+CREATE TABLE IF NOT EXISTS executions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+    original_command TEXT NOT NULL,
+    command TEXT NOT NULL,
+    working_dir TEXT NOT NULL,
+    exit_code INTEGER,
+    duration_ms INTEGER NOT NULL DEFAULT 0,
+    was_cancelled INTEGER NOT NULL DEFAULT 0,
+    executed_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Index for listing executions (most recent first)
+CREATE INDEX IF NOT EXISTS idx_executions_time
+    ON executions(executed_at DESC);
+"#;
+
+/// Version 3: message search
+///
+/// Creates:
+/// - `provider` column on `messages` recording which provider handled the exchange
+/// - `messages_fts` FTS5 index over message content (external content table)
+/// - Triggers keeping the index in sync with inserts, updates and deletes
+/// - Index entries for all existing messages
+const MESSAGE_SEARCH_SCHEMA: &str = r#"
+-- Provider that handled the message (NULL for messages saved before version 3)
+ALTER TABLE messages ADD COLUMN provider TEXT;
+
+-- Full-text index over message content
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content,
+    content='messages',
+    content_rowid='id'
+);
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content)
+        VALUES ('delete', old.id, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content)
+        VALUES ('delete', old.id, old.content);
+    INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+END;
+
+-- Index messages saved before the search index existed
+INSERT INTO messages_fts (messages_fts) VALUES ('rebuild');
+"#;
+
+/// Version 4: session labels
+///
+/// Creates:
+/// - `title` and `pinned` columns on `sessions`
+/// - `session_tags` table for free-form session tags
+/// - Index for finding sessions by tag
+const SESSION_LABELS_SCHEMA: &str = r#"
+-- Human-readable title (auto-generated or user-set)
+ALTER TABLE sessions ADD COLUMN title TEXT;
+
+-- Pinned sessions are never removed by cleanup
+ALTER TABLE sessions ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+
+-- Tags table: any number of tags per session
+CREATE TABLE IF NOT EXISTS session_tags (
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (session_id, tag)
+);
+
+-- Index for finding sessions by tag
+CREATE INDEX IF NOT EXISTS idx_session_tags_tag
+    ON session_tags(tag);
+"#;
+
+/// Session fork lineage schema SQL
+///
+/// Creates:
+/// - `parent_session_id` and `forked_from_message_id` columns on `sessions`
+/// - Index for finding a session's forks
+const SESSION_FORKS_SCHEMA: &str = r#"
+-- Session this one was forked from (kept as a root if the parent is deleted)
+ALTER TABLE sessions ADD COLUMN parent_session_id TEXT
+    REFERENCES sessions(id) ON DELETE SET NULL;
+
+-- Last parent message copied into the fork (the parent's message ID)
+ALTER TABLE sessions ADD COLUMN forked_from_message_id INTEGER;
+
+-- Index for finding a session's forks
+CREATE INDEX IF NOT EXISTS idx_sessions_parent
+    ON sessions(parent_session_id);
+"#;
+
+/// Non-destructive summary schema SQL
+///
+/// Creates:
+/// - `summarized_by` column on `messages` linking a message to the summary
+///   that replaced it in the provider context
+/// - Index for finding the messages a summary covers
+const SUMMARIZED_BY_SCHEMA: &str = r#"
+-- Summary that supersedes this message (NULL while the message is still sent as-is)
+ALTER TABLE messages ADD COLUMN summarized_by INTEGER
+    REFERENCES messages(id) ON DELETE SET NULL;
+
+-- Index for finding the messages a summary covers
+CREATE INDEX IF NOT EXISTS idx_messages_summarized_by
+    ON messages(summarized_by);
+"#;
+
+/// Summarization lock schema SQL
+///
+/// Creates:
+/// - `summarization_locks` table holding one row per session being summarized
+const SUMMARIZATION_LOCKS_SCHEMA: &str = r#"
+-- Sessions currently being summarized (by process ID)
+CREATE TABLE IF NOT EXISTS summarization_locks (
+    session_id TEXT PRIMARY KEY REFERENCES sessions(id) ON DELETE CASCADE,
+    pid INTEGER NOT NULL,
+    acquired_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+"#;
+
+/// Pinned message schema SQL
+///
+/// Creates:
+/// - `pinned` column on `messages` marking messages that are always sent
+///   verbatim and never summarized
+const PINNED_MESSAGES_SCHEMA: &str = r#"
+-- Always sent as-is, never summarized or dropped from the context
+ALTER TABLE messages ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+"#;
+
+/// File change snapshot schema SQL
+///
+/// Creates:
+/// - `file_change_batches` table, one row per set of files written together
+/// - `file_changes` table holding each file's content before and after
+/// - Indexes for listing batches and their files
+const FILE_CHANGES_SCHEMA: &str = r#"
+-- Files written together from one AI response
+CREATE TABLE IF NOT EXISTS file_change_batches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+    message_id INTEGER REFERENCES messages(id) ON DELETE SET NULL,
+    working_dir TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    undone_at TEXT
+);
+
+-- Each file's content before (NULL for a new file) and after the write
+CREATE TABLE IF NOT EXISTS file_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    batch_id INTEGER NOT NULL REFERENCES file_change_batches(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    previous_content TEXT,
+    new_content TEXT NOT NULL
+);
+
+-- Index for listing batches (most recent first)
+CREATE INDEX IF NOT EXISTS idx_file_change_batches_time
+    ON file_change_batches(created_at DESC);
 
--- Record schema version
-INSERT OR IGNORE INTO schema_version (version) VALUES (1);
+-- Index for loading a batch's files
+CREATE INDEX IF NOT EXISTS idx_file_changes_batch
+    ON file_changes(batch_id);
 "#;
 
 /// Ensures the database schema is up to date
 ///
 /// This function:
-/// 1. Checks if the schema_version table exists
-/// 2. If not, runs the initial schema migration
-/// 3. If yes, verifies the version matches expected
+/// 1. Reads the current schema version (0 for a fresh database)
+/// 2. Verifies the version is not newer than supported
+/// 3. Applies each pending migration in order, each in its own transaction
 ///
 /// # Errors
 ///
-/// Returns `StorageError::Migration` if schema creation fails
+/// Returns `StorageError::Migration` if a migration fails
 /// or if the database has an incompatible schema version.
 pub fn ensure_schema(conn: &Connection) -> Result<(), StorageError> {
+    migrate_to(conn, SCHEMA_VERSION)
+}
+
+/// Returns the schema version of the database, or 0 if it has no schema yet.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the version cannot be read.
+pub fn current_version(conn: &Connection) -> Result<i32, StorageError> {
     // Check if schema_version table exists
     let table_exists: bool = conn
         .query_row(
@@ -81,38 +330,79 @@ pub fn ensure_schema(conn: &Connection) -> Result<(), StorageError> {
         .map_err(|e| StorageError::Database(e.to_string()))?;
 
     if !table_exists {
-        // Fresh database - run initial schema
-        tracing::info!("Initializing database schema (version {})", SCHEMA_VERSION);
-        conn.execute_batch(INIT_SCHEMA)
-            .map_err(|e| StorageError::Migration(format!("Failed to create schema: {e}")))?;
-        return Ok(());
+        return Ok(0);
     }
 
-    // Check current version
-    let current_version: i32 = conn
+    let version: Option<i32> = conn
         .query_row("SELECT MAX(version) FROM schema_version", [], |row| {
             row.get(0)
         })
         .map_err(|e| StorageError::Database(e.to_string()))?;
 
-    if current_version > SCHEMA_VERSION {
+    Ok(version.unwrap_or(0))
+}
+
+/// Returns the migrations that have not yet been applied to the database.
+///
+/// # Errors
+///
+/// Returns `StorageError::Migration` if the database has an incompatible
+/// schema version, or `StorageError::Database` if the version cannot be read.
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>, StorageError> {
+    let version = current_version(conn)?;
+    check_supported(version)?;
+
+    Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+}
+
+/// Applies pending migrations up to and including `target`.
+pub(crate) fn migrate_to(conn: &Connection, target: i32) -> Result<(), StorageError> {
+    for migration in pending_migrations(conn)? {
+        if migration.version > target {
+            break;
+        }
+        tracing::info!(
+            "Migrating database schema to version {} ({})",
+            migration.version,
+            migration.description
+        );
+        apply_migration(conn, migration)?;
+    }
+
+    Ok(())
+}
+
+/// Applies a single migration and records its version atomically.
+///
+/// If any statement fails the whole migration is rolled back, leaving the
+/// database at its previous version.
+fn apply_migration(conn: &Connection, migration: &Migration) -> Result<(), StorageError> {
+    let fail = |e: rusqlite::Error| {
+        StorageError::Migration(format!(
+            "Failed to apply migration {} ({}): {e}",
+            migration.version, migration.description
+        ))
+    };
+
+    let tx = conn.unchecked_transaction().map_err(fail)?;
+    tx.execute_batch(migration.sql).map_err(fail)?;
+    tx.execute(
+        "INSERT INTO schema_version (version) VALUES (?1)",
+        [migration.version],
+    )
+    .map_err(fail)?;
+    tx.commit().map_err(fail)
+}
+
+/// Rejects databases created by a newer cherry2k.
+fn check_supported(version: i32) -> Result<(), StorageError> {
+    if version > SCHEMA_VERSION {
         return Err(StorageError::Migration(format!(
             "Database schema version {} is newer than supported version {}. \
              Please upgrade cherry2k.",
-            current_version, SCHEMA_VERSION
+            version, SCHEMA_VERSION
         )));
     }
-
-    if current_version < SCHEMA_VERSION {
-        // Future: run incremental migrations here
-        // For now, we only have version 1
-        tracing::warn!(
-            "Database schema version {} is older than expected {}",
-            current_version,
-            SCHEMA_VERSION
-        );
-    }
-
     Ok(())
 }
 
@@ -197,6 +487,107 @@ mod tests {
         assert!(idx_messages, "idx_messages_session index should exist");
     }
 
+    #[test]
+    fn migrations_are_consecutive() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i32 + 1);
+        }
+        assert_eq!(MIGRATIONS.last().unwrap().version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn migrates_from_every_version() {
+        for start in 0..SCHEMA_VERSION {
+            let conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+            migrate_to(&conn, start).unwrap();
+            assert_eq!(current_version(&conn).unwrap(), start);
+
+            if start >= 1 {
+                conn.execute(
+                    "INSERT INTO sessions (id, working_dir) VALUES ('s1', '/test')",
+                    [],
+                )
+                .unwrap();
+                conn.execute(
+                    "INSERT INTO messages (session_id, role, content) VALUES ('s1', 'user', 'hi')",
+                    [],
+                )
+                .unwrap();
+            }
+
+            ensure_schema(&conn).unwrap();
+
+            assert_eq!(
+                current_version(&conn).unwrap(),
+                SCHEMA_VERSION,
+                "migrating from version {start}"
+            );
+            assert!(pending_migrations(&conn).unwrap().is_empty());
+
+            if start >= 1 {
+                let count: i64 = conn
+                    .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))
+                    .unwrap();
+                assert_eq!(
+                    count, 1,
+                    "data should survive migrating from version {start}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pending_migrations_lists_unapplied() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(pending_migrations(&conn).unwrap().len(), MIGRATIONS.len());
+
+        migrate_to(&conn, 1).unwrap();
+
+        let pending = pending_migrations(&conn).unwrap();
+        assert_eq!(pending.len(), MIGRATIONS.len() - 1);
+        assert_eq!(pending[0].version, 2);
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate_to(&conn, 1).unwrap();
+
+        let broken = Migration {
+            version: 2,
+            description: "broken",
+            sql: "CREATE TABLE half_done (id INTEGER); SELECT * FROM missing_table;",
+        };
+        let result = apply_migration(&conn, &broken);
+
+        assert!(matches!(result, Err(StorageError::Migration(_))));
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        let half_done: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='half_done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!half_done, "partial migration should be rolled back");
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [SCHEMA_VERSION + 1],
+        )
+        .unwrap();
+
+        let result = ensure_schema(&conn);
+
+        assert!(matches!(result, Err(StorageError::Migration(_))));
+    }
+
     #[test]
     fn foreign_key_constraint_works() {
         let conn = Connection::open_in_memory().unwrap();
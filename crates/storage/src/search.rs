@@ -0,0 +1,389 @@
+//! Full-text search across all conversations.
+//!
+//! Message content is indexed by the `messages_fts` FTS5 table, which
+//! triggers keep in sync with the `messages` table. Searches can be narrowed
+//! by working directory, provider, role and date range, and return a short
+//! snippet around each match.
+
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+use cherry2k_core::provider::Role;
+
+use crate::StorageError;
+use crate::connection::Database;
+use crate::message::parse_role;
+use crate::util::{escape_like, format_datetime, parse_datetime};
+
+/// Marks the start of a matched term in [`SearchHit::snippet`].
+pub const MATCH_START: char = '\u{2}';
+
+/// Marks the end of a matched term in [`SearchHit::snippet`].
+pub const MATCH_END: char = '\u{3}';
+
+/// Number of tokens of context included in a snippet.
+const SNIPPET_TOKENS: i32 = 16;
+
+/// Filters narrowing a message search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Only sessions in this directory or below it
+    pub working_dir: Option<String>,
+    /// Only messages handled by this provider
+    pub provider: Option<String>,
+    /// Only messages with this role
+    pub role: Option<Role>,
+    /// Only messages created at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only messages created before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// A message matching a search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// The matching message ID
+    pub message_id: i64,
+    /// The session containing the message
+    pub session_id: String,
+    /// The session's working directory
+    pub working_dir: String,
+    /// The role of the message sender
+    pub role: Role,
+    /// The provider that handled the message (if recorded)
+    pub provider: Option<String>,
+    /// Excerpt around the match, with matched terms wrapped in
+    /// [`MATCH_START`] and [`MATCH_END`]
+    pub snippet: String,
+    /// When the message was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// Searches message content across all sessions.
+///
+/// Each whitespace-separated word in `query` must appear in the message;
+/// words are matched literally, so punctuation and FTS operators in user
+/// input are harmless. Results are ordered by relevance.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `query` - The words to search for
+/// * `filter` - Optional filters narrowing the search
+/// * `limit` - Maximum number of hits to return
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn search_messages(
+    db: &Database,
+    query: &str,
+    filter: &SearchFilter,
+    limit: usize,
+) -> Result<Vec<SearchHit>, StorageError> {
+    let Some(fts_query) = to_fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let dir_pattern = filter
+        .working_dir
+        .as_deref()
+        .map(|dir| format!("{}/%", escape_like(dir.trim_end_matches('/'))));
+    let working_dir = filter
+        .working_dir
+        .as_deref()
+        .map(|dir| dir.trim_end_matches('/').to_string());
+    let provider = filter.provider.clone();
+    let role = filter.role.map(|r| r.to_string());
+    let since = filter.since.map(format_datetime);
+    let until = filter.until.map(format_datetime);
+
+    db.call(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.session_id, s.working_dir, m.role, m.provider,
+                    snippet(messages_fts, 0, ?8, ?9, '…', ?10), m.created_at
+             FROM messages_fts
+             JOIN messages m ON m.id = messages_fts.rowid
+             JOIN sessions s ON s.id = m.session_id
+             WHERE messages_fts MATCH ?1
+               AND (?2 IS NULL OR s.working_dir = ?2 OR s.working_dir LIKE ?3 ESCAPE '\\')
+               AND (?4 IS NULL OR m.provider = ?4)
+               AND (?5 IS NULL OR m.role = ?5)
+               AND (?6 IS NULL OR m.created_at >= ?6)
+               AND (?7 IS NULL OR m.created_at < ?7)
+             ORDER BY rank, m.created_at DESC
+             LIMIT ?11",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                fts_query,
+                working_dir,
+                dir_pattern,
+                provider,
+                role,
+                since,
+                until,
+                MATCH_START.to_string(),
+                MATCH_END.to_string(),
+                SNIPPET_TOKENS,
+                limit as i64,
+            ],
+            |row| {
+                let role_str: String = row.get(3)?;
+                let created_at_str: String = row.get(6)?;
+
+                Ok(SearchHit {
+                    message_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    working_dir: row.get(2)?,
+                    role: parse_role(&role_str),
+                    provider: row.get(4)?,
+                    snippet: row.get(5)?,
+                    created_at: parse_datetime(&created_at_str),
+                })
+            },
+        )?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Converts user input into an FTS5 query matching every word literally.
+///
+/// Returns `None` if the input contains no words.
+fn to_fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{save_message, save_message_from};
+    use crate::session::{create_session, delete_session};
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    async fn setup_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    mod to_fts_query {
+        use super::*;
+
+        #[test]
+        fn quotes_each_term() {
+            assert_eq!(
+                to_fts_query("docker compose").unwrap(),
+                "\"docker\" \"compose\""
+            );
+        }
+
+        #[test]
+        fn escapes_quotes_and_operators() {
+            assert_eq!(
+                to_fts_query("say \"hi\" OR -x").unwrap(),
+                "\"say\" \"\"\"hi\"\"\" \"OR\" \"-x\""
+            );
+        }
+
+        #[test]
+        fn empty_query_is_none() {
+            assert!(to_fts_query("   ").is_none());
+        }
+    }
+
+    mod search_messages {
+        use super::*;
+
+        #[tokio::test]
+        async fn finds_messages_across_sessions() {
+            let (db, _temp) = setup_db().await;
+            let a = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            let b = create_session(&db, Path::new("/projects/b")).await.unwrap();
+            save_message(&db, &a, Role::User, "How do I rebase a branch?", None)
+                .await
+                .unwrap();
+            save_message(&db, &b, Role::Assistant, "Use git rebase -i", None)
+                .await
+                .unwrap();
+            save_message(&db, &b, Role::User, "Unrelated question", None)
+                .await
+                .unwrap();
+
+            let hits = search_messages(&db, "rebase", &SearchFilter::default(), 10)
+                .await
+                .unwrap();
+
+            assert_eq!(hits.len(), 2);
+            let sessions: Vec<_> = hits.iter().map(|h| h.session_id.as_str()).collect();
+            assert!(sessions.contains(&a.as_str()));
+            assert!(sessions.contains(&b.as_str()));
+        }
+
+        #[tokio::test]
+        async fn highlights_matches_in_snippet() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            save_message(&db, &id, Role::User, "restart the nginx service", None)
+                .await
+                .unwrap();
+
+            let hits = search_messages(&db, "nginx", &SearchFilter::default(), 10)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                hits[0].snippet,
+                format!("restart the {MATCH_START}nginx{MATCH_END} service")
+            );
+        }
+
+        #[tokio::test]
+        async fn filters_by_working_dir_including_subdirectories() {
+            let (db, _temp) = setup_db().await;
+            let root = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            let sub = create_session(&db, Path::new("/projects/a/src"))
+                .await
+                .unwrap();
+            let sibling = create_session(&db, Path::new("/projects/ab"))
+                .await
+                .unwrap();
+            for id in [&root, &sub, &sibling] {
+                save_message(&db, id, Role::User, "cargo test", None)
+                    .await
+                    .unwrap();
+            }
+
+            let filter = SearchFilter {
+                working_dir: Some("/projects/a".to_string()),
+                ..Default::default()
+            };
+            let hits = search_messages(&db, "cargo", &filter, 10).await.unwrap();
+
+            assert_eq!(hits.len(), 2);
+            assert!(hits.iter().all(|h| h.session_id != sibling));
+        }
+
+        #[tokio::test]
+        async fn filters_by_provider_and_role() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            save_message_from(&db, &id, Role::User, "kubectl pods", None, "openai")
+                .await
+                .unwrap();
+            save_message_from(
+                &db,
+                &id,
+                Role::Assistant,
+                "kubectl get pods",
+                None,
+                "openai",
+            )
+            .await
+            .unwrap();
+            save_message_from(&db, &id, Role::Assistant, "kubectl logs", None, "ollama")
+                .await
+                .unwrap();
+
+            let filter = SearchFilter {
+                provider: Some("openai".to_string()),
+                role: Some(Role::Assistant),
+                ..Default::default()
+            };
+            let hits = search_messages(&db, "kubectl", &filter, 10).await.unwrap();
+
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].role, Role::Assistant);
+            assert_eq!(hits[0].provider.as_deref(), Some("openai"));
+        }
+
+        #[tokio::test]
+        async fn filters_by_date_range() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            save_message(&db, &id, Role::User, "terraform plan", None)
+                .await
+                .unwrap();
+
+            let future = SearchFilter {
+                since: Some(Utc::now() + chrono::Duration::days(1)),
+                ..Default::default()
+            };
+            let past = SearchFilter {
+                until: Some(Utc::now() - chrono::Duration::days(1)),
+                ..Default::default()
+            };
+            let around = SearchFilter {
+                since: Some(Utc::now() - chrono::Duration::days(1)),
+                until: Some(Utc::now() + chrono::Duration::days(1)),
+                ..Default::default()
+            };
+
+            assert!(
+                search_messages(&db, "terraform", &future, 10)
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+            assert!(
+                search_messages(&db, "terraform", &past, 10)
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+            assert_eq!(
+                search_messages(&db, "terraform", &around, 10)
+                    .await
+                    .unwrap()
+                    .len(),
+                1
+            );
+        }
+
+        #[tokio::test]
+        async fn index_follows_deleted_sessions() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            save_message(&db, &id, Role::User, "ephemeral note", None)
+                .await
+                .unwrap();
+
+            delete_session(&db, &id).await.unwrap();
+
+            let hits = search_messages(&db, "ephemeral", &SearchFilter::default(), 10)
+                .await
+                .unwrap();
+            assert!(hits.is_empty());
+        }
+
+        #[tokio::test]
+        async fn special_characters_do_not_error() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/projects/a")).await.unwrap();
+            save_message(&db, &id, Role::User, "what does rm -rf do", None)
+                .await
+                .unwrap();
+
+            let hits = search_messages(&db, "rm -rf \"", &SearchFilter::default(), 10)
+                .await
+                .unwrap();
+
+            assert_eq!(hits.len(), 1);
+        }
+    }
+}
@@ -3,6 +3,11 @@
 //! This module provides CRUD operations for sessions, which group messages
 //! by working directory and time. Sessions auto-continue if the last message
 //! was within 4 hours.
+//!
+//! Sessions can be labelled with a title and tags so they can be found again
+//! by name, and pinned to exempt them from automatic cleanup. A session can
+//! be forked from any of its messages to explore an alternative without
+//! changing the original; forks record the session and message they came from.
 
 use std::path::Path;
 
@@ -12,6 +17,7 @@ use rusqlite::params;
 
 use crate::StorageError;
 use crate::connection::Database;
+use crate::retention::{RetentionPolicy, prune_sessions};
 use crate::util::parse_datetime;
 
 /// A full session record from the database.
@@ -25,6 +31,16 @@ pub struct Session {
     pub created_at: DateTime<Utc>,
     /// When the last message was added
     pub last_message_at: DateTime<Utc>,
+    /// Human-readable title (auto-generated or user-set)
+    pub title: Option<String>,
+    /// Tags, sorted alphabetically
+    pub tags: Vec<String>,
+    /// Whether the session is exempt from cleanup
+    pub pinned: bool,
+    /// The session this one was forked from (if any)
+    pub parent_session_id: Option<String>,
+    /// The last parent message copied into this fork (if forked)
+    pub forked_from_message_id: Option<i64>,
 }
 
 /// A lightweight session info for list views.
@@ -38,6 +54,14 @@ pub struct SessionInfo {
     pub last_message_at: DateTime<Utc>,
     /// First 100 characters of the first user message (if any)
     pub first_message_preview: Option<String>,
+    /// Human-readable title (auto-generated or user-set)
+    pub title: Option<String>,
+    /// Tags, sorted alphabetically
+    pub tags: Vec<String>,
+    /// Whether the session is exempt from cleanup
+    pub pinned: bool,
+    /// The session this one was forked from (if any)
+    pub parent_session_id: Option<String>,
 }
 
 /// Generates a timestamp-based session ID with random suffix.
@@ -157,7 +181,7 @@ pub async fn get_or_create_session(
                 "SELECT id FROM sessions
                  WHERE working_dir = ?1
                    AND last_message_at >= ?2
-                 ORDER BY last_message_at DESC
+                 ORDER BY last_message_at DESC, id DESC
                  LIMIT 1",
                 params![working_dir_str, threshold_str],
                 |row| row.get(0),
@@ -197,9 +221,78 @@ pub async fn get_or_create_session(
 pub async fn get_session(db: &Database, session_id: &str) -> Result<Option<Session>, StorageError> {
     let id = session_id.to_string();
 
+    db.call(move |conn| read_session(conn, &id))
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Finds a session by title or tag.
+///
+/// An exact (case-insensitive) title match wins over a tag match. If several
+/// sessions match, the most recently active one is returned.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `name` - The title or tag to look for
+///
+/// # Returns
+///
+/// The matching session, or `None` if nothing matches.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the query fails.
+pub async fn find_session_by_name(
+    db: &Database,
+    name: &str,
+) -> Result<Option<Session>, StorageError> {
+    let name = name.trim().to_string();
+    let tag = normalize_tag(&name);
+
     db.call(move |conn| {
-        conn.query_row(
-            "SELECT id, working_dir, created_at, last_message_at
+        let by_title: Option<String> = conn
+            .query_row(
+                "SELECT id FROM sessions
+                 WHERE title = ?1 COLLATE NOCASE
+                 ORDER BY last_message_at DESC
+                 LIMIT 1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let id = match (by_title, tag) {
+            (Some(id), _) => Some(id),
+            (None, Some(tag)) => conn
+                .query_row(
+                    "SELECT s.id FROM sessions s
+                     JOIN session_tags t ON t.session_id = s.id
+                     WHERE t.tag = ?1
+                     ORDER BY s.last_message_at DESC
+                     LIMIT 1",
+                    params![tag],
+                    |row| row.get(0),
+                )
+                .optional()?,
+            (None, None) => None,
+        };
+
+        match id {
+            Some(id) => read_session(conn, &id),
+            None => Ok(None),
+        }
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Reads a full session record, including its tags.
+fn read_session(conn: &rusqlite::Connection, id: &str) -> rusqlite::Result<Option<Session>> {
+    let session = conn
+        .query_row(
+            "SELECT id, working_dir, created_at, last_message_at, title, pinned,
+                    parent_session_id, forked_from_message_id
              FROM sessions WHERE id = ?1",
             params![id],
             |row| {
@@ -211,13 +304,26 @@ pub async fn get_session(db: &Database, session_id: &str) -> Result<Option<Sessi
                     working_dir: row.get(1)?,
                     created_at: parse_datetime(&created_at_str),
                     last_message_at: parse_datetime(&last_message_at_str),
+                    title: row.get(4)?,
+                    tags: Vec::new(),
+                    pinned: row.get(5)?,
+                    parent_session_id: row.get(6)?,
+                    forked_from_message_id: row.get(7)?,
                 })
             },
         )
-        .optional()
-    })
-    .await
-    .map_err(|e| StorageError::Database(e.to_string()))
+        .optional()?;
+
+    let Some(mut session) = session else {
+        return Ok(None);
+    };
+
+    session.tags = conn
+        .prepare("SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY tag")?
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Some(session))
 }
 
 /// Lists sessions for a directory with first message preview.
@@ -251,10 +357,15 @@ pub async fn list_sessions(
                      FROM messages m
                      WHERE m.session_id = s.id AND m.role = 'user'
                      ORDER BY m.created_at ASC
-                     LIMIT 1) as preview
+                     LIMIT 1) as preview,
+                    s.title, s.pinned,
+                    (SELECT group_concat(tag, ',')
+                     FROM (SELECT tag FROM session_tags
+                           WHERE session_id = s.id ORDER BY tag)) as tags,
+                    s.parent_session_id
              FROM sessions s
              WHERE s.working_dir = ?1
-             ORDER BY s.last_message_at DESC
+             ORDER BY s.last_message_at DESC, s.id DESC
              LIMIT ?2",
         )?;
 
@@ -262,11 +373,19 @@ pub async fn list_sessions(
             let created_at_str: String = row.get(1)?;
             let last_message_at_str: String = row.get(2)?;
 
+            let tags: Option<String> = row.get(6)?;
+
             Ok(SessionInfo {
                 id: row.get(0)?,
                 created_at: parse_datetime(&created_at_str),
                 last_message_at: parse_datetime(&last_message_at_str),
                 first_message_preview: row.get(3)?,
+                title: row.get(4)?,
+                tags: tags
+                    .map(|t| t.split(',').map(str::to_string).collect())
+                    .unwrap_or_default(),
+                pinned: row.get(5)?,
+                parent_session_id: row.get(7)?,
             })
         })?;
 
@@ -276,6 +395,318 @@ pub async fn list_sessions(
     .map_err(|e| StorageError::Database(e.to_string()))
 }
 
+/// Result of the fork transaction, mapped to errors outside the closure.
+enum ForkOutcome {
+    Created,
+    SessionMissing,
+    MessageMissing(i64),
+}
+
+/// Forks a session, copying its messages into a new session.
+///
+/// The fork has the same working directory and becomes the most recently
+/// active session there, so the next chat continues it. Copied messages keep
+/// their original timestamps; the original session is left unchanged.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to fork
+/// * `at_message_id` - Last message to copy, or `None` to copy all messages
+///
+/// # Returns
+///
+/// The ID of the new session.
+///
+/// # Errors
+///
+/// Returns `StorageError::SessionNotFound` if the session doesn't exist.
+/// Returns `StorageError::Database` if the message is not part of the session
+/// or the copy fails.
+pub async fn fork_session(
+    db: &Database,
+    session_id: &str,
+    at_message_id: Option<i64>,
+) -> Result<String, StorageError> {
+    let parent_id = session_id.to_string();
+    let fork_id = generate_session_id();
+
+    let id = fork_id.clone();
+    let result = db
+        .call(move |conn| {
+            let tx = conn.transaction()?;
+
+            let parent: Option<(String, Option<String>)> = tx
+                .query_row(
+                    "SELECT working_dir, title FROM sessions WHERE id = ?1",
+                    params![parent_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let Some((working_dir, title)) = parent else {
+                return Ok(ForkOutcome::SessionMissing);
+            };
+
+            // Fork point: the given message, or the parent's latest message
+            let fork_point: Option<i64> = match at_message_id {
+                Some(message_id) => {
+                    let belongs = tx
+                        .query_row(
+                            "SELECT 1 FROM messages WHERE id = ?1 AND session_id = ?2",
+                            params![message_id, parent_id],
+                            |_| Ok(()),
+                        )
+                        .optional()?
+                        .is_some();
+                    if !belongs {
+                        return Ok(ForkOutcome::MessageMissing(message_id));
+                    }
+                    Some(message_id)
+                }
+                None => tx.query_row(
+                    "SELECT MAX(id) FROM messages WHERE session_id = ?1",
+                    params![parent_id],
+                    |row| row.get(0),
+                )?,
+            };
+
+            let title = title.map(|t| format!("{t} (fork)"));
+            tx.execute(
+                "INSERT INTO sessions
+                     (id, working_dir, title, parent_session_id, forked_from_message_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, working_dir, title, parent_id, fork_point],
+            )?;
+
+            tx.execute(
+                "INSERT INTO messages
+                     (session_id, role, content, token_count, is_summary, provider, pinned,
+                      created_at)
+                 SELECT ?1, role, content, token_count, is_summary, provider, pinned, created_at
+                 FROM messages
+                 WHERE session_id = ?2 AND id <= ?3
+                 ORDER BY id",
+                params![id, parent_id, fork_point.unwrap_or(0)],
+            )?;
+
+            // Copies keep their order, so the n-th copy is the n-th original.
+            // Point summarized copies at the copy of their summary; summaries
+            // past the fork point aren't copied and their messages stay active.
+            tx.execute(
+                "WITH originals AS (
+                     SELECT id, summarized_by, ROW_NUMBER() OVER (ORDER BY id) AS n
+                     FROM messages WHERE session_id = ?2 AND id <= ?3
+                 ),
+                 copies AS (
+                     SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS n
+                     FROM messages WHERE session_id = ?1
+                 ),
+                 links AS (
+                     SELECT copy.id AS copy_id, summary_copy.id AS summary_copy_id
+                     FROM originals
+                     JOIN copies AS copy ON copy.n = originals.n
+                     JOIN originals AS summary ON summary.id = originals.summarized_by
+                     JOIN copies AS summary_copy ON summary_copy.n = summary.n
+                 )
+                 UPDATE messages
+                 SET summarized_by = (SELECT summary_copy_id FROM links WHERE copy_id = messages.id)
+                 WHERE session_id = ?1",
+                params![id, parent_id, fork_point.unwrap_or(0)],
+            )?;
+
+            tx.commit()?;
+            Ok(ForkOutcome::Created)
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    match result {
+        ForkOutcome::Created => {
+            tracing::debug!("Forked session {} as {}", session_id, fork_id);
+            Ok(fork_id)
+        }
+        ForkOutcome::SessionMissing => Err(StorageError::SessionNotFound {
+            id: session_id.to_string(),
+        }),
+        ForkOutcome::MessageMissing(message_id) => Err(StorageError::Database(format!(
+            "Message {message_id} is not part of session {session_id}"
+        ))),
+    }
+}
+
+/// Normalizes a tag for storage and lookup.
+///
+/// Tags are trimmed and lowercased. Returns `None` if the tag is empty or
+/// contains whitespace or commas.
+#[must_use]
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+/// Sets or clears a session's title.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session ID to update
+/// * `title` - The new title, or `None` to clear it
+///
+/// # Errors
+///
+/// Returns `StorageError::SessionNotFound` if the session doesn't exist.
+/// Returns `StorageError::Database` if the update fails.
+pub async fn set_session_title(
+    db: &Database,
+    session_id: &str,
+    title: Option<&str>,
+) -> Result<(), StorageError> {
+    let id = session_id.to_string();
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+
+    let rows_affected = db
+        .call(move |conn| {
+            conn.execute(
+                "UPDATE sessions SET title = ?2 WHERE id = ?1",
+                params![id, title],
+            )
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    if rows_affected == 0 {
+        return Err(StorageError::SessionNotFound {
+            id: session_id.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Pins or unpins a session.
+///
+/// Pinned sessions are never removed by [`cleanup_old_sessions`].
+///
+/// # Errors
+///
+/// Returns `StorageError::SessionNotFound` if the session doesn't exist.
+/// Returns `StorageError::Database` if the update fails.
+pub async fn set_session_pinned(
+    db: &Database,
+    session_id: &str,
+    pinned: bool,
+) -> Result<(), StorageError> {
+    let id = session_id.to_string();
+
+    let rows_affected = db
+        .call(move |conn| {
+            conn.execute(
+                "UPDATE sessions SET pinned = ?2 WHERE id = ?1",
+                params![id, pinned],
+            )
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    if rows_affected == 0 {
+        return Err(StorageError::SessionNotFound {
+            id: session_id.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Adds tags to a session.
+///
+/// Tags are normalized with [`normalize_tag`]; invalid tags and tags the
+/// session already has are ignored.
+///
+/// # Errors
+///
+/// Returns `StorageError::SessionNotFound` if the session doesn't exist.
+/// Returns `StorageError::Database` if the insert fails.
+pub async fn add_session_tags(
+    db: &Database,
+    session_id: &str,
+    tags: &[String],
+) -> Result<(), StorageError> {
+    let id = session_id.to_string();
+    let tags: Vec<String> = tags.iter().filter_map(|t| normalize_tag(t)).collect();
+
+    let exists = db
+        .call(move |conn| {
+            let tx = conn.transaction()?;
+            let exists = tx
+                .query_row("SELECT 1 FROM sessions WHERE id = ?1", params![id], |_| {
+                    Ok(())
+                })
+                .optional()?
+                .is_some();
+
+            if exists {
+                for tag in &tags {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)",
+                        params![id, tag],
+                    )?;
+                }
+            }
+
+            tx.commit()?;
+            Ok(exists)
+        })
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+    if !exists {
+        return Err(StorageError::SessionNotFound {
+            id: session_id.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Removes tags from a session.
+///
+/// # Returns
+///
+/// The number of tags removed.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the delete fails.
+pub async fn remove_session_tags(
+    db: &Database,
+    session_id: &str,
+    tags: &[String],
+) -> Result<usize, StorageError> {
+    let id = session_id.to_string();
+    let tags: Vec<String> = tags.iter().filter_map(|t| normalize_tag(t)).collect();
+
+    db.call(move |conn| {
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        for tag in &tags {
+            removed += tx.execute(
+                "DELETE FROM session_tags WHERE session_id = ?1 AND tag = ?2",
+                params![id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
 /// Updates the session's last_message_at timestamp to now.
 ///
 /// # Arguments
@@ -338,9 +769,11 @@ pub async fn delete_session(db: &Database, session_id: &str) -> Result<(), Stora
     Ok(())
 }
 
-/// Deletes sessions older than 30 days.
+/// Deletes unpinned sessions older than 30 days.
 ///
-/// This should be called periodically to clean up old conversation history.
+/// This applies the default [`RetentionPolicy`]; use
+/// [`prune_sessions`](crate::retention::prune_sessions) to apply a configured
+/// one. Pinned sessions are always kept.
 ///
 /// # Arguments
 ///
@@ -354,24 +787,8 @@ pub async fn delete_session(db: &Database, session_id: &str) -> Result<(), Stora
 ///
 /// Returns `StorageError::Database` if the delete fails.
 pub async fn cleanup_old_sessions(db: &Database) -> Result<usize, StorageError> {
-    let threshold = Utc::now() - Duration::days(30);
-    let threshold_str = threshold.format("%Y-%m-%d %H:%M:%S").to_string();
-
-    let rows_deleted = db
-        .call(move |conn| {
-            conn.execute(
-                "DELETE FROM sessions WHERE last_message_at < ?1",
-                params![threshold_str],
-            )
-        })
-        .await
-        .map_err(|e| StorageError::Database(e.to_string()))?;
-
-    if rows_deleted > 0 {
-        tracing::info!("Cleaned up {} old sessions", rows_deleted);
-    }
-
-    Ok(rows_deleted)
+    let report = prune_sessions(db, &RetentionPolicy::default()).await?;
+    Ok(report.removed.len())
 }
 
 #[cfg(test)]
@@ -769,5 +1186,310 @@ mod tests {
             let session = get_session(&db, &id).await.unwrap();
             assert!(session.is_some());
         }
+
+        #[tokio::test]
+        async fn keeps_pinned_sessions() {
+            let (db, _temp) = setup_db().await;
+
+            db.call(|conn| {
+                conn.execute(
+                    "INSERT INTO sessions (id, working_dir, last_message_at, pinned) VALUES ('old', '/test', '2020-01-01 00:00:00', 1)",
+                    [],
+                )
+            })
+            .await
+            .unwrap();
+
+            let count = cleanup_old_sessions(&db).await.unwrap();
+
+            assert_eq!(count, 0);
+            assert!(get_session(&db, "old").await.unwrap().is_some());
+        }
+    }
+
+    mod normalize_tag {
+        use super::*;
+
+        #[test]
+        fn lowercases_and_trims() {
+            assert_eq!(normalize_tag("  Infra ").as_deref(), Some("infra"));
+        }
+
+        #[test]
+        fn rejects_empty_and_separators() {
+            assert!(normalize_tag("  ").is_none());
+            assert!(normalize_tag("two words").is_none());
+            assert!(normalize_tag("a,b").is_none());
+        }
+    }
+
+    mod labels {
+        use super::*;
+
+        #[tokio::test]
+        async fn title_round_trips_and_clears() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test")).await.unwrap();
+
+            set_session_title(&db, &id, Some("  Fix CI  "))
+                .await
+                .unwrap();
+            let session = get_session(&db, &id).await.unwrap().unwrap();
+            assert_eq!(session.title.as_deref(), Some("Fix CI"));
+
+            set_session_title(&db, &id, None).await.unwrap();
+            let session = get_session(&db, &id).await.unwrap().unwrap();
+            assert!(session.title.is_none());
+        }
+
+        #[tokio::test]
+        async fn tags_are_normalized_and_deduplicated() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test")).await.unwrap();
+
+            let tags = vec!["Infra".to_string(), "infra".to_string(), "ci".to_string()];
+            add_session_tags(&db, &id, &tags).await.unwrap();
+
+            let session = get_session(&db, &id).await.unwrap().unwrap();
+            assert_eq!(session.tags, vec!["ci", "infra"]);
+
+            let listed = list_sessions(&db, Path::new("/test"), 10).await.unwrap();
+            assert_eq!(listed[0].tags, vec!["ci", "infra"]);
+
+            let removed = remove_session_tags(&db, &id, &["CI".to_string()])
+                .await
+                .unwrap();
+            assert_eq!(removed, 1);
+            let session = get_session(&db, &id).await.unwrap().unwrap();
+            assert_eq!(session.tags, vec!["infra"]);
+        }
+
+        #[tokio::test]
+        async fn pin_is_reported() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test")).await.unwrap();
+
+            set_session_pinned(&db, &id, true).await.unwrap();
+
+            assert!(get_session(&db, &id).await.unwrap().unwrap().pinned);
+            let listed = list_sessions(&db, Path::new("/test"), 10).await.unwrap();
+            assert!(listed[0].pinned);
+        }
+
+        #[tokio::test]
+        async fn errors_for_nonexistent_session() {
+            let (db, _temp) = setup_db().await;
+
+            assert!(matches!(
+                set_session_title(&db, "missing", Some("x")).await,
+                Err(StorageError::SessionNotFound { .. })
+            ));
+            assert!(matches!(
+                set_session_pinned(&db, "missing", true).await,
+                Err(StorageError::SessionNotFound { .. })
+            ));
+            assert!(matches!(
+                add_session_tags(&db, "missing", &["x".to_string()]).await,
+                Err(StorageError::SessionNotFound { .. })
+            ));
+        }
+    }
+
+    mod find_session_by_name {
+        use super::*;
+
+        #[tokio::test]
+        async fn matches_title_case_insensitively() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test")).await.unwrap();
+            set_session_title(&db, &id, Some("Deploy Pipeline"))
+                .await
+                .unwrap();
+
+            let found = find_session_by_name(&db, "deploy pipeline").await.unwrap();
+
+            assert_eq!(found.unwrap().id, id);
+        }
+
+        #[tokio::test]
+        async fn falls_back_to_tag() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test")).await.unwrap();
+            add_session_tags(&db, &id, &["infra".to_string()])
+                .await
+                .unwrap();
+
+            let found = find_session_by_name(&db, "Infra").await.unwrap();
+
+            assert_eq!(found.unwrap().id, id);
+        }
+
+        #[tokio::test]
+        async fn prefers_title_over_tag() {
+            let (db, _temp) = setup_db().await;
+            let tagged = create_session(&db, Path::new("/test")).await.unwrap();
+            let titled = create_session(&db, Path::new("/other")).await.unwrap();
+            add_session_tags(&db, &tagged, &["release".to_string()])
+                .await
+                .unwrap();
+            set_session_title(&db, &titled, Some("release"))
+                .await
+                .unwrap();
+
+            let found = find_session_by_name(&db, "release").await.unwrap();
+
+            assert_eq!(found.unwrap().id, titled);
+        }
+
+        #[tokio::test]
+        async fn returns_none_when_nothing_matches() {
+            let (db, _temp) = setup_db().await;
+
+            assert!(
+                find_session_by_name(&db, "nothing")
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    mod fork_session {
+        use super::*;
+        use crate::message::{get_messages, save_message};
+        use cherry2k_core::provider::Role;
+
+        async fn setup_conversation() -> (Database, TempDir, String, Vec<i64>) {
+            let (db, temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test/fork")).await.unwrap();
+            let mut message_ids = Vec::new();
+            for (role, content) in [
+                (Role::User, "first question"),
+                (Role::Assistant, "first answer"),
+                (Role::User, "second question"),
+                (Role::Assistant, "second answer"),
+            ] {
+                message_ids.push(save_message(&db, &id, role, content, None).await.unwrap());
+            }
+            (db, temp, id, message_ids)
+        }
+
+        #[tokio::test]
+        async fn copies_messages_up_to_fork_point() {
+            let (db, _temp, parent, message_ids) = setup_conversation().await;
+
+            let fork = fork_session(&db, &parent, Some(message_ids[1]))
+                .await
+                .unwrap();
+
+            let messages = get_messages(&db, &fork).await.unwrap();
+            let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, vec!["first question", "first answer"]);
+            // The original is untouched
+            assert_eq!(get_messages(&db, &parent).await.unwrap().len(), 4);
+        }
+
+        #[tokio::test]
+        async fn links_copies_to_copied_summary() {
+            let (db, _temp, parent, message_ids) = setup_conversation().await;
+            let summary_id = crate::message::save_summary(&db, &parent, "earlier")
+                .await
+                .unwrap();
+            let summarized = message_ids[..2].to_vec();
+            db.call(move |conn| {
+                for id in summarized {
+                    conn.execute(
+                        "UPDATE messages SET summarized_by = ?1 WHERE id = ?2",
+                        params![summary_id, id],
+                    )?;
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+            let fork = fork_session(&db, &parent, None).await.unwrap();
+            let messages = get_messages(&db, &fork).await.unwrap();
+            let summary = messages.iter().find(|m| m.is_summary).unwrap();
+            let links: Vec<_> = messages.iter().map(|m| m.summarized_by).collect();
+            assert_eq!(
+                links,
+                vec![Some(summary.id), Some(summary.id), None, None, None]
+            );
+
+            // Forking before the summary leaves the copies unsummarized
+            let fork = fork_session(&db, &parent, Some(message_ids[3]))
+                .await
+                .unwrap();
+            let messages = get_messages(&db, &fork).await.unwrap();
+            assert_eq!(messages.len(), 4);
+            assert!(messages.iter().all(|m| m.summarized_by.is_none()));
+        }
+
+        #[tokio::test]
+        async fn records_lineage() {
+            let (db, _temp, parent, message_ids) = setup_conversation().await;
+
+            let fork = fork_session(&db, &parent, None).await.unwrap();
+
+            let session = get_session(&db, &fork).await.unwrap().unwrap();
+            assert_eq!(session.parent_session_id.as_deref(), Some(parent.as_str()));
+            assert_eq!(session.forked_from_message_id, Some(message_ids[3]));
+            assert_eq!(session.working_dir, "/test/fork");
+            assert_eq!(get_messages(&db, &fork).await.unwrap().len(), 4);
+
+            let listed = list_sessions(&db, Path::new("/test/fork"), 10)
+                .await
+                .unwrap();
+            let info = listed.iter().find(|s| s.id == fork).unwrap();
+            assert_eq!(info.parent_session_id.as_deref(), Some(parent.as_str()));
+        }
+
+        #[tokio::test]
+        async fn marks_title_as_fork() {
+            let (db, _temp, parent, _) = setup_conversation().await;
+            set_session_title(&db, &parent, Some("Deploy"))
+                .await
+                .unwrap();
+
+            let fork = fork_session(&db, &parent, None).await.unwrap();
+
+            let session = get_session(&db, &fork).await.unwrap().unwrap();
+            assert_eq!(session.title.as_deref(), Some("Deploy (fork)"));
+        }
+
+        #[tokio::test]
+        async fn rejects_message_from_another_session() {
+            let (db, _temp, parent, _) = setup_conversation().await;
+            let other = create_session(&db, Path::new("/test/other")).await.unwrap();
+            let foreign = save_message(&db, &other, Role::User, "elsewhere", None)
+                .await
+                .unwrap();
+
+            let result = fork_session(&db, &parent, Some(foreign)).await;
+
+            assert!(matches!(result, Err(StorageError::Database(_))));
+        }
+
+        #[tokio::test]
+        async fn errors_for_nonexistent_session() {
+            let (db, _temp) = setup_db().await;
+
+            let result = fork_session(&db, "missing", None).await;
+
+            assert!(matches!(result, Err(StorageError::SessionNotFound { .. })));
+        }
+
+        #[tokio::test]
+        async fn fork_survives_parent_deletion() {
+            let (db, _temp, parent, _) = setup_conversation().await;
+            let fork = fork_session(&db, &parent, None).await.unwrap();
+
+            delete_session(&db, &parent).await.unwrap();
+
+            let session = get_session(&db, &fork).await.unwrap().unwrap();
+            assert!(session.parent_session_id.is_none());
+            assert_eq!(get_messages(&db, &fork).await.unwrap().len(), 4);
+        }
     }
 }
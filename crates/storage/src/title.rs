@@ -0,0 +1,327 @@
+//! Automatic session titles.
+//!
+//! After the first exchange in a session, the AI provider is asked for a
+//! short title describing the conversation. Titles set by the user are never
+//! overwritten.
+
+use futures::StreamExt;
+
+use cherry2k_core::provider::{CompletionRequest, Message, Role, Summarizer};
+
+use crate::Database;
+use crate::StorageError;
+use crate::message::get_messages;
+use crate::session::{get_session, set_session_title};
+
+/// Maximum length of a generated title, in characters.
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Maximum characters of each message included in the title prompt.
+const EXCERPT_CHARS: usize = 500;
+
+/// Prompt template for generating a session title.
+const TITLE_PROMPT: &str = r#"Write a short title (at most 6 words) for the conversation below.
+Reply with the title only: no quotes, no punctuation at the end.
+
+User: {user}
+Assistant: {assistant}"#;
+
+/// Generates a title for a session from its first exchange.
+///
+/// Does nothing if the session already has a title or has no user message
+/// yet.
+///
+/// # Arguments
+///
+/// * `db` - The database connection
+/// * `session_id` - The session to title
+/// * `summarizer` - Provider and model that write the title
+///
+/// # Returns
+///
+/// The new title, or `None` if no title was generated.
+///
+/// # Errors
+///
+/// Returns `StorageError::SessionNotFound` if the session doesn't exist.
+/// Returns `StorageError::Database` if the provider call or update fails.
+pub async fn generate_session_title(
+    db: &Database,
+    session_id: &str,
+    summarizer: Summarizer<'_>,
+) -> Result<Option<String>, StorageError> {
+    let session =
+        get_session(db, session_id)
+            .await?
+            .ok_or_else(|| StorageError::SessionNotFound {
+                id: session_id.to_string(),
+            })?;
+
+    if session.title.is_some() {
+        return Ok(None);
+    }
+
+    let messages = get_messages(db, session_id).await?;
+    let first = |role: Role| {
+        messages
+            .iter()
+            .find(|m| m.role == role && !m.is_summary)
+            .map(|m| excerpt(&m.content))
+    };
+    let Some(user) = first(Role::User) else {
+        return Ok(None);
+    };
+    let assistant = first(Role::Assistant).unwrap_or_default();
+
+    let prompt = TITLE_PROMPT
+        .replace("{user}", &user)
+        .replace("{assistant}", &assistant);
+    let request = CompletionRequest::new()
+        .with_message(Message::user(&prompt))
+        .with_max_tokens(30);
+
+    let stream = summarizer
+        .complete(request)
+        .await
+        .map_err(|e| StorageError::Database(format!("Title generation failed: {e}")))?;
+
+    tokio::pin!(stream);
+    let mut response = String::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(text) => response.push_str(&text),
+            Err(e) => {
+                return Err(StorageError::Database(format!(
+                    "Title generation stream error: {e}"
+                )));
+            }
+        }
+    }
+
+    let Some(title) = clean_title(&response) else {
+        return Ok(None);
+    };
+
+    set_session_title(db, session_id, Some(&title)).await?;
+    Ok(Some(title))
+}
+
+/// Truncates message content for the title prompt.
+fn excerpt(content: &str) -> String {
+    content.chars().take(EXCERPT_CHARS).collect()
+}
+
+/// Turns a provider response into a single-line title.
+///
+/// Takes the first non-empty line, strips a leading "Title:", surrounding
+/// quotes or markdown and a trailing period, and caps the length.
+fn clean_title(response: &str) -> Option<String> {
+    let line = response.lines().map(str::trim).find(|l| !l.is_empty())?;
+
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line);
+    let line = line
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '*' | '#'))
+        .trim_end_matches('.');
+
+    let title: String = line.chars().take(MAX_TITLE_CHARS).collect();
+    let title = title.trim_end();
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::save_message;
+    use crate::session::create_session;
+    use cherry2k_core::provider::AiProvider;
+    use futures::future::BoxFuture;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Provider that always replies with a fixed response.
+    struct FixedProvider(&'static str);
+
+    impl AiProvider for FixedProvider {
+        fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> BoxFuture<
+            '_,
+            Result<cherry2k_core::provider::CompletionStream, cherry2k_core::ProviderError>,
+        > {
+            let text = self.0.to_string();
+            Box::pin(async move {
+                let stream = futures::stream::iter([Ok(text)]);
+                Ok(Box::pin(stream) as cherry2k_core::provider::CompletionStream)
+            })
+        }
+
+        fn provider_id(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn validate_config(&self) -> Result<(), cherry2k_core::ConfigError> {
+            Ok(())
+        }
+
+        fn health_check(&self) -> BoxFuture<'_, Result<(), cherry2k_core::ProviderError>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    /// Provider that replies with the model it was asked for.
+    struct ModelEchoProvider;
+
+    impl AiProvider for ModelEchoProvider {
+        fn complete(
+            &self,
+            request: CompletionRequest,
+        ) -> BoxFuture<
+            '_,
+            Result<cherry2k_core::provider::CompletionStream, cherry2k_core::ProviderError>,
+        > {
+            let text = request.model.unwrap_or_else(|| "default".to_string());
+            Box::pin(async move {
+                let stream = futures::stream::iter([Ok(text)]);
+                Ok(Box::pin(stream) as cherry2k_core::provider::CompletionStream)
+            })
+        }
+
+        fn provider_id(&self) -> &'static str {
+            "echo"
+        }
+
+        fn validate_config(&self) -> Result<(), cherry2k_core::ConfigError> {
+            Ok(())
+        }
+
+        fn health_check(&self) -> BoxFuture<'_, Result<(), cherry2k_core::ProviderError>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    async fn setup_with_session() -> (Database, TempDir, String) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let session_id = create_session(&db, Path::new("/test/title")).await.unwrap();
+        (db, temp_dir, session_id)
+    }
+
+    mod clean_title {
+        use super::*;
+
+        #[test]
+        fn strips_quotes_prefix_and_period() {
+            assert_eq!(
+                clean_title("Title: \"Fixing the CI build.\"\n").as_deref(),
+                Some("Fixing the CI build")
+            );
+        }
+
+        #[test]
+        fn uses_first_non_empty_line() {
+            assert_eq!(
+                clean_title("\n  Docker networking\nExtra").as_deref(),
+                Some("Docker networking")
+            );
+        }
+
+        #[test]
+        fn caps_length() {
+            let title = clean_title(&"a".repeat(200)).unwrap();
+            assert_eq!(title.chars().count(), MAX_TITLE_CHARS);
+        }
+
+        #[test]
+        fn empty_response_is_none() {
+            assert!(clean_title("  \n\"\"").is_none());
+        }
+    }
+
+    mod generate_session_title {
+        use super::*;
+
+        #[tokio::test]
+        async fn saves_generated_title() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, "How do I list pods?", None)
+                .await
+                .unwrap();
+
+            let title = generate_session_title(
+                &db,
+                &session_id,
+                Summarizer::new(&FixedProvider("Listing pods")),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(title.as_deref(), Some("Listing pods"));
+            let session = get_session(&db, &session_id).await.unwrap().unwrap();
+            assert_eq!(session.title.as_deref(), Some("Listing pods"));
+        }
+
+        #[tokio::test]
+        async fn keeps_existing_title() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, "Hello", None)
+                .await
+                .unwrap();
+            set_session_title(&db, &session_id, Some("Mine"))
+                .await
+                .unwrap();
+
+            let title =
+                generate_session_title(&db, &session_id, Summarizer::new(&FixedProvider("Other")))
+                    .await
+                    .unwrap();
+
+            assert!(title.is_none());
+            let session = get_session(&db, &session_id).await.unwrap().unwrap();
+            assert_eq!(session.title.as_deref(), Some("Mine"));
+        }
+
+        #[tokio::test]
+        async fn asks_summarizer_model() {
+            let (db, _temp, session_id) = setup_with_session().await;
+            save_message(&db, &session_id, Role::User, "Hello", None)
+                .await
+                .unwrap();
+            let summarizer = Summarizer {
+                provider: &ModelEchoProvider,
+                model: Some("tiny-model"),
+            };
+
+            let title = generate_session_title(&db, &session_id, summarizer)
+                .await
+                .unwrap();
+
+            assert_eq!(title.as_deref(), Some("tiny-model"));
+        }
+
+        #[tokio::test]
+        async fn skips_session_without_user_message() {
+            let (db, _temp, session_id) = setup_with_session().await;
+
+            let title = generate_session_title(
+                &db,
+                &session_id,
+                Summarizer::new(&FixedProvider("Nothing")),
+            )
+            .await
+            .unwrap();
+
+            assert!(title.is_none());
+        }
+    }
+}
@@ -0,0 +1,645 @@
+//! Session export and import.
+//!
+//! Sessions are exported as [`SessionTranscript`]s: a faithful copy of the
+//! session and every message in it, including summaries, providers and
+//! timestamps, along with the session's title, tags, pin and fork lineage.
+//! Transcripts serialize with serde so callers can write them as JSON, and
+//! importing one recreates the session exactly, moving it to a new ID if the
+//! original is already taken. A fork keeps its parent only if the parent is
+//! already in the database, so import parents first.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use cherry2k_core::provider::Role;
+
+use crate::StorageError;
+use crate::connection::Database;
+use crate::message::parse_role;
+use crate::session::{generate_session_id, is_valid_session_id};
+use crate::util::{format_datetime, parse_datetime};
+
+/// A complete, portable copy of a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionTranscript {
+    /// Session identifier at export time
+    pub id: String,
+    /// The working directory where the session was created
+    pub working_dir: String,
+    /// When the session was created
+    pub created_at: DateTime<Utc>,
+    /// When the last message was added
+    pub last_message_at: DateTime<Utc>,
+    /// Human-readable title (if set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Tags, sorted alphabetically
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Whether the session is exempt from cleanup
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// The session this one was forked from (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<String>,
+    /// Position in the parent's transcript of the last message copied into
+    /// this fork
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from_message: Option<usize>,
+    /// All messages, oldest first
+    pub messages: Vec<TranscriptMessage>,
+}
+
+/// A single message within a [`SessionTranscript`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    /// The role of the message sender
+    pub role: Role,
+    /// The message content
+    pub content: String,
+    /// Whether this message is a summary of earlier messages
+    #[serde(default)]
+    pub is_summary: bool,
+    /// The provider that handled the exchange (if recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Token count (if recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_count: Option<i64>,
+    /// Position in the transcript of the summary that replaced this message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summarized_by: Option<usize>,
+    /// Whether this message is always sent verbatim and never summarized
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// When the message was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// The result of importing a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedSession {
+    /// The ID the session was stored under
+    pub id: String,
+    /// The ID recorded in the transcript
+    pub original_id: String,
+    /// Number of messages imported
+    pub message_count: usize,
+}
+
+impl ImportedSession {
+    /// Returns true if the session had to be given a new ID.
+    #[must_use]
+    pub fn was_renamed(&self) -> bool {
+        self.id != self.original_id
+    }
+}
+
+/// Exports a session and all of its messages.
+///
+/// # Errors
+///
+/// Returns `StorageError::SessionNotFound` if the session doesn't exist,
+/// or `StorageError::Database` if a query fails.
+pub async fn export_session(
+    db: &Database,
+    session_id: &str,
+) -> Result<SessionTranscript, StorageError> {
+    let id = session_id.to_string();
+
+    db.call(move |conn| read_transcript(conn, &id))
+        .await
+        .map_err(|e| StorageError::Database(e.to_string()))?
+        .ok_or_else(|| StorageError::SessionNotFound {
+            id: session_id.to_string(),
+        })
+}
+
+/// Exports every session, oldest first.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if a query fails.
+pub async fn export_all_sessions(db: &Database) -> Result<Vec<SessionTranscript>, StorageError> {
+    db.call(|conn| {
+        let ids = conn
+            .prepare("SELECT id FROM sessions ORDER BY created_at ASC, id ASC")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut transcripts = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(transcript) = read_transcript(conn, &id)? {
+                transcripts.push(transcript);
+            }
+        }
+        Ok(transcripts)
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Recreates a session from a transcript.
+///
+/// The session and its messages are inserted in a single transaction with
+/// their original timestamps. If the transcript's ID is already in use (or
+/// isn't a valid session ID), a fresh ID is generated instead. The fork
+/// lineage is dropped if the parent session isn't in the database.
+///
+/// # Errors
+///
+/// Returns `StorageError::Database` if the insert fails.
+pub async fn import_session(
+    db: &Database,
+    transcript: SessionTranscript,
+) -> Result<ImportedSession, StorageError> {
+    db.call(move |conn| {
+        let tx = conn.transaction()?;
+
+        let taken = |id: &str| -> rusqlite::Result<bool> {
+            tx.query_row("SELECT 1 FROM sessions WHERE id = ?1", params![id], |_| {
+                Ok(())
+            })
+            .optional()
+            .map(|row| row.is_some())
+        };
+
+        let mut id = transcript.id.clone();
+        while !is_valid_session_id(&id) || taken(&id)? {
+            id = generate_session_id();
+        }
+
+        let mut parent_id = transcript.parent_session_id.as_deref();
+        if let Some(parent) = parent_id
+            && !taken(parent)?
+        {
+            parent_id = None;
+        }
+        // Fork points refer to positions in the parent's transcript
+        let fork_point = match (parent_id, transcript.forked_from_message) {
+            (Some(parent), Some(i)) => message_ids(&tx, parent)?.get(i).copied(),
+            _ => None,
+        };
+
+        tx.execute(
+            "INSERT INTO sessions (id, working_dir, created_at, last_message_at, title, pinned,
+                                   parent_session_id, forked_from_message_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                id,
+                transcript.working_dir,
+                format_datetime(transcript.created_at),
+                format_datetime(transcript.last_message_at),
+                transcript.title,
+                transcript.pinned,
+                parent_id,
+                fork_point,
+            ],
+        )?;
+
+        for tag in &transcript.tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+
+        let mut message_ids = Vec::with_capacity(transcript.messages.len());
+        for message in &transcript.messages {
+            tx.execute(
+                "INSERT INTO messages (session_id, role, content, token_count, is_summary,
+                                       provider, pinned, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    id,
+                    message.role.to_string(),
+                    message.content,
+                    message.token_count,
+                    message.is_summary,
+                    message.provider,
+                    message.pinned,
+                    format_datetime(message.created_at),
+                ],
+            )?;
+            message_ids.push(tx.last_insert_rowid());
+        }
+
+        // Summary links refer to positions in the transcript
+        for (message, id) in transcript.messages.iter().zip(&message_ids) {
+            if let Some(summary_id) = message.summarized_by.and_then(|i| message_ids.get(i)) {
+                tx.execute(
+                    "UPDATE messages SET summarized_by = ?1 WHERE id = ?2",
+                    params![summary_id, id],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(ImportedSession {
+            id,
+            original_id: transcript.id,
+            message_count: transcript.messages.len(),
+        })
+    })
+    .await
+    .map_err(|e| StorageError::Database(e.to_string()))
+}
+
+/// Reads a session and its messages, or `None` if the session doesn't exist.
+fn read_transcript(
+    conn: &rusqlite::Connection,
+    session_id: &str,
+) -> rusqlite::Result<Option<SessionTranscript>> {
+    let session = conn
+        .query_row(
+            "SELECT id, working_dir, created_at, last_message_at, title, pinned,
+                    parent_session_id, forked_from_message_id
+             FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| {
+                let created_at_str: String = row.get(2)?;
+                let last_message_at_str: String = row.get(3)?;
+                let session = SessionTranscript {
+                    id: row.get(0)?,
+                    working_dir: row.get(1)?,
+                    created_at: parse_datetime(&created_at_str),
+                    last_message_at: parse_datetime(&last_message_at_str),
+                    title: row.get(4)?,
+                    tags: Vec::new(),
+                    pinned: row.get(5)?,
+                    parent_session_id: row.get(6)?,
+                    forked_from_message: None,
+                    messages: Vec::new(),
+                };
+                Ok((session, row.get::<_, Option<i64>>(7)?))
+            },
+        )
+        .optional()?;
+
+    let Some((mut session, fork_point)) = session else {
+        return Ok(None);
+    };
+
+    session.tags = conn
+        .prepare("SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY tag")?
+        .query_map(params![session_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Replace the fork point with its position in the parent's transcript
+    if let (Some(parent), Some(fork_point)) = (&session.parent_session_id, fork_point) {
+        session.forked_from_message = message_ids(conn, parent)?
+            .iter()
+            .position(|&id| id == fork_point);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT role, content, is_summary, provider, token_count, created_at, id, summarized_by,
+                pinned
+         FROM messages
+         WHERE session_id = ?1
+         ORDER BY created_at ASC, id ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            let role_str: String = row.get(0)?;
+            let created_at_str: String = row.get(5)?;
+            let message = TranscriptMessage {
+                role: parse_role(&role_str),
+                content: row.get(1)?,
+                is_summary: row.get(2)?,
+                provider: row.get(3)?,
+                token_count: row.get(4)?,
+                summarized_by: None,
+                pinned: row.get(8)?,
+                created_at: parse_datetime(&created_at_str),
+            };
+            Ok((
+                message,
+                row.get::<_, i64>(6)?,
+                row.get::<_, Option<i64>>(7)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Replace message IDs with positions in the transcript
+    let positions: HashMap<i64, usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, (_, id, _))| (*id, i))
+        .collect();
+    session.messages = rows
+        .into_iter()
+        .map(|(mut message, _, summary_id)| {
+            message.summarized_by = summary_id.and_then(|id| positions.get(&id).copied());
+            message
+        })
+        .collect();
+
+    Ok(Some(session))
+}
+
+/// Message IDs of a session in transcript order.
+fn message_ids(conn: &rusqlite::Connection, session_id: &str) -> rusqlite::Result<Vec<i64>> {
+    conn.prepare("SELECT id FROM messages WHERE session_id = ?1 ORDER BY created_at ASC, id ASC")?
+        .query_map(params![session_id], |row| row.get(0))?
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{get_messages, save_message, save_message_from, save_summary};
+    use crate::session::{
+        add_session_tags, create_session, fork_session, get_session, set_session_pinned,
+        set_session_title,
+    };
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    async fn setup_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    async fn setup_with_conversation() -> (Database, TempDir, String) {
+        let (db, temp) = setup_db().await;
+        let id = create_session(&db, Path::new("/test/export"))
+            .await
+            .unwrap();
+        save_summary(&db, &id, "Earlier we set up the project")
+            .await
+            .unwrap();
+        save_message_from(&db, &id, Role::User, "How do I test?", None, "openai")
+            .await
+            .unwrap();
+        save_message(&db, &id, Role::Assistant, "Run cargo test", Some(7))
+            .await
+            .unwrap();
+        (db, temp, id)
+    }
+
+    mod export_session {
+        use super::*;
+
+        #[tokio::test]
+        async fn includes_all_messages() {
+            let (db, _temp, id) = setup_with_conversation().await;
+
+            let transcript = export_session(&db, &id).await.unwrap();
+
+            assert_eq!(transcript.id, id);
+            assert_eq!(transcript.working_dir, "/test/export");
+            assert_eq!(transcript.messages.len(), 3);
+            assert!(transcript.messages[0].is_summary);
+            assert_eq!(transcript.messages[1].provider.as_deref(), Some("openai"));
+            assert_eq!(transcript.messages[2].role, Role::Assistant);
+            assert_eq!(transcript.messages[2].token_count, Some(7));
+        }
+
+        #[tokio::test]
+        async fn errors_for_nonexistent() {
+            let (db, _temp) = setup_db().await;
+
+            let result = export_session(&db, "2026-01-01-0000-000-0000").await;
+
+            assert!(matches!(result, Err(StorageError::SessionNotFound { .. })));
+        }
+    }
+
+    mod export_all_sessions {
+        use super::*;
+
+        #[tokio::test]
+        async fn exports_every_session() {
+            let (db, _temp, _id) = setup_with_conversation().await;
+            create_session(&db, Path::new("/test/other")).await.unwrap();
+
+            let transcripts = export_all_sessions(&db).await.unwrap();
+
+            assert_eq!(transcripts.len(), 2);
+        }
+    }
+
+    mod import_session {
+        use super::*;
+
+        #[tokio::test]
+        async fn round_trips_into_empty_database() {
+            let (db, _temp, id) = setup_with_conversation().await;
+            let transcript = export_session(&db, &id).await.unwrap();
+
+            let (other, _other_temp) = setup_db().await;
+            let imported = import_session(&other, transcript.clone()).await.unwrap();
+
+            assert_eq!(imported.id, id);
+            assert!(!imported.was_renamed());
+            assert_eq!(imported.message_count, 3);
+            assert_eq!(export_session(&other, &id).await.unwrap(), transcript);
+        }
+
+        #[tokio::test]
+        async fn round_trips_summary_links() {
+            let (db, _temp) = setup_db().await;
+            let id = create_session(&db, Path::new("/test/export"))
+                .await
+                .unwrap();
+            let question = save_message(&db, &id, Role::User, "Old question", None)
+                .await
+                .unwrap();
+            let summary = save_summary(&db, &id, "They asked a question")
+                .await
+                .unwrap();
+            db.call(move |conn| {
+                conn.execute(
+                    "UPDATE messages SET summarized_by = ?1 WHERE id = ?2",
+                    params![summary, question],
+                )
+            })
+            .await
+            .unwrap();
+
+            let transcript = export_session(&db, &id).await.unwrap();
+            assert_eq!(transcript.messages[0].summarized_by, Some(1));
+
+            let (other, _other_temp) = setup_db().await;
+            import_session(&other, transcript.clone()).await.unwrap();
+
+            let messages = get_messages(&other, &id).await.unwrap();
+            assert_eq!(messages[0].summarized_by, Some(messages[1].id));
+            assert_eq!(export_session(&other, &id).await.unwrap(), transcript);
+        }
+
+        #[tokio::test]
+        async fn round_trips_session_metadata() {
+            let (db, _temp, parent) = setup_with_conversation().await;
+            set_session_title(&db, &parent, Some("Testing setup"))
+                .await
+                .unwrap();
+            add_session_tags(&db, &parent, &["rust".to_string(), "ci".to_string()])
+                .await
+                .unwrap();
+            set_session_pinned(&db, &parent, true).await.unwrap();
+            let fork_point = get_messages(&db, &parent).await.unwrap()[1].id;
+            let fork = fork_session(&db, &parent, Some(fork_point)).await.unwrap();
+
+            let parent_transcript = export_session(&db, &parent).await.unwrap();
+            let fork_transcript = export_session(&db, &fork).await.unwrap();
+            assert_eq!(parent_transcript.title.as_deref(), Some("Testing setup"));
+            assert_eq!(parent_transcript.tags, ["ci", "rust"]);
+            assert!(parent_transcript.pinned);
+            assert_eq!(
+                fork_transcript.parent_session_id.as_deref(),
+                Some(parent.as_str())
+            );
+            assert_eq!(fork_transcript.forked_from_message, Some(1));
+
+            let (other, _other_temp) = setup_db().await;
+            import_session(&other, parent_transcript.clone())
+                .await
+                .unwrap();
+            import_session(&other, fork_transcript.clone())
+                .await
+                .unwrap();
+
+            assert_eq!(
+                export_session(&other, &parent).await.unwrap(),
+                parent_transcript
+            );
+            assert_eq!(
+                export_session(&other, &fork).await.unwrap(),
+                fork_transcript
+            );
+            let imported_fork = get_session(&other, &fork).await.unwrap().unwrap();
+            let imported_point = get_messages(&other, &parent).await.unwrap()[1].id;
+            assert_eq!(imported_fork.forked_from_message_id, Some(imported_point));
+        }
+
+        #[tokio::test]
+        async fn drops_lineage_without_parent() {
+            let (db, _temp, parent) = setup_with_conversation().await;
+            let fork = fork_session(&db, &parent, None).await.unwrap();
+            let transcript = export_session(&db, &fork).await.unwrap();
+
+            let (other, _other_temp) = setup_db().await;
+            import_session(&other, transcript).await.unwrap();
+
+            let session = get_session(&other, &fork).await.unwrap().unwrap();
+            assert!(session.parent_session_id.is_none());
+            assert!(session.forked_from_message_id.is_none());
+        }
+
+        #[tokio::test]
+        async fn renames_on_collision() {
+            let (db, _temp, id) = setup_with_conversation().await;
+            let transcript = export_session(&db, &id).await.unwrap();
+
+            let imported = import_session(&db, transcript).await.unwrap();
+
+            assert!(imported.was_renamed());
+            assert_eq!(imported.original_id, id);
+            assert!(get_session(&db, &imported.id).await.unwrap().is_some());
+            assert_eq!(get_messages(&db, &imported.id).await.unwrap().len(), 3);
+            // The original session is untouched
+            assert_eq!(get_messages(&db, &id).await.unwrap().len(), 3);
+        }
+
+        #[tokio::test]
+        async fn replaces_invalid_id() {
+            let (db, _temp) = setup_db().await;
+            let transcript = SessionTranscript {
+                id: "../../etc/passwd".to_string(),
+                working_dir: "/test".to_string(),
+                created_at: Utc::now(),
+                last_message_at: Utc::now(),
+                title: None,
+                tags: Vec::new(),
+                pinned: false,
+                parent_session_id: None,
+                forked_from_message: None,
+                messages: Vec::new(),
+            };
+
+            let imported = import_session(&db, transcript).await.unwrap();
+
+            assert!(imported.was_renamed());
+            assert!(is_valid_session_id(&imported.id));
+        }
+
+        #[tokio::test]
+        async fn preserves_timestamps() {
+            let (db, _temp) = setup_db().await;
+            let created_at = parse_datetime("2025-06-01 10:00:00");
+            let transcript = SessionTranscript {
+                id: "2025-06-01-1000-000-abcd".to_string(),
+                working_dir: "/test".to_string(),
+                created_at,
+                last_message_at: parse_datetime("2025-06-01 10:05:00"),
+                title: None,
+                tags: Vec::new(),
+                pinned: false,
+                parent_session_id: None,
+                forked_from_message: None,
+                messages: vec![TranscriptMessage {
+                    role: Role::User,
+                    content: "hello".to_string(),
+                    is_summary: false,
+                    provider: None,
+                    token_count: None,
+                    summarized_by: None,
+                    pinned: false,
+                    created_at: parse_datetime("2025-06-01 10:05:00"),
+                }],
+            };
+
+            import_session(&db, transcript.clone()).await.unwrap();
+
+            let session = get_session(&db, &transcript.id).await.unwrap().unwrap();
+            assert_eq!(session.created_at, created_at);
+            let messages = get_messages(&db, &transcript.id).await.unwrap();
+            assert_eq!(messages[0].created_at, transcript.messages[0].created_at);
+        }
+    }
+
+    mod serde {
+        use super::*;
+
+        #[test]
+        fn optional_fields_may_be_omitted() {
+            let json = r#"{
+                "role": "assistant",
+                "content": "hi",
+                "created_at": "2026-01-01T00:00:00Z"
+            }"#;
+
+            let message: TranscriptMessage = serde_json::from_str(json).unwrap();
+
+            assert!(!message.is_summary);
+            assert!(message.provider.is_none());
+        }
+
+        #[test]
+        fn session_metadata_may_be_omitted() {
+            let json = r#"{
+                "id": "2026-01-01-0000-000-abcd",
+                "working_dir": "/test",
+                "created_at": "2026-01-01T00:00:00Z",
+                "last_message_at": "2026-01-01T00:00:00Z",
+                "messages": []
+            }"#;
+
+            let transcript: SessionTranscript = serde_json::from_str(json).unwrap();
+
+            assert!(transcript.title.is_none());
+            assert!(transcript.tags.is_empty());
+            assert!(!transcript.pinned);
+            assert!(transcript.parent_session_id.is_none());
+        }
+    }
+}
@@ -15,6 +15,25 @@ pub fn parse_datetime(s: &str) -> DateTime<Utc> {
         })
 }
 
+/// Formats a timestamp the way SQLite's `datetime()` stores it.
+pub fn format_datetime(dt: DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Escapes `%`, `_` and `\` so user input matches literally in a LIKE pattern.
+///
+/// Queries using the result must declare `ESCAPE '\'`.
+pub fn escape_like(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -31,6 +50,12 @@ mod tests {
         assert_eq!(dt.second(), 45);
     }
 
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+        assert_eq!(escape_like("plain"), "plain");
+    }
+
     #[test]
     fn returns_now_for_invalid() {
         let dt = parse_datetime("invalid");